@@ -1060,7 +1060,7 @@ export default function Home() {
       // transfer_to_user discriminator from IDL
       const transferDiscriminator = new Uint8Array([57, 90, 102, 225, 172, 136, 54, 176]);
 
-      // Args: encrypted_amount (u128)
+      // Args: encrypted_amount (u128), encrypted_memo (Option<bytes>)
       const encryptedBytes = new Uint8Array(16);
       for (let i = 0; i < 16; i++) {
         encryptedBytes[i] = Number((encryptedU128 >> BigInt(i * 8)) & BigInt(0xff));
@@ -1068,7 +1068,8 @@ export default function Home() {
 
       const transferData = Buffer.concat([
         Buffer.from(transferDiscriminator),
-        Buffer.from(encryptedBytes)
+        Buffer.from(encryptedBytes),
+        Buffer.from([0]) // encrypted_memo: None
       ]);
 
       const transferInstruction = new TransactionInstruction({
@@ -1076,7 +1077,7 @@ export default function Home() {
           { pubkey: sourceBalancePda, isSigner: false, isWritable: true },
           { pubkey: destBalancePda, isSigner: false, isWritable: true },
           { pubkey: mint, isSigner: false, isWritable: false },
-          { pubkey: solanaPublicKey, isSigner: true, isWritable: false }, // source_user
+          { pubkey: solanaPublicKey, isSigner: true, isWritable: true }, // source_user
           { pubkey: recipientPubkey, isSigner: false, isWritable: false }, // dest_user
         ],
         programId: SOLANA_CONFIG.program,
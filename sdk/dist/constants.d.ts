@@ -1,15 +0,0 @@
-import { PublicKey } from "@solana/web3.js";
-export declare const PROGRAM_IDS: {
-    INCO_TOKEN: PublicKey;
-    PRIVATE_AMM: PublicKey;
-    INCO_LIGHTNING: PublicKey;
-};
-export declare const RPC_ENDPOINTS: {
-    DEVNET: string;
-    MAINNET: string;
-};
-export declare const SEEDS: {
-    POOL: Buffer<ArrayBuffer>;
-    POSITION: Buffer<ArrayBuffer>;
-    SWAP_RESULT: Buffer<ArrayBuffer>;
-};
@@ -1,4 +0,0 @@
-export { PrivateAmmClient } from "./PrivateAmmClient";
-export { IncoEncryption } from "./IncoEncryption";
-export { PROGRAM_IDS, RPC_ENDPOINTS, SEEDS } from "./constants";
-export type { PrivateAmmConfig, Pool, SwapParams, AddLiquidityParams, } from "./PrivateAmmClient";
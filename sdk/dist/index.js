@@ -1,13 +0,0 @@
-"use strict";
-// Inco Confidential Swap SDK
-// Private AMM with fully encrypted swaps on Solana
-Object.defineProperty(exports, "__esModule", { value: true });
-exports.SEEDS = exports.RPC_ENDPOINTS = exports.PROGRAM_IDS = exports.IncoEncryption = exports.PrivateAmmClient = void 0;
-var PrivateAmmClient_1 = require("./PrivateAmmClient");
-Object.defineProperty(exports, "PrivateAmmClient", { enumerable: true, get: function () { return PrivateAmmClient_1.PrivateAmmClient; } });
-var IncoEncryption_1 = require("./IncoEncryption");
-Object.defineProperty(exports, "IncoEncryption", { enumerable: true, get: function () { return IncoEncryption_1.IncoEncryption; } });
-var constants_1 = require("./constants");
-Object.defineProperty(exports, "PROGRAM_IDS", { enumerable: true, get: function () { return constants_1.PROGRAM_IDS; } });
-Object.defineProperty(exports, "RPC_ENDPOINTS", { enumerable: true, get: function () { return constants_1.RPC_ENDPOINTS; } });
-Object.defineProperty(exports, "SEEDS", { enumerable: true, get: function () { return constants_1.SEEDS; } });
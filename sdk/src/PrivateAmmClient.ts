@@ -5,6 +5,7 @@ import {
     TransactionInstruction,
     SystemProgram,
     Keypair,
+    ComputeBudgetProgram,
 } from "@solana/web3.js";
 import { Program, AnchorProvider, Idl, BN } from "@coral-xyz/anchor";
 import { PROGRAM_IDS, SEEDS, RPC_ENDPOINTS } from "./constants";
@@ -20,19 +21,152 @@ export interface Pool {
     tokenBMint: PublicKey;
     feeBps: number;
     authority: PublicKey;
+    lpMint: PublicKey | null; // set once tokenized LP shares are enabled
 }
 
 export interface SwapParams {
     pool: PublicKey;
     encryptedAmountIn: bigint;
     encryptedMinOut: bigint;
-    direction: boolean; // true = A→B, false = B→A
+    encryptedHints: bigint[]; // curve hints checked on-chain; constant product takes the output
+    inputMint: PublicKey; // token sold
+    outputMint: PublicKey; // token bought
+    nonce: bigint; // receipt index, unique per (pool, user)
+    deadlineSlot: bigint; // rejected if executed after this slot
+    oracle?: PublicKey; // price feed, required by Oracle pools
+    tickGrid?: PublicKey; // tick grid, required by Concentrated pools
 }
 
+// Must match private_amm::PoolType
+export enum PoolType {
+    ConstantProduct = 0,
+    StableSwap = 1,
+    Oracle = 2,
+    Concentrated = 3,
+}
+
+export interface ClaimSwapOutputParams {
+    pool: PublicKey;
+    nonce: bigint;
+    outputMint: PublicKey;
+}
+
+export interface SwapRouteParams {
+    pools: PublicKey[]; // constant-product pools in route order, at most MAX_ROUTE_HOPS
+    mints: PublicKey[]; // token path, one longer than pools (e.g. cSOL, cUSDC, cETH)
+    encryptedAmountIn: bigint;
+    encryptedMinOut: bigint; // checked on the final hop only
+    encryptedHints: bigint[]; // every hop's curve hints, in route order
+    deadlineSlot: bigint;
+}
+
+export interface SubmitBatchOrderParams {
+    pool: PublicKey;
+    epoch: bigint; // current slot / BATCH_EPOCH_SLOTS
+    encryptedAmountA: bigint; // token A sold (encrypt 0 when selling B)
+    encryptedAmountB: bigint; // token B sold (encrypt 0 when selling A)
+}
+
+export interface ClaimBatchOrderParams {
+    pool: PublicKey;
+    epoch: bigint;
+    index: number; // position of the order in the batch
+}
+
+export interface PlaceLimitOrderParams {
+    pool: PublicKey;
+    inputMint: PublicKey; // token sold
+    encryptedAmountIn: bigint;
+    encryptedLimitPrice: bigint; // min output per input, times 2^LIMIT_PRICE_BITS
+    nonce: bigint; // order index, unique per (pool, user)
+}
+
+export interface OpenRangePositionParams {
+    pool: PublicKey;
+    tickLower: number; // multiple of the grid's tick spacing
+    tickUpper: number;
+    encryptedLiquidity: bigint;
+    encryptedMaxA: bigint; // most token A to deposit
+    encryptedMaxB: bigint; // most token B to deposit
+    deadlineSlot: bigint;
+}
+
+// Must match private_amm::MAX_ROUTE_HOPS
+export const MAX_ROUTE_HOPS = 2;
+
+// Must match private_amm::LIMIT_PRICE_BITS
+export const LIMIT_PRICE_BITS = 32;
+
+// Must match private_amm::BATCH_EPOCH_SLOTS
+export const BATCH_EPOCH_SLOTS = 150n;
+
+// Must match private_amm::BATCH_SETTLE_WINDOW_SLOTS
+export const BATCH_SETTLE_WINDOW_SLOTS = 1500n;
+
+// Must match private_amm::BATCH_PRICE_BITS
+export const BATCH_PRICE_BITS = 32;
+
+// Owner of every upgradeable program's data account
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+export interface QuoteParams {
+    pool: PublicKey;
+    encryptedAmountIn: bigint;
+    aToB: boolean; // true sells token A for token B
+    encryptedHints: bigint[]; // curve hints, as for swap
+    oracle?: PublicKey; // price feed, required by Oracle pools
+    tickGrid?: PublicKey; // tick grid, required by Concentrated pools
+}
+
+export interface Quote {
+    amountOut: bigint; // encrypted handle, hinted output after fee (zero if rejected)
+    fee: bigint; // encrypted handle
+}
+
+// Must match private_amm::REGISTRY_PAGE_SIZE
+export const REGISTRY_PAGE_SIZE = 32n;
+
+// Must match inco_token::MAX_BATCH_TRANSFERS (legs per batch_transfer transaction)
+export const MAX_BATCH_TRANSFERS = 7;
+
+export interface BatchTransferLeg {
+    recipient: PublicKey; // wallet whose inco_token balance PDA already exists
+    encryptedAmount: bigint;
+}
+
+/**
+ * Split transfer legs into batch_transfer-sized chunks, in order
+ */
+export const chunkBatchTransfers = (legs: BatchTransferLeg[]): BatchTransferLeg[][] => {
+    const chunks: BatchTransferLeg[][] = [];
+    for (let i = 0; i < legs.length; i += MAX_BATCH_TRANSFERS) {
+        chunks.push(legs.slice(i, i + MAX_BATCH_TRANSFERS));
+    }
+    return chunks;
+};
+
+export interface Factory {
+    admin: PublicKey;
+    defaultFeeBps: number;
+    protocolFeeOn: boolean;
+    protocolFeeShareBps: number;
+    feeRecipient: PublicKey;
+    creationFeeLamports: bigint;
+    poolCount: bigint;
+}
+
+// Offset of PrivatePool.lp_mint after the account discriminator
+const LP_MINT_OFFSET = 405;
+
 export interface AddLiquidityParams {
     pool: PublicKey;
     encryptedAmountA: bigint;
     encryptedAmountB: bigint;
+    encryptedMinLpOut: bigint; // nothing moves if fewer LP tokens would be minted
+    encryptedLpHint: bigint; // LP tokens minted, checked on-chain
+    encryptedInvariantHint: bigint; // StableSwap invariant growth, checked on-chain (0 otherwise)
+    seedPool: boolean; // first deposit into an empty pool (retry if a seed failed; StableSwap seeds are balanced)
+    deadlineSlot: bigint; // rejected if executed after this slot
 }
 
 /**
@@ -53,12 +187,22 @@ export class PrivateAmmClient {
     }
 
     /**
-     * Derive pool PDA address
+     * Order two mints canonically (ascending bytes), as the program requires
+     */
+    sortMints(mintX: PublicKey, mintY: PublicKey): [PublicKey, PublicKey] {
+        return Buffer.compare(mintX.toBuffer(), mintY.toBuffer()) < 0
+            ? [mintX, mintY]
+            : [mintY, mintX];
+    }
+
+    /**
+     * Derive pool PDA address (mints may be given in either order)
      */
     async getPoolAddress(
-        tokenAMint: PublicKey,
-        tokenBMint: PublicKey
+        mintX: PublicKey,
+        mintY: PublicKey
     ): Promise<[PublicKey, number]> {
+        const [tokenAMint, tokenBMint] = this.sortMints(mintX, mintY);
         return PublicKey.findProgramAddressSync(
             [SEEDS.POOL, tokenAMint.toBuffer(), tokenBMint.toBuffer()],
             PROGRAM_IDS.PRIVATE_AMM
@@ -79,44 +223,318 @@ export class PrivateAmmClient {
     }
 
     /**
-     * Derive swap result PDA
+     * Derive swap receipt PDA for one swap
      */
-    async getSwapResultAddress(
+    async getSwapReceiptAddress(
         pool: PublicKey,
-        user: PublicKey
+        user: PublicKey,
+        nonce: bigint
+    ): Promise<[PublicKey, number]> {
+        const nonceData = Buffer.alloc(8);
+        nonceData.writeBigUInt64LE(nonce, 0);
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.SWAP_RECEIPT, pool.toBuffer(), user.toBuffer(), nonceData],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive the batch-auction order queue PDA for one epoch
+     */
+    async getBatchAddress(
+        pool: PublicKey,
+        epoch: bigint
+    ): Promise<[PublicKey, number]> {
+        const epochData = Buffer.alloc(8);
+        epochData.writeBigUInt64LE(epoch, 0);
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.BATCH, pool.toBuffer(), epochData],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive limit order PDA
+     */
+    async getLimitOrderAddress(
+        pool: PublicKey,
+        owner: PublicKey,
+        nonce: bigint
+    ): Promise<[PublicKey, number]> {
+        const nonceData = Buffer.alloc(8);
+        nonceData.writeBigUInt64LE(nonce, 0);
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.LIMIT_ORDER, pool.toBuffer(), owner.toBuffer(), nonceData],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive the factory config PDA
+     */
+    async getFactoryAddress(): Promise<[PublicKey, number]> {
+        return PublicKey.findProgramAddressSync([SEEDS.FACTORY], PROGRAM_IDS.PRIVATE_AMM);
+    }
+
+    /**
+     * Derive a pool registry page PDA
+     */
+    async getRegistryPageAddress(page: bigint): Promise<[PublicKey, number]> {
+        const pageData = Buffer.alloc(8);
+        pageData.writeBigUInt64LE(page, 0);
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.REGISTRY, pageData],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive the liquidity-mining rewards PDA of a pool
+     */
+    async getRewardsAddress(pool: PublicKey): Promise<[PublicKey, number]> {
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.REWARDS, pool.toBuffer()],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive the tick grid PDA of a concentrated pool
+     */
+    async getTickGridAddress(pool: PublicKey): Promise<[PublicKey, number]> {
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.TICK_GRID, pool.toBuffer()],
+            PROGRAM_IDS.PRIVATE_AMM
+        );
+    }
+
+    /**
+     * Derive range position PDA
+     */
+    async getRangePositionAddress(
+        pool: PublicKey,
+        owner: PublicKey,
+        tickLower: number,
+        tickUpper: number
     ): Promise<[PublicKey, number]> {
+        const lowerData = Buffer.alloc(4);
+        const upperData = Buffer.alloc(4);
+        lowerData.writeInt32LE(tickLower, 0);
+        upperData.writeInt32LE(tickUpper, 0);
         return PublicKey.findProgramAddressSync(
-            [SEEDS.SWAP_RESULT, pool.toBuffer(), user.toBuffer()],
+            [SEEDS.RANGE_POSITION, pool.toBuffer(), owner.toBuffer(), lowerData, upperData],
             PROGRAM_IDS.PRIVATE_AMM
         );
     }
 
+    /**
+     * Derive an inco_token balance PDA (pool vaults use the pool as owner)
+     */
+    async getBalanceAddress(
+        owner: PublicKey,
+        mint: PublicKey
+    ): Promise<[PublicKey, number]> {
+        return PublicKey.findProgramAddressSync(
+            [SEEDS.USER_BALANCE, owner.toBuffer(), mint.toBuffer()],
+            PROGRAM_IDS.INCO_TOKEN
+        );
+    }
+
+    /**
+     * Mints, user balances and pool vaults moved by liquidity and swap instructions
+     */
+    private async getCustodyKeys(poolAddress: PublicKey) {
+        const pool = await this.getPool(poolAddress);
+        if (!pool) throw new Error(`Pool not found: ${poolAddress.toString()}`);
+
+        const [userBalanceA] = await this.getBalanceAddress(this.wallet.publicKey, pool.tokenAMint);
+        const [userBalanceB] = await this.getBalanceAddress(this.wallet.publicKey, pool.tokenBMint);
+        const [vaultA] = await this.getBalanceAddress(poolAddress, pool.tokenAMint);
+        const [vaultB] = await this.getBalanceAddress(poolAddress, pool.tokenBMint);
+
+        return [
+            { pubkey: pool.tokenAMint, isSigner: false, isWritable: false },
+            { pubkey: pool.tokenBMint, isSigner: false, isWritable: false },
+            { pubkey: userBalanceA, isSigner: false, isWritable: true },
+            { pubkey: userBalanceB, isSigner: false, isWritable: true },
+            { pubkey: vaultA, isSigner: false, isWritable: true },
+            { pubkey: vaultB, isSigner: false, isWritable: true },
+        ];
+    }
+
+    /**
+     * Rewards account meta for LP instructions; Anchor reads the program ID as "no rewards"
+     */
+    private async getRewardsKey(pool: PublicKey) {
+        const [rewards] = await this.getRewardsAddress(pool);
+        const exists = (await this.connection.getAccountInfo(rewards)) !== null;
+        return exists
+            ? { pubkey: rewards, isSigner: false, isWritable: true }
+            : { pubkey: PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: false };
+    }
+
+    /**
+     * Create the factory config (once); the wallet becomes its admin
+     * The wallet must be the program's upgrade authority
+     */
+    async initializeFactory(defaultFeeBps: number, creationFeeLamports: bigint): Promise<string> {
+        const [factory] = await this.getFactoryAddress();
+        const [programData] = PublicKey.findProgramAddressSync(
+            [PROGRAM_IDS.PRIVATE_AMM.toBuffer()],
+            BPF_LOADER_UPGRADEABLE_ID
+        );
+
+        const keys = [
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: false },
+            { pubkey: programData, isSigner: false, isWritable: false },
+            { pubkey: factory, isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for initialize_factory
+        const discriminator = Buffer.from([179, 64, 75, 250, 39, 254, 240, 178]);
+        const feeData = Buffer.alloc(2);
+        const creationFeeData = Buffer.alloc(8);
+        feeData.writeUInt16LE(defaultFeeBps, 0);
+        creationFeeData.writeBigUInt64LE(creationFeeLamports, 0);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, feeData, creationFeeData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Factory initialized: ${factory.toString()}`);
+        return signature;
+    }
+
+    /**
+     * Update the factory defaults (factory admin only)
+     */
+    async updateFactory(params: Omit<Factory, "admin" | "poolCount">): Promise<string> {
+        const [factory] = await this.getFactoryAddress();
+
+        const keys = [
+            { pubkey: factory, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: false },
+        ];
+
+        // Discriminator for update_factory
+        const discriminator = Buffer.from([183, 55, 14, 110, 32, 166, 162, 88]);
+        const feeData = Buffer.alloc(2);
+        const shareData = Buffer.alloc(2);
+        const creationFeeData = Buffer.alloc(8);
+        feeData.writeUInt16LE(params.defaultFeeBps, 0);
+        shareData.writeUInt16LE(params.protocolFeeShareBps, 0);
+        creationFeeData.writeBigUInt64LE(params.creationFeeLamports, 0);
+
+        const data = Buffer.concat([
+            discriminator,
+            feeData,
+            Buffer.from([params.protocolFeeOn ? 1 : 0]),
+            shareData,
+            params.feeRecipient.toBuffer(),
+            creationFeeData,
+        ]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Factory updated`);
+        return signature;
+    }
+
+    /**
+     * Hand the factory to a new admin (factory admin only)
+     */
+    async setFactoryAdmin(newAdmin: PublicKey): Promise<string> {
+        const [factory] = await this.getFactoryAddress();
+
+        const keys = [
+            { pubkey: factory, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: false },
+        ];
+
+        // Discriminator for set_factory_admin
+        const discriminator = Buffer.from([5, 103, 187, 62, 33, 38, 103, 225]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, newAdmin.toBuffer()]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Factory admin set to ${newAdmin.toString()}`);
+        return signature;
+    }
+
     /**
      * Initialize a new private liquidity pool
      */
     async initializePool(
-        tokenAMint: PublicKey,
-        tokenBMint: PublicKey,
-        feeBps: number
+        mintX: PublicKey,
+        mintY: PublicKey,
+        feeBps: number | null, // null for the factory default
+        poolType: PoolType = PoolType.ConstantProduct,
+        amp: bigint = 0n // StableSwap amplification
     ): Promise<string> {
+        const [tokenAMint, tokenBMint] = this.sortMints(mintX, mintY);
         const [poolAddress] = await this.getPoolAddress(tokenAMint, tokenBMint);
+        const [vaultA] = await this.getBalanceAddress(poolAddress, tokenAMint);
+        const [vaultB] = await this.getBalanceAddress(poolAddress, tokenBMint);
+
+        const factory = await this.getFactory();
+        if (!factory) throw new Error("Factory not initialized");
+        const [factoryAddress] = await this.getFactoryAddress();
+        const [registryPage] = await this.getRegistryPageAddress(
+            factory.poolCount / REGISTRY_PAGE_SIZE
+        );
 
         // Build initialize_pool instruction
         const keys = [
             { pubkey: poolAddress, isSigner: false, isWritable: true },
+            { pubkey: factoryAddress, isSigner: false, isWritable: true },
+            { pubkey: registryPage, isSigner: false, isWritable: true },
+            { pubkey: factory.admin, isSigner: false, isWritable: true },
             { pubkey: tokenAMint, isSigner: false, isWritable: false },
             { pubkey: tokenBMint, isSigner: false, isWritable: false },
+            { pubkey: vaultA, isSigner: false, isWritable: true },
+            { pubkey: vaultB, isSigner: false, isWritable: true },
             { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
             { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
         ];
 
         // Discriminator for initialize_pool (first 8 bytes of sha256("global:initialize_pool"))
         const discriminator = Buffer.from([95, 180, 10, 172, 84, 174, 232, 40]);
-        const feeData = Buffer.alloc(2);
-        feeData.writeUInt16LE(feeBps, 0);
+        // Option<u16>: 0 for None, 1 followed by the value for Some
+        const feeData = Buffer.alloc(feeBps === null ? 1 : 3);
+        if (feeBps !== null) {
+            feeData.writeUInt8(1, 0);
+            feeData.writeUInt16LE(feeBps, 1);
+        }
+        const poolTypeData = Buffer.from([poolType]);
+        const ampData = Buffer.alloc(8);
+        ampData.writeBigUInt64LE(amp, 0);
 
-        const data = Buffer.concat([discriminator, feeData]);
+        const data = Buffer.concat([discriminator, feeData, poolTypeData, ampData]);
 
         const ix = new TransactionInstruction({
             keys,
@@ -144,8 +562,11 @@ export class PrivateAmmClient {
         const keys = [
             { pubkey: params.pool, isSigner: false, isWritable: true },
             { pubkey: userPosition, isSigner: false, isWritable: true },
+            await this.getRewardsKey(params.pool),
+            ...(await this.getCustodyKeys(params.pool)),
             { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
             { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
         ];
 
@@ -153,12 +574,30 @@ export class PrivateAmmClient {
         const discriminator = Buffer.from([181, 157, 89, 67, 143, 182, 52, 72]);
         const amountAData = Buffer.alloc(16);
         const amountBData = Buffer.alloc(16);
+        const minLpOutData = Buffer.alloc(16);
+        const lpHintData = Buffer.alloc(16);
+        const invariantHintData = Buffer.alloc(16);
 
         // Write BigInts as little-endian 128-bit
         this.writeBigInt128LE(amountAData, params.encryptedAmountA);
         this.writeBigInt128LE(amountBData, params.encryptedAmountB);
+        this.writeBigInt128LE(minLpOutData, params.encryptedMinLpOut);
+        this.writeBigInt128LE(lpHintData, params.encryptedLpHint);
+        this.writeBigInt128LE(invariantHintData, params.encryptedInvariantHint);
 
-        const data = Buffer.concat([discriminator, amountAData, amountBData]);
+        const deadlineData = Buffer.alloc(8);
+        deadlineData.writeBigUInt64LE(params.deadlineSlot, 0);
+
+        const data = Buffer.concat([
+            discriminator,
+            amountAData,
+            amountBData,
+            minLpOutData,
+            lpHintData,
+            invariantHintData,
+            Buffer.from([params.seedPool ? 1 : 0]),
+            deadlineData,
+        ]);
 
         const ix = new TransactionInstruction({
             keys,
@@ -174,21 +613,70 @@ export class PrivateAmmClient {
         return signature;
     }
 
+    /**
+     * Store a StableSwap pool's invariant for its current balances (anyone may call this)
+     * Compounds banked LP fees and moves swaps to the current (ramped) amplification;
+     * swaps work without it from the seed on. The hints are [p1, p2, q1, q2]
+     */
+    async syncInvariant(
+        pool: PublicKey,
+        encryptedInvariant: bigint,
+        encryptedHints: bigint[]
+    ): Promise<string> {
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for sync_invariant
+        const discriminator = Buffer.from([153, 59, 7, 15, 155, 209, 32, 41]);
+        const invariantData = Buffer.alloc(16);
+        this.writeBigInt128LE(invariantData, encryptedInvariant);
+        const data = Buffer.concat([discriminator, invariantData, this.encodeHandles(encryptedHints)]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Invariant synced (encrypted)`);
+        return signature;
+    }
+
     /**
      * Execute a private swap
      * All amounts are encrypted - no one can see trade size!
+     * The output is held on a receipt until claimSwapOutput is called
      */
     async swap(params: SwapParams): Promise<string> {
-        const [swapResult] = await this.getSwapResultAddress(
+        const [swapReceipt] = await this.getSwapReceiptAddress(
             params.pool,
-            this.wallet.publicKey
+            this.wallet.publicKey,
+            params.nonce
         );
 
+        const [userBalanceIn] = await this.getBalanceAddress(this.wallet.publicKey, params.inputMint);
+        const [vaultIn] = await this.getBalanceAddress(params.pool, params.inputMint);
+
         const keys = [
             { pubkey: params.pool, isSigner: false, isWritable: true },
-            { pubkey: swapResult, isSigner: false, isWritable: true },
+            { pubkey: swapReceipt, isSigner: false, isWritable: true },
+            { pubkey: params.inputMint, isSigner: false, isWritable: false },
+            { pubkey: params.outputMint, isSigner: false, isWritable: false },
+            { pubkey: userBalanceIn, isSigner: false, isWritable: true },
+            { pubkey: vaultIn, isSigner: false, isWritable: true },
+            // Anchor reads the program ID as "no oracle"
+            { pubkey: params.oracle ?? PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: false },
+            { pubkey: params.tickGrid ?? PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: !!params.tickGrid },
             { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
             { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
         ];
 
@@ -196,17 +684,21 @@ export class PrivateAmmClient {
         const discriminator = Buffer.from([248, 198, 158, 145, 225, 117, 135, 200]);
         const amountInData = Buffer.alloc(16);
         const minOutData = Buffer.alloc(16);
-        const directionData = Buffer.alloc(1);
+        const nonceData = Buffer.alloc(8);
+        const deadlineData = Buffer.alloc(8);
 
         this.writeBigInt128LE(amountInData, params.encryptedAmountIn);
         this.writeBigInt128LE(minOutData, params.encryptedMinOut);
-        directionData.writeUInt8(params.direction ? 1 : 0, 0);
+        nonceData.writeBigUInt64LE(params.nonce, 0);
+        deadlineData.writeBigUInt64LE(params.deadlineSlot, 0);
 
         const data = Buffer.concat([
             discriminator,
             amountInData,
             minOutData,
-            directionData,
+            this.encodeHandles(params.encryptedHints),
+            nonceData,
+            deadlineData,
         ]);
 
         const ix = new TransactionInstruction({
@@ -224,30 +716,1137 @@ export class PrivateAmmClient {
     }
 
     /**
-     * Fetch pool info
+     * Swap along a route of pools in one transaction
+     * Intermediate amounts move vault to vault and are never recorded
      */
-    async getPool(poolAddress: PublicKey): Promise<Pool | null> {
-        const accountInfo = await this.connection.getAccountInfo(poolAddress);
-        if (!accountInfo) return null;
+    async swapRoute(params: SwapRouteParams): Promise<string> {
+        if (params.mints.length !== params.pools.length + 1) {
+            throw new Error("Route needs exactly one more mint than pools");
+        }
+        if (params.pools.length > MAX_ROUTE_HOPS) {
+            throw new Error(`Routes are limited to ${MAX_ROUTE_HOPS} pools`);
+        }
+        const inputMint = params.mints[0];
+        const outputMint = params.mints[params.mints.length - 1];
+        const [userBalanceIn] = await this.getBalanceAddress(this.wallet.publicKey, inputMint);
+        const [userBalanceOut] = await this.getBalanceAddress(this.wallet.publicKey, outputMint);
 
-        // Parse pool data (skip 8-byte discriminator)
-        const data = accountInfo.data.slice(8);
+        const keys = [
+            { pubkey: inputMint, isSigner: false, isWritable: false },
+            { pubkey: outputMint, isSigner: false, isWritable: false },
+            { pubkey: userBalanceIn, isSigner: false, isWritable: true },
+            { pubkey: userBalanceOut, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
 
-        return {
-            address: poolAddress,
-            tokenAMint: new PublicKey(data.slice(0, 32)),
-            tokenBMint: new PublicKey(data.slice(32, 64)),
-            // Encrypted reserves are not readable - only Inco can decrypt
-            feeBps: data.readUInt16LE(64 + 64), // After 4 Euint128 fields
-            authority: new PublicKey(data.slice(64 + 64 + 2, 64 + 64 + 2 + 32)),
-        };
-    }
+        // Remaining accounts per hop: pool, output mint, input vault, output vault
+        for (let i = 0; i < params.pools.length; i++) {
+            const pool = params.pools[i];
+            const [vaultIn] = await this.getBalanceAddress(pool, params.mints[i]);
+            const [vaultOut] = await this.getBalanceAddress(pool, params.mints[i + 1]);
+            keys.push(
+                { pubkey: pool, isSigner: false, isWritable: true },
+                { pubkey: params.mints[i + 1], isSigner: false, isWritable: false },
+                { pubkey: vaultIn, isSigner: false, isWritable: true },
+                { pubkey: vaultOut, isSigner: false, isWritable: true }
+            );
+        }
 
-    // Helper: Write BigInt as 128-bit little-endian
-    private writeBigInt128LE(buffer: Buffer, value: bigint): void {
-        for (let i = 0; i < 16; i++) {
-            buffer[i] = Number((value >> BigInt(i * 8)) & BigInt(0xff));
+        // Discriminator for swap_route
+        const discriminator = Buffer.from([86, 183, 163, 144, 0, 50, 173, 28]);
+        const amountInData = Buffer.alloc(16);
+        const minOutData = Buffer.alloc(16);
+        const deadlineData = Buffer.alloc(8);
+
+        this.writeBigInt128LE(amountInData, params.encryptedAmountIn);
+        this.writeBigInt128LE(minOutData, params.encryptedMinOut);
+        deadlineData.writeBigUInt64LE(params.deadlineSlot, 0);
+
+        const data = Buffer.concat([
+            discriminator,
+            amountInData,
+            minOutData,
+            this.encodeHandles(params.encryptedHints),
+            deadlineData,
+        ]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Private route swap executed across ${params.pools.length} pools`);
+        return signature;
+    }
+
+    /**
+     * Pay out a swap's output and close its receipt
+     */
+    async claimSwapOutput(params: ClaimSwapOutputParams): Promise<string> {
+        const [swapReceipt] = await this.getSwapReceiptAddress(
+            params.pool,
+            this.wallet.publicKey,
+            params.nonce
+        );
+        const [userBalanceOut] = await this.getBalanceAddress(this.wallet.publicKey, params.outputMint);
+        const [vaultOut] = await this.getBalanceAddress(params.pool, params.outputMint);
+
+        const keys = [
+            { pubkey: params.pool, isSigner: false, isWritable: true },
+            { pubkey: swapReceipt, isSigner: false, isWritable: true },
+            { pubkey: params.outputMint, isSigner: false, isWritable: false },
+            { pubkey: userBalanceOut, isSigner: false, isWritable: true },
+            { pubkey: vaultOut, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for claim_swap_output
+        const discriminator = Buffer.from([189, 36, 100, 140, 18, 48, 128, 14]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: discriminator,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Swap output claimed`);
+        return signature;
+    }
+
+    /**
+     * Queue an encrypted order in the current batch-auction epoch
+     * Both legs are deposited, so the direction stays hidden
+     */
+    async submitBatchOrder(params: SubmitBatchOrderParams): Promise<string> {
+        const [batch] = await this.getBatchAddress(params.pool, params.epoch);
+
+        const keys = [
+            { pubkey: params.pool, isSigner: false, isWritable: false },
+            { pubkey: batch, isSigner: false, isWritable: true },
+            ...(await this.getCustodyKeys(params.pool)),
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for submit_batch_order
+        const discriminator = Buffer.from([35, 173, 49, 40, 36, 49, 158, 224]);
+        const epochData = Buffer.alloc(8);
+        const amountAData = Buffer.alloc(16);
+        const amountBData = Buffer.alloc(16);
+
+        epochData.writeBigUInt64LE(params.epoch, 0);
+        this.writeBigInt128LE(amountAData, params.encryptedAmountA);
+        this.writeBigInt128LE(amountBData, params.encryptedAmountB);
+
+        const data = Buffer.concat([discriminator, epochData, amountAData, amountBData]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Batch order queued for epoch ${params.epoch}`);
+        return signature;
+    }
+
+    /**
+     * Settle an ended batch epoch (anyone may settle)
+     *
+     * The prices are encrypted hints, each BATCH_PRICE_BITS fixed point and
+     * rounded down, over the depths d = reserve + batch total of each token:
+     * floor(dB * 2^32 / dA) and floor(dA * 2^32 / dB). Wrong hints settle
+     * nothing and every order claims its deposits back, so the wallet
+     * sending them needs no special role
+     */
+    async settleBatch(
+        pool: PublicKey,
+        epoch: bigint,
+        encryptedPriceAInB: bigint,
+        encryptedPriceBInA: bigint
+    ): Promise<string> {
+        const [batch] = await this.getBatchAddress(pool, epoch);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: batch, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for settle_batch
+        const discriminator = Buffer.from([22, 2, 21, 223, 225, 122, 163, 214]);
+        const priceAInBData = Buffer.alloc(16);
+        const priceBInAData = Buffer.alloc(16);
+
+        this.writeBigInt128LE(priceAInBData, encryptedPriceAInB);
+        this.writeBigInt128LE(priceBInAData, encryptedPriceBInA);
+
+        const data = Buffer.concat([discriminator, priceAInBData, priceBInAData]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Batch epoch ${epoch} settled`);
+        return signature;
+    }
+
+    /**
+     * Pay out a settled batch order
+     */
+    async claimBatchOrder(params: ClaimBatchOrderParams): Promise<string> {
+        // Discriminator for claim_batch_order
+        const discriminator = Buffer.from([16, 63, 16, 17, 34, 215, 153, 222]);
+        const signature = await this.sendBatchOrderPayout(params, discriminator);
+
+        console.log(`Batch order ${params.index} claimed`);
+        return signature;
+    }
+
+    /**
+     * Take back a batch order's deposits once its epoch missed the settlement window
+     */
+    async refundBatchOrder(params: ClaimBatchOrderParams): Promise<string> {
+        // Discriminator for refund_batch_order
+        const discriminator = Buffer.from([31, 63, 125, 131, 137, 121, 255, 189]);
+        const signature = await this.sendBatchOrderPayout(params, discriminator);
+
+        console.log(`Batch order ${params.index} refunded`);
+        return signature;
+    }
+
+    // claim_batch_order and refund_batch_order share their accounts and data
+    private async sendBatchOrderPayout(params: ClaimBatchOrderParams, discriminator: Buffer): Promise<string> {
+        const [batch] = await this.getBatchAddress(params.pool, params.epoch);
+
+        const keys = [
+            { pubkey: params.pool, isSigner: false, isWritable: true },
+            { pubkey: batch, isSigner: false, isWritable: true },
+            ...(await this.getCustodyKeys(params.pool)),
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        const data = Buffer.concat([discriminator, Buffer.from([params.index])]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+        return signature;
+    }
+
+    /**
+     * Rest an encrypted limit order; the input is escrowed in the pool
+     */
+    async placeLimitOrder(params: PlaceLimitOrderParams): Promise<string> {
+        const [limitOrder] = await this.getLimitOrderAddress(
+            params.pool,
+            this.wallet.publicKey,
+            params.nonce
+        );
+        const [userBalanceIn] = await this.getBalanceAddress(this.wallet.publicKey, params.inputMint);
+        const [vaultIn] = await this.getBalanceAddress(params.pool, params.inputMint);
+
+        const keys = [
+            { pubkey: params.pool, isSigner: false, isWritable: false },
+            { pubkey: limitOrder, isSigner: false, isWritable: true },
+            { pubkey: params.inputMint, isSigner: false, isWritable: false },
+            { pubkey: userBalanceIn, isSigner: false, isWritable: true },
+            { pubkey: vaultIn, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for place_limit_order
+        const discriminator = Buffer.from([108, 176, 33, 186, 146, 229, 1, 197]);
+        const amountInData = Buffer.alloc(16);
+        const limitPriceData = Buffer.alloc(16);
+        const nonceData = Buffer.alloc(8);
+
+        this.writeBigInt128LE(amountInData, params.encryptedAmountIn);
+        this.writeBigInt128LE(limitPriceData, params.encryptedLimitPrice);
+        nonceData.writeBigUInt64LE(params.nonce, 0);
+
+        const data = Buffer.concat([discriminator, amountInData, limitPriceData, nonceData]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Limit order placed (size and price hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Try to fill a limit order against the pool
+     * `encryptedHints` are the curve hints for the order's full size, as for swap,
+     * except that a constant-product hint must be the exact output. Computing it
+     * takes the decrypted order and reserves, so this is for the owner or a
+     * keeper the owner has granted allowances; other fills move zero.
+     * Pass the pool's price feed for Oracle pools
+     */
+    async tryFill(
+        pool: PublicKey,
+        owner: PublicKey,
+        nonce: bigint,
+        encryptedHints: bigint[],
+        oracle?: PublicKey
+    ): Promise<string> {
+        const [limitOrder] = await this.getLimitOrderAddress(pool, owner, nonce);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: limitOrder, isSigner: false, isWritable: true },
+            // Anchor reads the program ID as "no oracle"
+            { pubkey: oracle ?? PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: false },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for try_fill
+        const discriminator = Buffer.from([196, 149, 247, 75, 29, 227, 164, 242]);
+        const data = Buffer.concat([discriminator, this.encodeHandles(encryptedHints)]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Fill attempted (outcome encrypted)`);
+        return signature;
+    }
+
+    /**
+     * Close your limit order, returning the escrow and any filled output
+     */
+    async cancelOrder(pool: PublicKey, nonce: bigint): Promise<string> {
+        const [limitOrder] = await this.getLimitOrderAddress(pool, this.wallet.publicKey, nonce);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: limitOrder, isSigner: false, isWritable: true },
+            ...(await this.getCustodyKeys(pool)),
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for cancel_order
+        const discriminator = Buffer.from([95, 129, 237, 240, 8, 49, 223, 132]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: discriminator,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Limit order closed`);
+        return signature;
+    }
+
+    /**
+     * Move part of your LP position to another user's position
+     */
+    async transferPosition(
+        pool: PublicKey,
+        recipient: PublicKey,
+        encryptedAmount: bigint
+    ): Promise<string> {
+        const [userPosition] = await this.getUserPositionAddress(pool, this.wallet.publicKey);
+        const [recipientPosition] = await this.getUserPositionAddress(pool, recipient);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: userPosition, isSigner: false, isWritable: true },
+            { pubkey: recipientPosition, isSigner: false, isWritable: true },
+            await this.getRewardsKey(pool),
+            { pubkey: recipient, isSigner: false, isWritable: false },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for transfer_position
+        const discriminator = Buffer.from([139, 130, 102, 147, 135, 77, 113, 222]);
+        const amountData = Buffer.alloc(16);
+        this.writeBigInt128LE(amountData, encryptedAmount);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, amountData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`LP position transferred (amount hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Register the LP cToken mint (pool authority only)
+     * Create it with inco_token initialize_mint, using the pool PDA as mint authority
+     */
+    async setLpMint(pool: PublicKey, lpMint: PublicKey): Promise<string> {
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: lpMint, isSigner: false, isWritable: false },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: false },
+        ];
+
+        // Discriminator for set_lp_mint
+        const discriminator = Buffer.from([189, 11, 158, 122, 176, 207, 231, 132]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: discriminator,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`LP mint set: ${lpMint.toString()}`);
+        return signature;
+    }
+
+    /**
+     * Mint part of your LP position as LP cTokens into your inco_token balance
+     * The balance must already exist (initialize_user_balance for the LP mint)
+     */
+    async tokenizePosition(pool: PublicKey, encryptedAmount: bigint): Promise<string> {
+        const poolInfo = await this.getPool(pool);
+        if (!poolInfo?.lpMint) throw new Error(`Pool has no LP mint: ${pool.toString()}`);
+        const [userPosition] = await this.getUserPositionAddress(pool, this.wallet.publicKey);
+        const [userLpBalance] = await this.getBalanceAddress(this.wallet.publicKey, poolInfo.lpMint);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: userPosition, isSigner: false, isWritable: true },
+            await this.getRewardsKey(pool),
+            { pubkey: poolInfo.lpMint, isSigner: false, isWritable: true },
+            { pubkey: userLpBalance, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for tokenize_position
+        const discriminator = Buffer.from([167, 82, 237, 13, 157, 135, 59, 138]);
+        const amountData = Buffer.alloc(16);
+        this.writeBigInt128LE(amountData, encryptedAmount);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, amountData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`LP position tokenized (amount hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Burn LP cTokens from your balance back into your LP position
+     */
+    async redeemPositionTokens(pool: PublicKey, encryptedAmount: bigint): Promise<string> {
+        const poolInfo = await this.getPool(pool);
+        if (!poolInfo?.lpMint) throw new Error(`Pool has no LP mint: ${pool.toString()}`);
+        const [userPosition] = await this.getUserPositionAddress(pool, this.wallet.publicKey);
+        const [userLpBalance] = await this.getBalanceAddress(this.wallet.publicKey, poolInfo.lpMint);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: userPosition, isSigner: false, isWritable: true },
+            await this.getRewardsKey(pool),
+            { pubkey: poolInfo.lpMint, isSigner: false, isWritable: true },
+            { pubkey: userLpBalance, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for redeem_position_tokens
+        const discriminator = Buffer.from([72, 5, 207, 72, 39, 86, 168, 7]);
+        const amountData = Buffer.alloc(16);
+        this.writeBigInt128LE(amountData, encryptedAmount);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, amountData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`LP tokens redeemed (amount hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Start liquidity-mining rewards for a pool (pool authority only)
+     * emissionRate reward tokens per second are shared among LPs until endTime
+     */
+    async initializeRewards(
+        pool: PublicKey,
+        rewardMint: PublicKey,
+        emissionRate: bigint,
+        endTime: bigint
+    ): Promise<string> {
+        const [rewards] = await this.getRewardsAddress(pool);
+        const [rewardVault] = await this.getBalanceAddress(rewards, rewardMint);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: rewards, isSigner: false, isWritable: true },
+            { pubkey: rewardMint, isSigner: false, isWritable: false },
+            { pubkey: rewardVault, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for initialize_rewards
+        const discriminator = Buffer.from([91, 174, 112, 191, 233, 236, 147, 12]);
+        const rateData = Buffer.alloc(8);
+        const endData = Buffer.alloc(8);
+        rateData.writeBigUInt64LE(emissionRate, 0);
+        endData.writeBigInt64LE(endTime, 0);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, rateData, endData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Rewards started: ${emissionRate} per second`);
+        return signature;
+    }
+
+    /**
+     * Deposit reward tokens into the pool's reward vault (pool authority only)
+     */
+    async fundRewards(
+        pool: PublicKey,
+        rewardMint: PublicKey,
+        encryptedAmount: bigint
+    ): Promise<string> {
+        const [rewards] = await this.getRewardsAddress(pool);
+        const [funderBalance] = await this.getBalanceAddress(this.wallet.publicKey, rewardMint);
+        const [rewardVault] = await this.getBalanceAddress(rewards, rewardMint);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: rewards, isSigner: false, isWritable: false },
+            { pubkey: rewardMint, isSigner: false, isWritable: false },
+            { pubkey: funderBalance, isSigner: false, isWritable: true },
+            { pubkey: rewardVault, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for fund_rewards
+        const discriminator = Buffer.from([114, 64, 163, 112, 175, 167, 19, 121]);
+        const amountData = Buffer.alloc(16);
+        this.writeBigInt128LE(amountData, encryptedAmount);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, amountData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Reward vault funded (amount hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Change a pool's reward emission rate and end time (pool authority only)
+     */
+    async setRewardEmission(
+        pool: PublicKey,
+        emissionRate: bigint,
+        endTime: bigint
+    ): Promise<string> {
+        const [rewards] = await this.getRewardsAddress(pool);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: rewards, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for set_reward_emission
+        const discriminator = Buffer.from([43, 97, 246, 165, 236, 217, 184, 193]);
+        const rateData = Buffer.alloc(8);
+        const endData = Buffer.alloc(8);
+        rateData.writeBigUInt64LE(emissionRate, 0);
+        endData.writeBigInt64LE(endTime, 0);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, rateData, endData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Reward emission set to ${emissionRate} per second`);
+        return signature;
+    }
+
+    /**
+     * Point a pool's reward accumulator at its current staked LP supply (anyone may sync)
+     *
+     * The hint is floor(2^73 / (lpSupply - tokenizedLpSupply)), computed from the
+     * decrypted supplies. Run it after the staked supply changes: emissions are
+     * held until it does
+     */
+    async syncRewardSupply(pool: PublicKey, encryptedInverseSupply: bigint): Promise<string> {
+        const [rewards] = await this.getRewardsAddress(pool);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: rewards, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for sync_reward_supply
+        const discriminator = Buffer.from([8, 128, 75, 4, 55, 251, 89, 129]);
+        const inverseData = Buffer.alloc(16);
+        this.writeBigInt128LE(inverseData, encryptedInverseSupply);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, inverseData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Reward LP supply synced`);
+        return signature;
+    }
+
+    /**
+     * Claim your accrued liquidity-mining rewards into your balance
+     */
+    async claimRewards(pool: PublicKey, rewardMint: PublicKey): Promise<string> {
+        const [rewards] = await this.getRewardsAddress(pool);
+        const [userPosition] = await this.getUserPositionAddress(pool, this.wallet.publicKey);
+        const [userRewardBalance] = await this.getBalanceAddress(this.wallet.publicKey, rewardMint);
+        const [rewardVault] = await this.getBalanceAddress(rewards, rewardMint);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: rewards, isSigner: false, isWritable: true },
+            { pubkey: userPosition, isSigner: false, isWritable: true },
+            { pubkey: rewardMint, isSigner: false, isWritable: false },
+            { pubkey: userRewardBalance, isSigner: false, isWritable: true },
+            { pubkey: rewardVault, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for claim_rewards
+        const discriminator = Buffer.from([4, 144, 132, 71, 116, 23, 151, 80]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: discriminator,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Rewards claimed (amount hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Create the tick grid of a concentrated pool (pool authority only)
+     */
+    async initializeTickGrid(
+        pool: PublicKey,
+        tickSpacing: number,
+        initialTick: number
+    ): Promise<string> {
+        const [tickGrid] = await this.getTickGridAddress(pool);
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: false },
+            { pubkey: tickGrid, isSigner: false, isWritable: true },
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for initialize_tick_grid
+        const discriminator = Buffer.from([188, 35, 34, 175, 60, 146, 118, 167]);
+        const spacingData = Buffer.alloc(2);
+        const tickData = Buffer.alloc(4);
+        spacingData.writeUInt16LE(tickSpacing, 0);
+        tickData.writeInt32LE(initialTick, 0);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data: Buffer.concat([discriminator, spacingData, tickData]),
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Tick grid created (spacing ${tickSpacing})`);
+        return signature;
+    }
+
+    /**
+     * Open or add to a concentrated-liquidity position between two ticks
+     */
+    async openRangePosition(params: OpenRangePositionParams): Promise<string> {
+        const [tickGrid] = await this.getTickGridAddress(params.pool);
+        const [rangePosition] = await this.getRangePositionAddress(
+            params.pool,
+            this.wallet.publicKey,
+            params.tickLower,
+            params.tickUpper
+        );
+
+        const keys = [
+            { pubkey: params.pool, isSigner: false, isWritable: true },
+            { pubkey: tickGrid, isSigner: false, isWritable: true },
+            { pubkey: rangePosition, isSigner: false, isWritable: true },
+            ...(await this.getCustodyKeys(params.pool)),
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        // Discriminator for open_range_position
+        const discriminator = Buffer.from([228, 76, 37, 186, 144, 21, 247, 208]);
+        const lowerData = Buffer.alloc(4);
+        const upperData = Buffer.alloc(4);
+        const liquidityData = Buffer.alloc(16);
+        const maxAData = Buffer.alloc(16);
+        const maxBData = Buffer.alloc(16);
+        const deadlineData = Buffer.alloc(8);
+
+        lowerData.writeInt32LE(params.tickLower, 0);
+        upperData.writeInt32LE(params.tickUpper, 0);
+        this.writeBigInt128LE(liquidityData, params.encryptedLiquidity);
+        this.writeBigInt128LE(maxAData, params.encryptedMaxA);
+        this.writeBigInt128LE(maxBData, params.encryptedMaxB);
+        deadlineData.writeBigUInt64LE(params.deadlineSlot, 0);
+
+        const data = Buffer.concat([
+            discriminator,
+            lowerData,
+            upperData,
+            liquidityData,
+            maxAData,
+            maxBData,
+            deadlineData,
+        ]);
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Range position [${params.tickLower}, ${params.tickUpper}) updated (liquidity hidden!)`);
+        return signature;
+    }
+
+    /**
+     * Collect the fees earned by your range position
+     */
+    async collectRangeFees(
+        pool: PublicKey,
+        tickLower: number,
+        tickUpper: number
+    ): Promise<string> {
+        return this.sendRangePositionInstruction(
+            pool,
+            tickLower,
+            tickUpper,
+            // Discriminator for collect_range_fees
+            Buffer.from([72, 129, 18, 220, 56, 147, 93, 148]),
+            `Range fees collected`
+        );
+    }
+
+    /**
+     * Withdraw a range position's liquidity and fees, closing the position
+     */
+    async closeRangePosition(
+        pool: PublicKey,
+        tickLower: number,
+        tickUpper: number,
+        deadlineSlot: bigint
+    ): Promise<string> {
+        const deadlineData = Buffer.alloc(8);
+        deadlineData.writeBigUInt64LE(deadlineSlot, 0);
+        return this.sendRangePositionInstruction(
+            pool,
+            tickLower,
+            tickUpper,
+            // Discriminator for close_range_position
+            Buffer.concat([Buffer.from([69, 227, 155, 176, 215, 169, 34, 104]), deadlineData]),
+            `Range position [${tickLower}, ${tickUpper}) closed`
+        );
+    }
+
+    /**
+     * Shared account list of collectRangeFees and closeRangePosition
+     */
+    private async sendRangePositionInstruction(
+        pool: PublicKey,
+        tickLower: number,
+        tickUpper: number,
+        data: Buffer,
+        message: string
+    ): Promise<string> {
+        const [tickGrid] = await this.getTickGridAddress(pool);
+        const [rangePosition] = await this.getRangePositionAddress(
+            pool,
+            this.wallet.publicKey,
+            tickLower,
+            tickUpper
+        );
+
+        const keys = [
+            { pubkey: pool, isSigner: false, isWritable: true },
+            { pubkey: tickGrid, isSigner: false, isWritable: true },
+            { pubkey: rangePosition, isSigner: false, isWritable: true },
+            ...(await this.getCustodyKeys(pool)),
+            { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+        ];
+
+        const ix = new TransactionInstruction({
+            keys,
+            programId: PROGRAM_IDS.PRIVATE_AMM,
+            data,
+        });
+
+        const tx = new Transaction().add(ix);
+        const signature = await this.wallet.sendTransaction(tx, this.connection);
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(message);
+        return signature;
+    }
+
+    /**
+     * Verify a hinted swap output: simulate private_amm::verify_quote for the
+     * encrypted output and fee handles, then run it with their allowances so the
+     * wallet can decrypt them. The pool cannot compute outputs itself; both
+     * handles are zero if it rejects the hints (constant-product hints must be exact)
+     */
+    async verifyQuote(params: QuoteParams): Promise<Quote> {
+        const buildTx = (allowances?: [PublicKey, PublicKey]) => {
+            // Anchor reads the program ID as "no account" for the optional ones
+            const optional = (key?: PublicKey, isWritable = false) =>
+                key
+                    ? { pubkey: key, isSigner: false, isWritable }
+                    : { pubkey: PROGRAM_IDS.PRIVATE_AMM, isSigner: false, isWritable: false };
+            const keys = [
+                { pubkey: params.pool, isSigner: false, isWritable: false },
+                optional(params.oracle),
+                optional(params.tickGrid),
+                optional(allowances?.[0], true),
+                optional(allowances?.[1], true),
+                { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+                { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+            ];
+
+            // Discriminator for verify_quote
+            const discriminator = Buffer.from([218, 71, 182, 29, 234, 91, 90, 70]);
+            const amountInData = Buffer.alloc(16);
+            this.writeBigInt128LE(amountInData, params.encryptedAmountIn);
+            const data = Buffer.concat([
+                discriminator,
+                amountInData,
+                Buffer.from([params.aToB ? 1 : 0]),
+                this.encodeHandles(params.encryptedHints),
+            ]);
+
+            return new Transaction().add(
+                new TransactionInstruction({ keys, programId: PROGRAM_IDS.PRIVATE_AMM, data })
+            );
+        };
+
+        const simulation = buildTx();
+        simulation.feePayer = this.wallet.publicKey;
+        simulation.recentBlockhash = (await this.connection.getLatestBlockhash()).blockhash;
+        const { value } = await this.connection.simulateTransaction(simulation);
+        if (value.err || !value.returnData) {
+            throw new Error(`Quote simulation failed: ${JSON.stringify(value.err)}`);
+        }
+
+        // QuoteResult: two 16-byte handles
+        const returned = Buffer.from(value.returnData.data[0], "base64");
+        const quote = {
+            amountOut: this.readBigInt128LE(returned, 0),
+            fee: this.readBigInt128LE(returned, 16),
+        };
+
+        const [amountOutAllowance] = await this.getAllowanceAddress(quote.amountOut, this.wallet.publicKey);
+        const [feeAllowance] = await this.getAllowanceAddress(quote.fee, this.wallet.publicKey);
+        const signature = await this.wallet.sendTransaction(
+            buildTx([amountOutAllowance, feeAllowance]),
+            this.connection
+        );
+        await this.connection.confirmTransaction(signature, "confirmed");
+
+        console.log(`Quote ready (decryptable by you only)`);
+        return quote;
+    }
+
+    /**
+     * Send encrypted amounts from the wallet's balance to any number of recipients
+     * One inco_token::batch_transfer per MAX_BATCH_TRANSFERS legs, each confirmed
+     * before the next is sent; if one fails, the later chunks are not sent. A leg
+     * the balance cannot cover moves zero. Recipient allowances are not granted
+     */
+    async batchTransfer(mint: PublicKey, legs: BatchTransferLeg[]): Promise<string[]> {
+        const [sourceBalance] = await this.getBalanceAddress(this.wallet.publicKey, mint);
+        const signatures: string[] = [];
+
+        for (const chunk of chunkBatchTransfers(legs)) {
+            const keys = [
+                { pubkey: sourceBalance, isSigner: false, isWritable: true },
+                { pubkey: mint, isSigner: false, isWritable: false },
+                { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
+                { pubkey: PROGRAM_IDS.INCO_LIGHTNING, isSigner: false, isWritable: false },
+                // No source allowance or system program: Anchor reads the program ID as "no account"
+                { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+                { pubkey: PROGRAM_IDS.INCO_TOKEN, isSigner: false, isWritable: false },
+            ];
+            // Remaining accounts: one destination balance PDA per leg
+            for (const leg of chunk) {
+                const [destination] = await this.getBalanceAddress(leg.recipient, mint);
+                keys.push({ pubkey: destination, isSigner: false, isWritable: true });
+            }
+
+            // Discriminator for batch_transfer
+            const discriminator = Buffer.from([209, 90, 4, 108, 61, 185, 18, 139]);
+            const data = Buffer.concat([
+                discriminator,
+                this.encodeHandles(chunk.map((leg) => leg.encryptedAmount)),
+            ]);
+
+            const tx = new Transaction().add(
+                ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+                new TransactionInstruction({ keys, programId: PROGRAM_IDS.INCO_TOKEN, data })
+            );
+            const signature = await this.wallet.sendTransaction(tx, this.connection);
+            await this.connection.confirmTransaction(signature, "confirmed");
+            signatures.push(signature);
+        }
+
+        console.log(`Batch transferred to ${legs.length} recipients in ${signatures.length} transactions`);
+        return signatures;
+    }
+
+    /**
+     * Derive the Inco Lightning allowance PDA that lets `allowed` decrypt `handle`
+     */
+    async getAllowanceAddress(handle: bigint, allowed: PublicKey): Promise<[PublicKey, number]> {
+        const handleData = Buffer.alloc(16);
+        this.writeBigInt128LE(handleData, handle);
+        return PublicKey.findProgramAddressSync(
+            [handleData, allowed.toBuffer()],
+            PROGRAM_IDS.INCO_LIGHTNING
+        );
+    }
+
+    /**
+     * Fetch the factory config
+     */
+    async getFactory(): Promise<Factory | null> {
+        const [factory] = await this.getFactoryAddress();
+        const accountInfo = await this.connection.getAccountInfo(factory);
+        if (!accountInfo) return null;
+
+        // Skip 8-byte discriminator
+        const data = accountInfo.data.slice(8);
+
+        return {
+            admin: new PublicKey(data.slice(0, 32)),
+            defaultFeeBps: data.readUInt16LE(32),
+            protocolFeeOn: data[34] === 1,
+            protocolFeeShareBps: data.readUInt16LE(35),
+            feeRecipient: new PublicKey(data.slice(37, 69)),
+            creationFeeLamports: data.readBigUInt64LE(69),
+            poolCount: data.readBigUInt64LE(77),
+        };
+    }
+
+    /**
+     * List every pool in the on-chain registry, in creation order
+     */
+    async getAllPools(): Promise<PublicKey[]> {
+        const factory = await this.getFactory();
+        if (!factory) return [];
+
+        const pages = (factory.poolCount + REGISTRY_PAGE_SIZE - 1n) / REGISTRY_PAGE_SIZE;
+        const addresses = [];
+        for (let page = 0n; page < pages; page++) {
+            addresses.push((await this.getRegistryPageAddress(page))[0]);
+        }
+        const accounts = await this.connection.getMultipleAccountsInfo(addresses);
+
+        const pools: PublicKey[] = [];
+        for (const account of accounts) {
+            if (!account) continue;
+            // Skip discriminator and page index; then a u32-prefixed vec of pubkeys
+            const data = account.data.slice(8 + 8);
+            const count = data.readUInt32LE(0);
+            for (let i = 0; i < count; i++) {
+                pools.push(new PublicKey(data.slice(4 + i * 32, 4 + (i + 1) * 32)));
+            }
+        }
+        return pools;
+    }
+
+    /**
+     * Fetch pool info
+     */
+    async getPool(poolAddress: PublicKey): Promise<Pool | null> {
+        const accountInfo = await this.connection.getAccountInfo(poolAddress);
+        if (!accountInfo) return null;
+
+        // Parse pool data (skip 8-byte discriminator)
+        const data = accountInfo.data.slice(8);
+        const lpMint = new PublicKey(data.slice(LP_MINT_OFFSET, LP_MINT_OFFSET + 32));
+
+        return {
+            address: poolAddress,
+            tokenAMint: new PublicKey(data.slice(0, 32)),
+            tokenBMint: new PublicKey(data.slice(32, 64)),
+            // Encrypted reserves are not readable - only Inco can decrypt
+            feeBps: data.readUInt16LE(64 + 64), // After 4 Euint128 fields
+            authority: new PublicKey(data.slice(64 + 64 + 2, 64 + 64 + 2 + 32)),
+            lpMint: lpMint.equals(PublicKey.default) ? null : lpMint,
+        };
+    }
+
+    // Helper: Write BigInt as 128-bit little-endian
+    private writeBigInt128LE(buffer: Buffer, value: bigint): void {
+        for (let i = 0; i < 16; i++) {
+            buffer[i] = Number((value >> BigInt(i * 8)) & BigInt(0xff));
+        }
+    }
+
+    // Helper: Borsh-encode a Vec<u128> of encrypted handles
+    private encodeHandles(handles: bigint[]): Buffer {
+        const length = Buffer.alloc(4);
+        length.writeUInt32LE(handles.length, 0);
+        const data = handles.map((handle) => {
+            const handleData = Buffer.alloc(16);
+            this.writeBigInt128LE(handleData, handle);
+            return handleData;
+        });
+        return Buffer.concat([length, ...data]);
+    }
+
+    // Helper: Read a 128-bit little-endian BigInt
+    private readBigInt128LE(buffer: Buffer, offset: number): bigint {
+        let value = 0n;
+        for (let i = 15; i >= 0; i--) {
+            value = (value << 8n) | BigInt(buffer[offset + i]);
         }
+        return value;
     }
 }
 
@@ -17,5 +17,13 @@ export const RPC_ENDPOINTS = {
 export const SEEDS = {
     POOL: Buffer.from("pool"),
     POSITION: Buffer.from("position"),
-    SWAP_RESULT: Buffer.from("swap_result"),
+    SWAP_RECEIPT: Buffer.from("swap_receipt"),
+    BATCH: Buffer.from("batch"),
+    LIMIT_ORDER: Buffer.from("limit_order"),
+    TICK_GRID: Buffer.from("tick_grid"),
+    RANGE_POSITION: Buffer.from("range_position"),
+    REWARDS: Buffer.from("rewards"),
+    FACTORY: Buffer.from("factory"),
+    REGISTRY: Buffer.from("registry"),
+    USER_BALANCE: Buffer.from("user_balance"),
 };
@@ -1,7 +1,17 @@
 // Inco Confidential Swap SDK
 // Private AMM with fully encrypted swaps on Solana
 
-export { PrivateAmmClient } from "./PrivateAmmClient";
+export {
+    PrivateAmmClient,
+    PoolType,
+    BATCH_EPOCH_SLOTS,
+    BATCH_SETTLE_WINDOW_SLOTS,
+    BATCH_PRICE_BITS,
+    LIMIT_PRICE_BITS,
+    REGISTRY_PAGE_SIZE,
+    MAX_BATCH_TRANSFERS,
+    chunkBatchTransfers,
+} from "./PrivateAmmClient";
 export { IncoEncryption } from "./IncoEncryption";
 export { PROGRAM_IDS, RPC_ENDPOINTS, SEEDS } from "./constants";
 
@@ -9,6 +19,16 @@ export { PROGRAM_IDS, RPC_ENDPOINTS, SEEDS } from "./constants";
 export type {
     PrivateAmmConfig,
     Pool,
+    Factory,
+    QuoteParams,
+    Quote,
     SwapParams,
+    ClaimSwapOutputParams,
+    SwapRouteParams,
+    SubmitBatchOrderParams,
+    ClaimBatchOrderParams,
+    PlaceLimitOrderParams,
+    OpenRangePositionParams,
     AddLiquidityParams,
+    BatchTransferLeg,
 } from "./PrivateAmmClient";
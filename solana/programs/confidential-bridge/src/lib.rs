@@ -0,0 +1,564 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+use inco_lightning::cpi::accounts::{Allow, Operation};
+use inco_lightning::cpi::{allow, new_euint128};
+use inco_lightning::types::Euint128;
+use inco_token::cpi::accounts::{BurnFromUser, MintToUser, SetMintAuthority};
+use inco_token::program::IncoToken;
+use inco_token::{COption, IncoMint, UserBalance};
+
+// Inco Lightning program ID (from their docs)
+pub const INCO_LIGHTNING_ID: Pubkey = pubkey!("5sjEbPiqgZrYwR31ahR6Uk9wf5awoX61YGg7jExQSwaj");
+
+/// Maximum size of the attestor set
+pub const MAX_ATTESTORS: usize = 10;
+
+/// Domain separator for deposit attestations
+pub const DEPOSIT_DOMAIN: &[u8] = b"inco-bridge:deposit";
+
+declare_id!("C5cEadGyMqQFVdeuzdS1zBVe657PHLYyvfCaAW76YKMc");
+
+/// Confidential Bridge Program
+/// Lock-and-mint bridge between the EVM ConfidentialERC20 and inco_token
+///
+/// EVM -> Solana: tokens locked on the EVM side are minted here once an
+/// M-of-N attestor quorum signs the deposit (ed25519 instructions in the same tx)
+/// Solana -> EVM: tokens are burned here and a message is emitted for attestors
+#[program]
+pub mod confidential_bridge {
+    use super::*;
+
+    /// Create the bridge config for a confidential mint
+    /// The admin must be the mint's current authority; the config PDA takes the
+    /// authority over in the same instruction, so no one else can bridge the mint
+    pub fn initialize_bridge(
+        ctx: Context<InitializeBridge>,
+        evm_chain_id: u64,
+        evm_token: [u8; 20],
+        attestors: Vec<Pubkey>,
+        threshold: u8,
+        release_authority: Pubkey,
+    ) -> Result<()> {
+        validate_attestor_set(&attestors, threshold)?;
+
+        let cpi_accounts = SetMintAuthority {
+            mint: ctx.accounts.mint.to_account_info(),
+            mint_authority: ctx.accounts.admin.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.inco_token_program.to_account_info(), cpi_accounts);
+        inco_token::cpi::set_mint_authority(cpi_ctx, ctx.accounts.bridge_config.key())?;
+
+        let config = &mut ctx.accounts.bridge_config;
+        config.admin = ctx.accounts.admin.key();
+        config.mint = ctx.accounts.mint.key();
+        config.evm_chain_id = evm_chain_id;
+        config.evm_token = evm_token;
+        config.attestors = attestors;
+        config.threshold = threshold;
+        config.release_authority = release_authority;
+        config.outbound_nonce = 0;
+        config.bump = ctx.bumps.bridge_config;
+
+        msg!(
+            "Initialized bridge for {} ({}-of-{} attestors)",
+            config.mint,
+            threshold,
+            config.attestors.len()
+        );
+        Ok(())
+    }
+
+    /// Rotate the attestor set, quorum threshold and release authority
+    pub fn set_attestors(
+        ctx: Context<SetAttestors>,
+        attestors: Vec<Pubkey>,
+        threshold: u8,
+        release_authority: Pubkey,
+    ) -> Result<()> {
+        validate_attestor_set(&attestors, threshold)?;
+
+        let config = &mut ctx.accounts.bridge_config;
+        config.attestors = attestors;
+        config.threshold = threshold;
+        config.release_authority = release_authority;
+
+        msg!("Attestor set updated ({}-of-{})", threshold, config.attestors.len());
+        Ok(())
+    }
+
+    /// Mint cTokens for a deposit observed on the EVM side
+    ///
+    /// The transaction must contain ed25519 verify instructions, before this
+    /// one, in which at least `threshold` attestors sign `deposit_message(..)`.
+    /// A deposit record PDA per source tx hash prevents replays.
+    ///
+    /// Trust assumption: the EVM amount is encrypted, so the program cannot
+    /// compare it with what it mints. Attestors observe the EVM deposit and
+    /// sign the hash of an Inco ciphertext of that amount; the quorum alone
+    /// vouches that the two match. The ciphertext is re-encrypted here with
+    /// new_euint128, so only the attested bytes can be minted - never an
+    /// existing handle supplied by the relayer.
+    pub fn complete_deposit(
+        ctx: Context<CompleteDeposit>,
+        source_tx_hash: [u8; 32],
+        ciphertext: Vec<u8>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.bridge_config;
+        let message = deposit_message(
+            &config.key(),
+            config.evm_chain_id,
+            &source_tx_hash,
+            &ctx.accounts.recipient.key(),
+            &ciphertext,
+        );
+        verify_quorum(
+            &ctx.accounts.instructions,
+            &config.attestors,
+            config.threshold,
+            &message,
+        )?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[b"bridge_config".as_ref(), mint_key.as_ref(), &[config.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let inco_ctx = CpiContext::new_with_signer(
+            ctx.accounts.inco_lightning_program.to_account_info(),
+            Operation { signer: ctx.accounts.bridge_config.to_account_info() },
+            signer_seeds,
+        );
+        let amount = new_euint128(inco_ctx, ciphertext, 0)?;
+
+        let cpi_accounts = MintToUser {
+            user_balance: ctx.accounts.recipient_balance.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            mint_authority: ctx.accounts.bridge_config.to_account_info(),
+            inco_lightning_program: ctx.accounts.inco_lightning_program.to_account_info(),
+            // The config PDA signs Inco operations but cannot fund an allowance account
+            user: None,
+            user_allowance: None,
+            system_program: None,
+        };
+        let cpi_program = ctx.accounts.inco_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        inco_token::cpi::mint_to_user(cpi_ctx, amount.0)?;
+
+        let deposit = &mut ctx.accounts.processed_deposit;
+        deposit.source_tx_hash = source_tx_hash;
+        deposit.recipient = ctx.accounts.recipient.key();
+        deposit.slot = Clock::get()?.slot;
+        deposit.bump = ctx.bumps.processed_deposit;
+
+        msg!("Completed bridge deposit for {}", deposit.recipient);
+        Ok(())
+    }
+
+    /// Burn cTokens and emit a message for attestors to release on the EVM side
+    /// If the balance is insufficient zero is burned, and the emitted handle reflects it
+    ///
+    /// `release_allowance` grants the bridge's release authority decryption of
+    /// the burned handle; without it the message cannot be released. Clients
+    /// simulate without it first to learn the handle, then derive the PDA.
+    /// With `balance_allowance`, the user may decrypt its new balance.
+    pub fn bridge_out(
+        ctx: Context<BridgeOut>,
+        encrypted_amount: u128,
+        evm_recipient: [u8; 20],
+    ) -> Result<()> {
+        let cpi_accounts = BurnFromUser {
+            user_balance: ctx.accounts.user_balance.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            user: ctx.accounts.user.to_account_info(),
+            inco_lightning_program: ctx.accounts.inco_lightning_program.to_account_info(),
+            user_allowance: ctx.accounts.balance_allowance.as_ref().map(|a| a.to_account_info()),
+            system_program: ctx.accounts.system_program.as_ref().map(|p| p.to_account_info()),
+        };
+        let cpi_program = ctx.accounts.inco_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        let burned = inco_token::cpi::burn_from_user(cpi_ctx, encrypted_amount)?.get();
+
+        if let Some(release_allowance) = &ctx.accounts.release_allowance {
+            let system_program = ctx.accounts.system_program.as_ref()
+                .ok_or(BridgeError::MissingAllowanceAccounts)?;
+            let release_authority = ctx.accounts.release_authority.as_ref()
+                .ok_or(BridgeError::MissingAllowanceAccounts)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.inco_lightning_program.to_account_info(),
+                Allow {
+                    allowance_account: release_allowance.to_account_info(),
+                    signer: ctx.accounts.user.to_account_info(),
+                    allowed_address: release_authority.to_account_info(),
+                    system_program: system_program.to_account_info(),
+                },
+            );
+            allow(cpi_ctx, burned.0, true, release_authority.key())?;
+        }
+
+        let config = &mut ctx.accounts.bridge_config;
+        config.outbound_nonce = config
+            .outbound_nonce
+            .checked_add(1)
+            .ok_or(BridgeError::NonceOverflow)?;
+
+        emit!(BridgeOutMessage {
+            nonce: config.outbound_nonce,
+            sender: ctx.accounts.user.key(),
+            evm_chain_id: config.evm_chain_id,
+            evm_token: config.evm_token,
+            evm_recipient,
+            amount: burned,
+        });
+
+        msg!("Bridged out message #{}", config.outbound_nonce);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ACCOUNT STRUCTURES
+// ============================================================================
+
+/// Bridge configuration for one confidential mint
+/// Seeds: ["bridge_config", mint] - also the mint authority of `mint`
+#[account]
+pub struct BridgeConfig {
+    /// Admin allowed to rotate attestors
+    pub admin: Pubkey,
+    /// inco_token mint minted/burned by the bridge
+    pub mint: Pubkey,
+    /// EVM chain the ConfidentialERC20 lives on
+    pub evm_chain_id: u64,
+    /// ConfidentialERC20 contract address on the EVM side
+    pub evm_token: [u8; 20],
+    /// Attestor ed25519 public keys
+    pub attestors: Vec<Pubkey>,
+    /// Number of distinct attestor signatures required
+    pub threshold: u8,
+    /// Key allowed to decrypt burned amounts (held by the attestor service)
+    pub release_authority: Pubkey,
+    /// Sequence number of outbound (burn) messages
+    pub outbound_nonce: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl BridgeConfig {
+    // 32 + 32 + 8 + 20 + (4 + 32 * 10) + 1 + 32 + 8 + 1 = 458 bytes
+    pub const LEN: usize = 32 + 32 + 8 + 20 + (4 + 32 * MAX_ATTESTORS) + 1 + 32 + 8 + 1;
+}
+
+/// Record of a completed deposit (replay protection)
+/// Seeds: ["deposit", bridge_config, source_tx_hash]
+#[account]
+pub struct ProcessedDeposit {
+    /// EVM transaction hash of the deposit
+    pub source_tx_hash: [u8; 32],
+    /// Solana recipient that was credited
+    pub recipient: Pubkey,
+    /// Slot the deposit was completed in
+    pub slot: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ProcessedDeposit {
+    pub const LEN: usize = 32 + 32 + 8 + 1; // 73 bytes
+}
+
+// ============================================================================
+// INSTRUCTION CONTEXTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeBridge<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + BridgeConfig::LEN,
+        seeds = [b"bridge_config", mint.key().as_ref()],
+        bump,
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    #[account(
+        mut,
+        constraint = mint.is_initialized @ BridgeError::InvalidMint,
+        constraint = mint.mint_authority == COption::Some(admin.key()) @ BridgeError::InvalidMintAuthority,
+    )]
+    pub mint: Account<'info, IncoMint>,
+
+    /// Current mint authority; hands it to the config PDA
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub inco_token_program: Program<'info, IncoToken>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAttestors<'info> {
+    #[account(
+        mut,
+        seeds = [b"bridge_config", bridge_config.mint.as_ref()],
+        bump = bridge_config.bump,
+        has_one = admin @ BridgeError::InvalidAdmin,
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(source_tx_hash: [u8; 32])]
+pub struct CompleteDeposit<'info> {
+    /// Bridge config (mint authority, signs Inco operations via CPI)
+    #[account(
+        mut,
+        seeds = [b"bridge_config", mint.key().as_ref()],
+        bump = bridge_config.bump,
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    /// Replay protection - init fails if this deposit was already completed
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + ProcessedDeposit::LEN,
+        seeds = [b"deposit", bridge_config.key().as_ref(), source_tx_hash.as_ref()],
+        bump,
+    )]
+    pub processed_deposit: Account<'info, ProcessedDeposit>,
+
+    /// Recipient's inco_token balance PDA
+    #[account(
+        mut,
+        constraint = recipient_balance.user == recipient.key() @ BridgeError::InvalidRecipient,
+        constraint = recipient_balance.mint == mint.key() @ BridgeError::InvalidMint,
+    )]
+    pub recipient_balance: Account<'info, UserBalance>,
+
+    /// Recipient wallet
+    /// CHECK: Just need the pubkey (bound into the attested message)
+    pub recipient: AccountInfo<'info>,
+
+    #[account(mut, address = bridge_config.mint @ BridgeError::InvalidMint)]
+    pub mint: Account<'info, IncoMint>,
+
+    /// Anyone may relay an attested deposit
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: Instructions sysvar - validated via address constraint
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub inco_token_program: Program<'info, IncoToken>,
+
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BridgeOut<'info> {
+    #[account(
+        mut,
+        seeds = [b"bridge_config", mint.key().as_ref()],
+        bump = bridge_config.bump,
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    /// User's inco_token balance PDA (seeds checked by inco_token)
+    #[account(mut)]
+    pub user_balance: Account<'info, UserBalance>,
+
+    #[account(mut, address = bridge_config.mint @ BridgeError::InvalidMint)]
+    pub mint: Account<'info, IncoMint>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub inco_token_program: Program<'info, IncoToken>,
+
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+
+    // Allowance accounts - only required when granting the release or balance allowance
+
+    /// CHECK: Validated against the bridge's release authority
+    #[account(address = bridge_config.release_authority @ BridgeError::InvalidReleaseAuthority)]
+    pub release_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Inco allowance PDA for (burned amount, release authority) - validated by Inco Lightning
+    #[account(mut)]
+    pub release_allowance: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Inco allowance PDA for (new user balance, user) - validated by Inco Lightning
+    #[account(mut)]
+    pub balance_allowance: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// Burn message picked up by attestors to release on the EVM side
+#[event]
+pub struct BridgeOutMessage {
+    pub nonce: u64,
+    pub sender: Pubkey,
+    pub evm_chain_id: u64,
+    pub evm_token: [u8; 20],
+    pub evm_recipient: [u8; 20],
+    /// Encrypted amount actually burned (zero if the balance was insufficient)
+    pub amount: Euint128,
+}
+
+// ============================================================================
+// ATTESTATION HELPERS
+// ============================================================================
+
+/// Message attestors sign for a deposit:
+/// DEPOSIT_DOMAIN || bridge_config || evm_chain_id (LE) || source_tx_hash || recipient || sha256(ciphertext)
+pub fn deposit_message(
+    bridge_config: &Pubkey,
+    evm_chain_id: u64,
+    source_tx_hash: &[u8; 32],
+    recipient: &Pubkey,
+    ciphertext: &[u8],
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(DEPOSIT_DOMAIN.len() + 32 + 8 + 32 + 32 + 32);
+    message.extend_from_slice(DEPOSIT_DOMAIN);
+    message.extend_from_slice(bridge_config.as_ref());
+    message.extend_from_slice(&evm_chain_id.to_le_bytes());
+    message.extend_from_slice(source_tx_hash);
+    message.extend_from_slice(recipient.as_ref());
+    message.extend_from_slice(&hash(ciphertext).to_bytes());
+    message
+}
+
+fn validate_attestor_set(attestors: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(attestors.len() <= MAX_ATTESTORS, BridgeError::TooManyAttestors);
+    require!(
+        threshold > 0 && threshold as usize <= attestors.len(),
+        BridgeError::InvalidThreshold
+    );
+    for (i, attestor) in attestors.iter().enumerate() {
+        require!(!attestors[..i].contains(attestor), BridgeError::DuplicateAttestor);
+    }
+    Ok(())
+}
+
+/// Count distinct attestors that signed `message` in ed25519 instructions
+/// preceding the current one, and require at least `threshold` of them
+fn verify_quorum(
+    instructions: &AccountInfo,
+    attestors: &[Pubkey],
+    threshold: u8,
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    let mut signed: Vec<Pubkey> = Vec::with_capacity(attestors.len());
+
+    for index in 0..current {
+        let ix = load_instruction_at_checked(index, instructions)?;
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+        for (signer, signed_message) in parse_ed25519_instruction(&ix.data)? {
+            if signed_message == message && attestors.contains(&signer) && !signed.contains(&signer) {
+                signed.push(signer);
+            }
+        }
+    }
+
+    require!(signed.len() >= threshold as usize, BridgeError::QuorumNotReached);
+    Ok(())
+}
+
+/// Parse (public key, message) pairs out of an ed25519 program instruction
+///
+/// Layout: num_signatures (u8), padding (u8), then per signature 7 u16 LE:
+/// signature_offset, signature_ix_index, public_key_offset, public_key_ix_index,
+/// message_offset, message_size, message_ix_index.
+/// Only self-contained instructions (all ix indices == u16::MAX) are accepted,
+/// since the ed25519 program already verified the bytes it points at.
+fn parse_ed25519_instruction(data: &[u8]) -> Result<Vec<(Pubkey, &[u8])>> {
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const CURRENT_IX: u16 = u16::MAX;
+
+    let count = *data.first().ok_or(BridgeError::MalformedAttestation)? as usize;
+    let read_u16 = |at: usize| -> Result<u16> {
+        let bytes = data.get(at..at + 2).ok_or(BridgeError::MalformedAttestation)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        let base = HEADER_LEN + i * OFFSETS_LEN;
+        let signature_ix = read_u16(base + 2)?;
+        let public_key_offset = read_u16(base + 4)? as usize;
+        let public_key_ix = read_u16(base + 6)?;
+        let message_offset = read_u16(base + 8)? as usize;
+        let message_size = read_u16(base + 10)? as usize;
+        let message_ix = read_u16(base + 12)?;
+        require!(
+            signature_ix == CURRENT_IX && public_key_ix == CURRENT_IX && message_ix == CURRENT_IX,
+            BridgeError::MalformedAttestation
+        );
+
+        let public_key = data
+            .get(public_key_offset..public_key_offset + 32)
+            .ok_or(BridgeError::MalformedAttestation)?;
+        let message = data
+            .get(message_offset..message_offset + message_size)
+            .ok_or(BridgeError::MalformedAttestation)?;
+        let public_key =
+            Pubkey::try_from(public_key).map_err(|_| BridgeError::MalformedAttestation)?;
+        entries.push((public_key, message));
+    }
+    Ok(entries)
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum BridgeError {
+    #[msg("Invalid bridge admin")]
+    InvalidAdmin,
+    #[msg("Invalid or uninitialized mint")]
+    InvalidMint,
+    #[msg("Bridge config must be the mint authority")]
+    InvalidMintAuthority,
+    #[msg("Recipient does not own the balance account")]
+    InvalidRecipient,
+    #[msg("Too many attestors")]
+    TooManyAttestors,
+    #[msg("Threshold must be between 1 and the number of attestors")]
+    InvalidThreshold,
+    #[msg("Duplicate attestor")]
+    DuplicateAttestor,
+    #[msg("Malformed ed25519 attestation instruction")]
+    MalformedAttestation,
+    #[msg("Attestor quorum not reached")]
+    QuorumNotReached,
+    #[msg("Outbound nonce overflow")]
+    NonceOverflow,
+    #[msg("Release authority does not match the bridge config")]
+    InvalidReleaseAuthority,
+    #[msg("Release allowance requires the release authority and system program")]
+    MissingAllowanceAccounts,
+}
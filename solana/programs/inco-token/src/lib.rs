@@ -1,10 +1,29 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer as SplTransfer};
-use inco_lightning::types::Euint128;
+use inco_lightning::cpi::accounts::{Allow, Operation};
+use inco_lightning::cpi::{
+    allow, as_euint128, e_add, e_ge, e_mul, e_select, e_shr, e_sub, new_euint128,
+};
+use inco_lightning::types::{Ebool, Euint128};
 
 // Use the actual Inco Lightning program ID from their docs
 pub const INCO_LIGHTNING_PROGRAM_ID: Pubkey = pubkey!("5sjEbPiqgZrYwR31ahR6Uk9wf5awoX61YGg7jExQSwaj");
 
+/// Maximum number of legs in a single batch_transfer
+/// A leg costs up to 8 instruction-trace entries (e_ge, e_select, e_sub, e_add
+/// and two allow CPIs that may each create their allowance PDA). With the outer
+/// instruction, a compute-budget instruction, the zero constant and the source
+/// allow, 7 legs use 61 of Solana's 64 trace entries
+pub const MAX_BATCH_TRANSFERS: usize = 7;
+
+/// Allowance accounts per batch_transfer leg:
+/// (recipient wallet, recipient balance allowance, source success allowance)
+pub const BATCH_ALLOW_ACCOUNTS: usize = 3;
+
+/// Fixed-point precision (bits) of the public vesting fraction
+/// vested = (total * fraction) >> VESTING_FRACTION_BITS
+pub const VESTING_FRACTION_BITS: u32 = 32;
+
 declare_id!("h6T7wsEJWMxN2uEZUc4SipEd8Zmz2DWasCDopindjC5");
 
 /// Confidential SPL Token Program
@@ -67,14 +86,42 @@ pub mod inco_token {
 
     /// Transfer confidential tokens between accounts
     /// Amount is the encrypted value
+    /// An optional encrypted memo (e.g. invoice reference) is readable only by
+    /// the source and destination owners; it requires the optional memo accounts.
+    /// Clients simulate without the two memo allowances first to learn the memo
+    /// handle from the event, then derive the allowance PDAs
     pub fn transfer(
         ctx: Context<IncoTransfer>,
         amount: u128,
+        encrypted_memo: Option<Vec<u8>>,
     ) -> Result<()> {
         // In production, you'd use Inco's CPI to perform encrypted arithmetic
         // For now, we store the transfer amount as destination balance
         let destination = &mut ctx.accounts.destination;
         destination.amount = Euint128::wrap(amount);
+
+        let memo = match encrypted_memo {
+            Some(ciphertext) => Some(create_memo(
+                MemoAccounts {
+                    inco_lightning_program: ctx.accounts.inco_lightning_program.as_ref(),
+                    sender_allowance: ctx.accounts.sender_memo_allowance.as_deref(),
+                    recipient_allowance: ctx.accounts.recipient_memo_allowance.as_deref(),
+                    system_program: ctx.accounts.system_program.as_deref(),
+                    signer: &ctx.accounts.authority,
+                    sender: ctx.accounts.sender.as_deref(),
+                    recipient: ctx.accounts.recipient.as_deref(),
+                },
+                ciphertext,
+            )?),
+            None => None,
+        };
+
+        emit!(ConfidentialTransfer {
+            source: ctx.accounts.source.owner,
+            destination: ctx.accounts.destination.owner,
+            mint: ctx.accounts.source.mint,
+            memo,
+        });
         
         msg!("Transferred confidential tokens");
         Ok(())
@@ -286,6 +333,20 @@ pub mod inco_token {
         Ok(())
     }
 
+    /// Initialize a balance PDA whose owner differs from the payer
+    /// Lets programs (e.g. private_amm pools) own balances via PDA signatures
+    pub fn initialize_owned_balance(ctx: Context<InitializeOwnedBalance>) -> Result<()> {
+        let user_balance = &mut ctx.accounts.user_balance;
+        user_balance.user = ctx.accounts.owner.key();
+        user_balance.mint = ctx.accounts.mint.key();
+        user_balance.encrypted_balance = Euint128::default();
+        user_balance.bump = ctx.bumps.user_balance;
+        user_balance.is_initialized = true;
+        
+        msg!("Initialized owned balance for {}", ctx.accounts.owner.key());
+        Ok(())
+    }
+
     /// Wrap SOL into user's confidential balance (per-user PDA)
     /// Locks SOL in vault, credits encrypted amount to user's balance
     pub fn wrap_to_user(
@@ -346,9 +407,11 @@ pub mod inco_token {
 
     /// Transfer between users' confidential balances
     /// Private transfer with encrypted amount
+    /// Optional encrypted memo as in `transfer`
     pub fn transfer_to_user(
         ctx: Context<TransferToUser>,
         encrypted_amount: u128,
+        encrypted_memo: Option<Vec<u8>>,
     ) -> Result<()> {
         // In production: use Inco FHE operations for encrypted arithmetic
         // For now: simple balance transfer
@@ -360,10 +423,253 @@ pub mod inco_token {
         // Set destination to transferred amount
         dest.encrypted_balance = Euint128::wrap(encrypted_amount);
 
+        let memo = match encrypted_memo {
+            Some(ciphertext) => Some(create_memo(
+                MemoAccounts {
+                    inco_lightning_program: ctx.accounts.inco_lightning_program.as_ref(),
+                    sender_allowance: ctx.accounts.sender_memo_allowance.as_deref(),
+                    recipient_allowance: ctx.accounts.recipient_memo_allowance.as_deref(),
+                    system_program: ctx.accounts.system_program.as_deref(),
+                    signer: &ctx.accounts.source_user,
+                    sender: Some(&ctx.accounts.source_user),
+                    recipient: Some(&ctx.accounts.dest_user),
+                },
+                ciphertext,
+            )?),
+            None => None,
+        };
+
+        emit!(ConfidentialTransfer {
+            source: ctx.accounts.source_user.key(),
+            destination: ctx.accounts.dest_user.key(),
+            mint: ctx.accounts.mint.key(),
+            memo,
+        });
+
         msg!("Transferred encrypted amount between users");
         Ok(())
     }
 
+    /// Homomorphic transfer between balance PDAs, signed by the source owner
+    /// Moves zero if the source balance is insufficient and returns the
+    /// handle actually moved, so calling programs can account for it
+    ///
+    /// With `source_allowance` / `dest_allowance`, each owner may decrypt its
+    /// new balance. Clients simulate without them first to learn the handles.
+    pub fn confidential_transfer(
+        ctx: Context<BalanceTransfer>,
+        encrypted_amount: u128,
+    ) -> Result<Euint128> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.owner.to_account_info();
+        let amount = Euint128::wrap(encrypted_amount);
+
+        // moved = source >= amount ? amount : 0
+        let source = &mut ctx.accounts.source_balance;
+        let zero = as_euint128(inco_op(&inco, &signer), 0)?;
+        let sufficient = e_ge(inco_op(&inco, &signer), source.encrypted_balance, amount, 0)?;
+        let moved = e_select(inco_op(&inco, &signer), sufficient, amount, zero, 0)?;
+        source.encrypted_balance = e_sub(inco_op(&inco, &signer), source.encrypted_balance, moved, 0)?;
+
+        let dest = &mut ctx.accounts.dest_balance;
+        dest.encrypted_balance = credit(&inco, &signer, dest.encrypted_balance, moved)?;
+
+        let system_program = ctx.accounts.system_program.as_deref();
+        grant_owner(
+            &inco,
+            &signer,
+            system_program,
+            ctx.accounts.source_allowance.as_deref(),
+            Some(&signer),
+            source.encrypted_balance,
+        )?;
+        grant_owner(
+            &inco,
+            &signer,
+            system_program,
+            ctx.accounts.dest_allowance.as_deref(),
+            ctx.accounts.dest_user.as_deref(),
+            dest.encrypted_balance,
+        )?;
+
+        msg!("Confidential transfer to {}", dest.user);
+        Ok(moved)
+    }
+
+    /// Batch transfer from one user's confidential balance to many users
+    /// Destination balance PDAs are passed via remaining_accounts, one per amount,
+    /// optionally followed by BATCH_ALLOW_ACCOUNTS allowance accounts per leg
+    ///
+    /// Each leg is checked against the running source balance with e_ge;
+    /// a leg that would overdraw moves zero instead of aborting the batch.
+    /// The per-leg success flag is emitted as an encrypted boolean.
+    ///
+    /// With the allowance accounts (and `source_allowance`), each recipient may
+    /// decrypt its new balance and the source user may decrypt its new balance
+    /// and every success flag. Clients simulate without them first to learn
+    /// the resulting handles, then derive the allowance PDAs.
+    pub fn batch_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTransfer<'info>>,
+        encrypted_amounts: Vec<u128>,
+    ) -> Result<()> {
+        require!(!encrypted_amounts.is_empty(), CustomError::EmptyBatch);
+        require!(
+            encrypted_amounts.len() <= MAX_BATCH_TRANSFERS,
+            CustomError::BatchTooLarge
+        );
+        let legs = encrypted_amounts.len();
+        let (destinations, allowances) = ctx.remaining_accounts.split_at(
+            legs.min(ctx.remaining_accounts.len()),
+        );
+        require!(
+            destinations.len() == legs
+                && (allowances.is_empty() || allowances.len() == legs * BATCH_ALLOW_ACCOUNTS),
+            CustomError::BatchLengthMismatch
+        );
+        let grants = if allowances.is_empty() {
+            None
+        } else {
+            let system_program = ctx.accounts.system_program.as_ref()
+                .ok_or_else(|| error!(CustomError::MissingAllowanceAccounts))?;
+            let source_allowance = ctx.accounts.source_allowance.as_ref()
+                .ok_or_else(|| error!(CustomError::MissingAllowanceAccounts))?;
+            Some((system_program.to_account_info(), source_allowance.to_account_info()))
+        };
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.source_user.to_account_info();
+        let source_key = ctx.accounts.source_balance.key();
+        let mint_key = ctx.accounts.mint.key();
+
+        let zero = as_euint128(inco_op(&inco, &signer), 0)?;
+        let mut source_balance = ctx.accounts.source_balance.encrypted_balance;
+
+        for (leg, (dest_info, encrypted_amount)) in destinations.iter().zip(encrypted_amounts).enumerate() {
+            require!(dest_info.is_writable, CustomError::InvalidDestination);
+            require_keys_neq!(dest_info.key(), source_key, CustomError::InvalidDestination);
+
+            let mut dest = Account::<UserBalance>::try_from(dest_info)?;
+            require!(dest.is_initialized, CustomError::UninitializedState);
+            require_keys_eq!(dest.mint, mint_key, CustomError::MintMismatch);
+            let expected = Pubkey::create_program_address(
+                &[b"user_balance", dest.user.as_ref(), mint_key.as_ref(), &[dest.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(CustomError::InvalidDestination))?;
+            require_keys_eq!(expected, dest_info.key(), CustomError::InvalidDestination);
+
+            // success = source >= amount; moved = success ? amount : 0
+            let amount = Euint128::wrap(encrypted_amount);
+            let success: Ebool = e_ge(inco_op(&inco, &signer), source_balance, amount, 0)?;
+            let moved = e_select(inco_op(&inco, &signer), success, amount, zero, 0)?;
+
+            source_balance = e_sub(inco_op(&inco, &signer), source_balance, moved, 0)?;
+            dest.encrypted_balance = credit(&inco, &signer, dest.encrypted_balance, moved)?;
+
+            if let Some((system_program, _)) = &grants {
+                let accounts = &allowances[leg * BATCH_ALLOW_ACCOUNTS..(leg + 1) * BATCH_ALLOW_ACCOUNTS];
+                let (recipient, recipient_allowance, success_allowance) =
+                    (&accounts[0], &accounts[1], &accounts[2]);
+                require_keys_eq!(recipient.key(), dest.user, CustomError::OwnerMismatch);
+
+                grant(&inco, &signer, system_program, recipient_allowance, recipient, dest.encrypted_balance.0)?;
+                grant(&inco, &signer, system_program, success_allowance, &signer, success.0)?;
+            }
+
+            emit!(BatchTransferLeg {
+                source: ctx.accounts.source_user.key(),
+                destination: dest.user,
+                mint: mint_key,
+                success,
+            });
+
+            // Persist now so a repeated destination sees the updated balance
+            dest.exit(ctx.program_id)?;
+        }
+
+        ctx.accounts.source_balance.encrypted_balance = source_balance;
+
+        if let Some((system_program, source_allowance)) = &grants {
+            grant(&inco, &signer, system_program, source_allowance, &signer, source_balance.0)?;
+        }
+
+        msg!("Batch transferred to {} users", legs);
+        Ok(())
+    }
+
+    /// Mint encrypted tokens into a user's balance PDA
+    /// Only the mint authority may call this (e.g. the bridge config PDA via CPI)
+    /// With `user_allowance`, the recipient may decrypt its new balance
+    pub fn mint_to_user(
+        ctx: Context<MintToUser>,
+        encrypted_amount: u128,
+    ) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.mint_authority.to_account_info();
+        let amount = Euint128::wrap(encrypted_amount);
+
+        let user_balance = &mut ctx.accounts.user_balance;
+        user_balance.encrypted_balance = credit(&inco, &signer, user_balance.encrypted_balance, amount)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.user_allowance.as_deref(),
+            ctx.accounts.user.as_deref(),
+            user_balance.encrypted_balance,
+        )?;
+
+        let mint = &mut ctx.accounts.mint;
+        mint.supply = credit(&inco, &signer, mint.supply, amount)?;
+
+        msg!("Minted confidential tokens to user {}", user_balance.user);
+        Ok(())
+    }
+
+    /// Burn an encrypted amount from the caller's balance PDA
+    /// Burns zero if the balance is insufficient; returns the burned handle
+    /// With `user_allowance`, the user may decrypt its new balance
+    pub fn burn_from_user(
+        ctx: Context<BurnFromUser>,
+        encrypted_amount: u128,
+    ) -> Result<Euint128> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let amount = Euint128::wrap(encrypted_amount);
+
+        // burned = balance >= amount ? amount : 0
+        let user_balance = &mut ctx.accounts.user_balance;
+        let zero = as_euint128(inco_op(&inco, &signer), 0)?;
+        let sufficient = e_ge(inco_op(&inco, &signer), user_balance.encrypted_balance, amount, 0)?;
+        let burned = e_select(inco_op(&inco, &signer), sufficient, amount, zero, 0)?;
+        user_balance.encrypted_balance =
+            e_sub(inco_op(&inco, &signer), user_balance.encrypted_balance, burned, 0)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.user_allowance.as_deref(),
+            Some(&signer),
+            user_balance.encrypted_balance,
+        )?;
+
+        let mint = &mut ctx.accounts.mint;
+        mint.supply = e_sub(inco_op(&inco, &signer), mint.supply, burned, 0)?;
+
+        msg!("Burned confidential tokens from user {}", user_balance.user);
+        Ok(burned)
+    }
+
+    /// Hand a mint's authority to a new key (current mint authority only)
+    /// Lets programs such as the confidential bridge take over a mint atomically
+    pub fn set_mint_authority(ctx: Context<SetMintAuthority>, new_authority: Pubkey) -> Result<()> {
+        let mint = &mut ctx.accounts.mint;
+        mint.mint_authority = COption::Some(new_authority);
+
+        msg!("Mint authority set to {}", new_authority);
+        Ok(())
+    }
+
     /// Faucet: Mint free test USDC to user's balance (for demo/testing)
     /// This allows anyone to get test USDC for swap testing
     pub fn faucet_usdc(
@@ -448,6 +754,265 @@ pub mod inco_token {
         msg!("Initialized USDC vault for mint: {}", ctx.accounts.usdc_mint.key());
         Ok(())
     }
+
+    // ========================================================================
+    // VESTING FUNCTIONS
+    // Amounts stay encrypted; the schedule (start/cliff/end) is public
+    // ========================================================================
+
+    /// Lock an encrypted amount from the grantor's balance into a vesting PDA
+    /// If the grantor's balance is insufficient, zero is locked
+    /// With `grantor_allowance`, the grantor may decrypt its new balance
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        vesting_id: u64,
+        encrypted_amount: u128,
+        start_ts: i64,
+        cliff_ts: i64,
+        end_ts: i64,
+        revocable: bool,
+    ) -> Result<()> {
+        require!(
+            start_ts <= cliff_ts && cliff_ts <= end_ts && start_ts < end_ts,
+            CustomError::InvalidVestingSchedule
+        );
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.grantor.to_account_info();
+
+        // locked = balance >= amount ? amount : 0
+        let amount = Euint128::wrap(encrypted_amount);
+        let grantor_balance = &mut ctx.accounts.grantor_balance;
+        let zero = as_euint128(inco_op(&inco, &signer), 0)?;
+        let sufficient = e_ge(inco_op(&inco, &signer), grantor_balance.encrypted_balance, amount, 0)?;
+        let locked = e_select(inco_op(&inco, &signer), sufficient, amount, zero, 0)?;
+        grantor_balance.encrypted_balance =
+            e_sub(inco_op(&inco, &signer), grantor_balance.encrypted_balance, locked, 0)?;
+        grant_owner(
+            &inco,
+            &signer,
+            Some(&ctx.accounts.system_program.to_account_info()),
+            ctx.accounts.grantor_allowance.as_deref(),
+            Some(&signer),
+            grantor_balance.encrypted_balance,
+        )?;
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.grantor = ctx.accounts.grantor.key();
+        vesting.beneficiary = ctx.accounts.beneficiary.key();
+        vesting.mint = ctx.accounts.mint.key();
+        vesting.vesting_id = vesting_id;
+        vesting.total_amount = locked;
+        vesting.released_amount = zero;
+        vesting.start_ts = start_ts;
+        vesting.cliff_ts = cliff_ts;
+        vesting.end_ts = end_ts;
+        vesting.revocable = revocable;
+        vesting.revoked = false;
+        vesting.bump = ctx.bumps.vesting;
+
+        msg!("Created vesting {} for {}", vesting_id, vesting.beneficiary);
+        Ok(())
+    }
+
+    /// Release the linearly vested, not yet released amount to the beneficiary
+    /// With `beneficiary_allowance`, the beneficiary may decrypt its new balance
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.vesting.cliff_ts, CustomError::CliffNotReached);
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.beneficiary.to_account_info();
+
+        let vesting = &mut ctx.accounts.vesting;
+        let vested = vested_amount(&inco, &signer, vesting, now)?;
+        let claimable = e_sub(inco_op(&inco, &signer), vested, vesting.released_amount, 0)?;
+
+        vesting.released_amount = vested;
+        let beneficiary_balance = &mut ctx.accounts.beneficiary_balance;
+        beneficiary_balance.encrypted_balance =
+            credit(&inco, &signer, beneficiary_balance.encrypted_balance, claimable)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.beneficiary_allowance.as_deref(),
+            Some(&signer),
+            beneficiary_balance.encrypted_balance,
+        )?;
+
+        msg!("Claimed vested tokens for {}", vesting.beneficiary);
+        Ok(())
+    }
+
+    /// Revoke a revocable grant: the unvested portion returns to the grantor
+    /// and the schedule is truncated at the current time
+    /// With `grantor_allowance`, the grantor may decrypt its new balance
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting = &mut ctx.accounts.vesting;
+        require!(vesting.revocable, CustomError::VestingNotRevocable);
+        require!(!vesting.revoked, CustomError::VestingAlreadyRevoked);
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.grantor.to_account_info();
+
+        let vested = if now < vesting.cliff_ts {
+            as_euint128(inco_op(&inco, &signer), 0)?
+        } else {
+            vested_amount(&inco, &signer, vesting, now)?
+        };
+        let unvested = e_sub(inco_op(&inco, &signer), vesting.total_amount, vested, 0)?;
+
+        let grantor_balance = &mut ctx.accounts.grantor_balance;
+        grantor_balance.encrypted_balance =
+            e_add(inco_op(&inco, &signer), grantor_balance.encrypted_balance, unvested, 0)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.grantor_allowance.as_deref(),
+            Some(&signer),
+            grantor_balance.encrypted_balance,
+        )?;
+
+        // Everything still in the grant is now fully vested
+        vesting.total_amount = vested;
+        vesting.cliff_ts = vesting.cliff_ts.min(now);
+        vesting.end_ts = now.max(vesting.start_ts);
+        vesting.revoked = true;
+
+        msg!("Revoked vesting {} for {}", vesting.vesting_id, vesting.beneficiary);
+        Ok(())
+    }
+
+    // ========================================================================
+    // HASH-TIME-LOCKED ESCROW FUNCTIONS
+    // Atomic cross-chain swaps: the same SHA-256 hashlock is used on the
+    // EVM side, so revealing the preimage here unlocks the counterpart there
+    // ========================================================================
+
+    /// Lock an encrypted amount from the sender's balance under a hashlock
+    /// If the sender's balance is insufficient, zero is locked
+    ///
+    /// With `recipient_allowance`, the recipient may decrypt `escrow.amount`.
+    /// It should decrypt it and check the locked amount before revealing the
+    /// preimage, since an underfunded lock holds an encrypted zero. Clients
+    /// simulate without the allowance first to learn the locked handle.
+    /// With `sender_allowance`, the sender may decrypt its new balance.
+    pub fn lock_htlc(
+        ctx: Context<LockHtlc>,
+        hashlock: [u8; 32],
+        encrypted_amount: u128,
+        expiry_slot: u64,
+    ) -> Result<()> {
+        require!(expiry_slot > Clock::get()?.slot, CustomError::InvalidExpiry);
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.sender.to_account_info();
+
+        // locked = balance >= amount ? amount : 0
+        let amount = Euint128::wrap(encrypted_amount);
+        let sender_balance = &mut ctx.accounts.sender_balance;
+        let zero = as_euint128(inco_op(&inco, &signer), 0)?;
+        let sufficient = e_ge(inco_op(&inco, &signer), sender_balance.encrypted_balance, amount, 0)?;
+        let locked = e_select(inco_op(&inco, &signer), sufficient, amount, zero, 0)?;
+        sender_balance.encrypted_balance =
+            e_sub(inco_op(&inco, &signer), sender_balance.encrypted_balance, locked, 0)?;
+        let system_program = ctx.accounts.system_program.to_account_info();
+        grant_owner(
+            &inco,
+            &signer,
+            Some(&system_program),
+            ctx.accounts.sender_allowance.as_deref(),
+            Some(&signer),
+            sender_balance.encrypted_balance,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sender = ctx.accounts.sender.key();
+        escrow.recipient = ctx.accounts.recipient.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.hashlock = hashlock;
+        escrow.amount = locked;
+        escrow.expiry_slot = expiry_slot;
+        escrow.bump = ctx.bumps.escrow;
+
+        if let Some(recipient_allowance) = &ctx.accounts.recipient_allowance {
+            grant(
+                &inco,
+                &signer,
+                &system_program,
+                recipient_allowance,
+                &ctx.accounts.recipient,
+                locked.0,
+            )?;
+        }
+
+        msg!("Locked HTLC for {} until slot {}", escrow.recipient, expiry_slot);
+        Ok(())
+    }
+
+    /// Claim a locked escrow by revealing the SHA-256 preimage
+    /// Credits the recipient and closes the escrow (rent back to sender)
+    /// With `recipient_allowance`, the recipient may decrypt its new balance
+    pub fn claim_htlc(ctx: Context<ClaimHtlc>, preimage: Vec<u8>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(Clock::get()?.slot < escrow.expiry_slot, CustomError::HtlcExpired);
+        require!(
+            anchor_lang::solana_program::hash::hash(&preimage).to_bytes() == escrow.hashlock,
+            CustomError::InvalidPreimage
+        );
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.recipient.to_account_info();
+
+        let recipient_balance = &mut ctx.accounts.recipient_balance;
+        recipient_balance.encrypted_balance =
+            credit(&inco, &signer, recipient_balance.encrypted_balance, escrow.amount)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.recipient_allowance.as_deref(),
+            Some(&signer),
+            recipient_balance.encrypted_balance,
+        )?;
+
+        emit!(HtlcClaimed {
+            hashlock: escrow.hashlock,
+            preimage,
+            recipient: escrow.recipient,
+        });
+
+        msg!("Claimed HTLC for {}", escrow.recipient);
+        Ok(())
+    }
+
+    /// Refund an expired escrow to the sender and close it
+    /// With `sender_allowance`, the sender may decrypt its new balance
+    pub fn refund_htlc(ctx: Context<RefundHtlc>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(Clock::get()?.slot >= escrow.expiry_slot, CustomError::HtlcNotExpired);
+
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.sender.to_account_info();
+
+        let sender_balance = &mut ctx.accounts.sender_balance;
+        sender_balance.encrypted_balance =
+            e_add(inco_op(&inco, &signer), sender_balance.encrypted_balance, escrow.amount, 0)?;
+        grant_owner(
+            &inco,
+            &signer,
+            ctx.accounts.system_program.as_deref(),
+            ctx.accounts.sender_allowance.as_deref(),
+            Some(&signer),
+            sender_balance.encrypted_balance,
+        )?;
+
+        msg!("Refunded HTLC to {}", escrow.sender);
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -544,6 +1109,66 @@ impl SolVault {
     pub const LEN: usize = 1 + 1; // 2 bytes (minimal, lamports are in the account itself)
 }
 
+/// Confidential vesting grant (PDA)
+/// Seeds: ["vesting", grantor, beneficiary, mint, vesting_id]
+/// Amounts are encrypted; the schedule is public
+#[account]
+pub struct VestingAccount {
+    /// Who funded the grant (and may revoke it)
+    pub grantor: Pubkey,
+    /// Who receives vested tokens
+    pub beneficiary: Pubkey,
+    /// Mint of the locked tokens
+    pub mint: Pubkey,
+    /// Grantor-chosen id, allows several grants per pair
+    pub vesting_id: u64,
+    /// FHE encrypted amount locked in the grant
+    pub total_amount: Euint128,
+    /// FHE encrypted amount already released to the beneficiary
+    pub released_amount: Euint128,
+    /// Unix timestamp vesting starts accruing from
+    pub start_ts: i64,
+    /// Unix timestamp before which nothing can be claimed
+    pub cliff_ts: i64,
+    /// Unix timestamp at which the grant is fully vested
+    pub end_ts: i64,
+    /// Whether the grantor may revoke the unvested portion
+    pub revocable: bool,
+    /// Whether the grant has been revoked
+    pub revoked: bool,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl VestingAccount {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 16 + 16 + 8 + 8 + 8 + 1 + 1 + 1; // 163 bytes
+}
+
+/// Hash-time-locked confidential escrow (PDA)
+/// Seeds: ["htlc", sender, hashlock]
+/// Closed on claim or refund
+#[account]
+pub struct HtlcEscrow {
+    /// Who locked the funds (receives refund and rent)
+    pub sender: Pubkey,
+    /// Who can claim with the preimage
+    pub recipient: Pubkey,
+    /// Mint of the locked tokens
+    pub mint: Pubkey,
+    /// SHA-256 hash of the secret preimage
+    pub hashlock: [u8; 32],
+    /// FHE encrypted locked amount
+    pub amount: Euint128,
+    /// Slot from which the sender may refund
+    pub expiry_slot: u64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl HtlcEscrow {
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 16 + 8 + 1; // 153 bytes
+}
+
 // ============================================================================
 // Instruction Contexts
 // ============================================================================
@@ -601,6 +1226,27 @@ pub struct IncoTransfer<'info> {
     pub destination: Account<'info, IncoAccount>,
     #[account(mut)]
     pub authority: Signer<'info>,
+    
+    // Memo accounts - only required when an encrypted memo is attached
+    
+    /// Sender wallet (owner of the source account; `authority` may be a delegate)
+    /// CHECK: Validated against source owner
+    #[account(constraint = sender.key() == source.owner @ CustomError::OwnerMismatch)]
+    pub sender: Option<UncheckedAccount<'info>>,
+    /// Recipient wallet (owner of the destination account)
+    /// CHECK: Validated against destination owner
+    #[account(constraint = recipient.key() == destination.owner @ CustomError::OwnerMismatch)]
+    pub recipient: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco allowance PDA for (memo, sender) - validated by Inco Lightning
+    #[account(mut)]
+    pub sender_memo_allowance: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco allowance PDA for (memo, recipient) - validated by Inco Lightning
+    #[account(mut)]
+    pub recipient_memo_allowance: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: Option<AccountInfo<'info>>,
+    pub system_program: Option<Program<'info, System>>,
 }
 
 #[derive(Accounts)]
@@ -854,6 +1500,32 @@ pub struct InitializeUserBalance<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeOwnedBalance<'info> {
+    /// Owner's balance PDA
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + UserBalance::LEN,
+        seeds = [b"user_balance", owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Owner of this balance (may be a PDA signing via CPI)
+    pub owner: Signer<'info>,
+    
+    /// Payer for account creation
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeSolVault<'info> {
     /// The SOL vault PDA (program-owned account)
@@ -967,12 +1639,190 @@ pub struct TransferToUser<'info> {
     /// The mint
     pub mint: Account<'info, IncoMint>,
     
-    /// Source user (authority)
+    /// Source user (authority, pays for memo allowances)
+    #[account(mut)]
     pub source_user: Signer<'info>,
     
     /// Destination user
     /// CHECK: Just need the pubkey
     pub dest_user: AccountInfo<'info>,
+    
+    // Memo accounts - only required when an encrypted memo is attached
+    
+    /// CHECK: Inco allowance PDA for (memo, sender) - validated by Inco Lightning
+    #[account(mut)]
+    pub sender_memo_allowance: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco allowance PDA for (memo, recipient) - validated by Inco Lightning
+    #[account(mut)]
+    pub recipient_memo_allowance: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: Option<AccountInfo<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct BalanceTransfer<'info> {
+    /// Source balance PDA (owned by `owner`)
+    #[account(
+        mut,
+        seeds = [b"user_balance", owner.key().as_ref(), mint.key().as_ref()],
+        bump = source_balance.bump,
+        constraint = source_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub source_balance: Account<'info, UserBalance>,
+    
+    /// Destination balance PDA
+    #[account(
+        mut,
+        seeds = [b"user_balance", dest_balance.user.as_ref(), mint.key().as_ref()],
+        bump = dest_balance.bump,
+        constraint = dest_balance.is_initialized @ CustomError::UninitializedState,
+        constraint = dest_balance.key() != source_balance.key() @ CustomError::InvalidDestination,
+    )]
+    pub dest_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Source owner (signs Inco operations; may be a PDA signing via CPI)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owners decrypt their new balances
+    
+    /// CHECK: Inco allowance PDA for (new source balance, owner) - validated by Inco Lightning
+    #[account(mut)]
+    pub source_allowance: Option<UncheckedAccount<'info>>,
+    /// Destination wallet (owner of the destination balance)
+    /// CHECK: Validated against the destination balance owner
+    #[account(constraint = dest_user.key() == dest_balance.user @ CustomError::OwnerMismatch)]
+    pub dest_user: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco allowance PDA for (new destination balance, destination user) - validated by Inco Lightning
+    #[account(mut)]
+    pub dest_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct BatchTransfer<'info> {
+    /// Source user's balance PDA
+    #[account(
+        mut,
+        seeds = [b"user_balance", source_user.key().as_ref(), mint.key().as_ref()],
+        bump = source_balance.bump,
+        constraint = source_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub source_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Source user (authority, signs Inco operations)
+    #[account(mut)]
+    pub source_user: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required when the per-leg allowances are passed
+    
+    /// CHECK: Inco allowance PDA for (new source balance, source user) - validated by Inco Lightning
+    #[account(mut)]
+    pub source_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+    
+    // remaining_accounts: destination UserBalance PDAs (writable), one per amount,
+    // then optionally BATCH_ALLOW_ACCOUNTS allowance accounts per leg
+}
+
+#[derive(Accounts)]
+pub struct MintToUser<'info> {
+    /// Recipient's balance PDA
+    #[account(
+        mut,
+        seeds = [b"user_balance", user_balance.user.as_ref(), mint.key().as_ref()],
+        bump = user_balance.bump,
+        constraint = user_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    #[account(
+        mut,
+        constraint = mint.is_initialized @ CustomError::UninitializedState,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key()) @ CustomError::OwnerMismatch,
+    )]
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Mint authority (signs Inco operations)
+    #[account(mut)]
+    pub mint_authority: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// Recipient wallet (owner of the balance)
+    /// CHECK: Validated against the balance owner
+    #[account(constraint = user.key() == user_balance.user @ CustomError::OwnerMismatch)]
+    pub user: Option<UncheckedAccount<'info>>,
+    /// CHECK: Inco allowance PDA for (new user balance, user) - validated by Inco Lightning
+    #[account(mut)]
+    pub user_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct BurnFromUser<'info> {
+    /// User's balance PDA
+    #[account(
+        mut,
+        seeds = [b"user_balance", user.key().as_ref(), mint.key().as_ref()],
+        bump = user_balance.bump,
+        constraint = user_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub user_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    #[account(mut, constraint = mint.is_initialized @ CustomError::UninitializedState)]
+    pub mint: Account<'info, IncoMint>,
+    
+    /// User burning from their balance
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// CHECK: Inco allowance PDA for (new user balance, user) - validated by Inco Lightning
+    #[account(mut)]
+    pub user_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintAuthority<'info> {
+    /// The mint
+    #[account(
+        mut,
+        constraint = mint.is_initialized @ CustomError::UninitializedState,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key()) @ CustomError::OwnerMismatch,
+    )]
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Current mint authority
+    pub mint_authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -1095,6 +1945,322 @@ pub struct UnwrapUsdcFromUser<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+// ============================================================================
+// VESTING INSTRUCTION CONTEXTS
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(vesting_id: u64)]
+pub struct CreateVesting<'info> {
+    /// The vesting grant PDA
+    #[account(
+        init,
+        payer = grantor,
+        space = 8 + VestingAccount::LEN,
+        seeds = [
+            b"vesting",
+            grantor.key().as_ref(),
+            beneficiary.key().as_ref(),
+            mint.key().as_ref(),
+            &vesting_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub vesting: Account<'info, VestingAccount>,
+    
+    /// Grantor's balance PDA (source of locked tokens)
+    #[account(
+        mut,
+        seeds = [b"user_balance", grantor.key().as_ref(), mint.key().as_ref()],
+        bump = grantor_balance.bump,
+        constraint = grantor_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub grantor_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Grantor funding the grant
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    
+    /// Beneficiary of the grant
+    /// CHECK: Just need the pubkey
+    pub beneficiary: AccountInfo<'info>,
+    
+    /// CHECK: Inco allowance PDA for (new grantor balance, grantor) - validated by Inco Lightning
+    #[account(mut)]
+    pub grantor_allowance: Option<UncheckedAccount<'info>>,
+    
+    /// System program
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// The vesting grant PDA
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            vesting.grantor.as_ref(),
+            beneficiary.key().as_ref(),
+            mint.key().as_ref(),
+            &vesting.vesting_id.to_le_bytes(),
+        ],
+        bump = vesting.bump,
+    )]
+    pub vesting: Account<'info, VestingAccount>,
+    
+    /// Beneficiary's balance PDA (receives vested tokens)
+    #[account(
+        mut,
+        seeds = [b"user_balance", beneficiary.key().as_ref(), mint.key().as_ref()],
+        bump = beneficiary_balance.bump,
+        constraint = beneficiary_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub beneficiary_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Beneficiary claiming
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// CHECK: Inco allowance PDA for (new beneficiary balance, beneficiary) - validated by Inco Lightning
+    #[account(mut)]
+    pub beneficiary_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    /// The vesting grant PDA
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            grantor.key().as_ref(),
+            vesting.beneficiary.as_ref(),
+            mint.key().as_ref(),
+            &vesting.vesting_id.to_le_bytes(),
+        ],
+        bump = vesting.bump,
+    )]
+    pub vesting: Account<'info, VestingAccount>,
+    
+    /// Grantor's balance PDA (receives the unvested portion)
+    #[account(
+        mut,
+        seeds = [b"user_balance", grantor.key().as_ref(), mint.key().as_ref()],
+        bump = grantor_balance.bump,
+        constraint = grantor_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub grantor_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Grantor revoking the grant
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// CHECK: Inco allowance PDA for (new grantor balance, grantor) - validated by Inco Lightning
+    #[account(mut)]
+    pub grantor_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// ============================================================================
+// HASH-TIME-LOCKED ESCROW INSTRUCTION CONTEXTS
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(hashlock: [u8; 32])]
+pub struct LockHtlc<'info> {
+    /// The escrow PDA
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + HtlcEscrow::LEN,
+        seeds = [b"htlc", sender.key().as_ref(), hashlock.as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, HtlcEscrow>,
+    
+    /// Sender's balance PDA (source of locked tokens)
+    #[account(
+        mut,
+        seeds = [b"user_balance", sender.key().as_ref(), mint.key().as_ref()],
+        bump = sender_balance.bump,
+        constraint = sender_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub sender_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Sender locking funds
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Counterparty allowed to claim
+    /// CHECK: Just need the pubkey
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Inco allowance PDA for (locked amount, recipient) - validated by Inco Lightning
+    #[account(mut)]
+    pub recipient_allowance: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Inco allowance PDA for (new sender balance, sender) - validated by Inco Lightning
+    #[account(mut)]
+    pub sender_allowance: Option<UncheckedAccount<'info>>,
+    
+    /// System program
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimHtlc<'info> {
+    /// The escrow PDA (closed, rent returned to sender)
+    #[account(
+        mut,
+        close = sender,
+        seeds = [b"htlc", escrow.sender.as_ref(), escrow.hashlock.as_ref()],
+        bump = escrow.bump,
+        has_one = sender @ CustomError::OwnerMismatch,
+        has_one = recipient @ CustomError::OwnerMismatch,
+        has_one = mint @ CustomError::MintMismatch,
+    )]
+    pub escrow: Account<'info, HtlcEscrow>,
+    
+    /// Recipient's balance PDA
+    #[account(
+        mut,
+        seeds = [b"user_balance", recipient.key().as_ref(), mint.key().as_ref()],
+        bump = recipient_balance.bump,
+        constraint = recipient_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub recipient_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Recipient claiming the escrow
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// Original sender (receives escrow rent)
+    /// CHECK: Validated via has_one on escrow
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// CHECK: Inco allowance PDA for (new recipient balance, recipient) - validated by Inco Lightning
+    #[account(mut)]
+    pub recipient_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundHtlc<'info> {
+    /// The escrow PDA (closed, rent returned to sender)
+    #[account(
+        mut,
+        close = sender,
+        seeds = [b"htlc", sender.key().as_ref(), escrow.hashlock.as_ref()],
+        bump = escrow.bump,
+        has_one = sender @ CustomError::OwnerMismatch,
+        has_one = mint @ CustomError::MintMismatch,
+    )]
+    pub escrow: Account<'info, HtlcEscrow>,
+    
+    /// Sender's balance PDA (receives the refund)
+    #[account(
+        mut,
+        seeds = [b"user_balance", sender.key().as_ref(), mint.key().as_ref()],
+        bump = sender_balance.bump,
+        constraint = sender_balance.is_initialized @ CustomError::UninitializedState,
+    )]
+    pub sender_balance: Account<'info, UserBalance>,
+    
+    /// The mint
+    pub mint: Account<'info, IncoMint>,
+    
+    /// Sender reclaiming the escrow
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_PROGRAM_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+    
+    // Allowance accounts - only required to let the owner decrypt the new balance
+    
+    /// CHECK: Inco allowance PDA for (new sender balance, sender) - validated by Inco Lightning
+    #[account(mut)]
+    pub sender_allowance: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Emitted on every transfer / transfer_to_user
+#[event]
+pub struct ConfidentialTransfer {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    /// Encrypted memo handle, decryptable only by sender and recipient
+    pub memo: Option<Euint128>,
+}
+
+/// Emitted when an HTLC is claimed; relayers use the preimage on the EVM side
+#[event]
+pub struct HtlcClaimed {
+    pub hashlock: [u8; 32],
+    pub preimage: Vec<u8>,
+    pub recipient: Pubkey,
+}
+
+/// Emitted once per leg of a batch_transfer
+#[event]
+pub struct BatchTransferLeg {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    /// Encrypted flag: true if the leg moved the requested amount
+    pub success: Ebool,
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -1113,5 +2279,166 @@ pub enum CustomError {
     OwnerMismatch,
     #[msg("Insufficient funds")]
     InsufficientFunds,
+    #[msg("Batch contains no transfers")]
+    EmptyBatch,
+    #[msg("Batch exceeds maximum number of transfers")]
+    BatchTooLarge,
+    #[msg("Number of amounts does not match number of destinations")]
+    BatchLengthMismatch,
+    #[msg("Invalid destination balance account")]
+    InvalidDestination,
+    #[msg("Encrypted memo requires the memo accounts")]
+    MissingMemoAccounts,
+    #[msg("Allowances require the owner, allowance and system program accounts")]
+    MissingAllowanceAccounts,
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+    #[msg("Vesting cliff not reached")]
+    CliffNotReached,
+    #[msg("Vesting grant is not revocable")]
+    VestingNotRevocable,
+    #[msg("Vesting grant already revoked")]
+    VestingAlreadyRevoked,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Preimage does not match hashlock")]
+    InvalidPreimage,
+    #[msg("HTLC has expired")]
+    HtlcExpired,
+    #[msg("HTLC has not expired yet")]
+    HtlcNotExpired,
+}
+
+// ============================================================================
+// Inco Lightning Helpers
+// ============================================================================
+
+/// Build a CPI context for a single Inco Lightning operation
+fn inco_op<'info>(
+    inco_lightning_program: &AccountInfo<'info>,
+    signer: &AccountInfo<'info>,
+) -> CpiContext<'static, 'static, 'static, 'info, Operation<'info>> {
+    CpiContext::new(
+        inco_lightning_program.clone(),
+        Operation { signer: signer.clone() },
+    )
 }
 
+/// Add `amount` to an encrypted balance
+/// A never-written balance holds the zero handle, which Inco rejects as
+/// uninitialized, so it is treated as an encrypted zero (like ConfidentialERC20)
+fn credit<'info>(
+    inco_lightning_program: &AccountInfo<'info>,
+    signer: &AccountInfo<'info>,
+    balance: Euint128,
+    amount: Euint128,
+) -> Result<Euint128> {
+    if !balance.is_initialized() {
+        return Ok(amount);
+    }
+    e_add(inco_op(inco_lightning_program, signer), balance, amount, 0)
+}
+
+/// Encrypted amount of a grant vested at `now` (caller checks the cliff)
+/// Uses a public fixed-point fraction so only e_mul/e_shr are needed
+fn vested_amount<'info>(
+    inco: &AccountInfo<'info>,
+    signer: &AccountInfo<'info>,
+    vesting: &VestingAccount,
+    now: i64,
+) -> Result<Euint128> {
+    if now >= vesting.end_ts {
+        return Ok(vesting.total_amount);
+    }
+
+    let elapsed = now.saturating_sub(vesting.start_ts).max(0) as u128;
+    let duration = (vesting.end_ts - vesting.start_ts) as u128;
+    let fraction = (elapsed << VESTING_FRACTION_BITS) / duration;
+
+    let fraction = as_euint128(inco_op(inco, signer), fraction)?;
+    let shift = as_euint128(inco_op(inco, signer), VESTING_FRACTION_BITS as u128)?;
+    let scaled = e_mul(inco_op(inco, signer), vesting.total_amount, fraction, 0)?;
+    e_shr(inco_op(inco, signer), scaled, shift, 0)
+}
+
+/// Accounts needed to attach an encrypted memo to a transfer
+struct MemoAccounts<'a, 'info> {
+    inco_lightning_program: Option<&'a AccountInfo<'info>>,
+    sender_allowance: Option<&'a AccountInfo<'info>>,
+    recipient_allowance: Option<&'a AccountInfo<'info>>,
+    system_program: Option<&'a AccountInfo<'info>>,
+    /// Transaction signer paying for the memo (owner or delegate)
+    signer: &'a AccountInfo<'info>,
+    /// Validated owner of the source balance
+    sender: Option<&'a AccountInfo<'info>>,
+    recipient: Option<&'a AccountInfo<'info>>,
+}
+
+/// Create a memo handle from client ciphertext and grant decryption
+/// to the sender and recipient only
+/// Both allowances or neither: without them nothing is granted, which lets
+/// clients simulate the transfer to learn the memo handle first
+fn create_memo(accounts: MemoAccounts<'_, '_>, ciphertext: Vec<u8>) -> Result<Euint128> {
+    let missing = || error!(CustomError::MissingMemoAccounts);
+    let inco = accounts.inco_lightning_program.ok_or_else(missing)?;
+    let system_program = accounts.system_program.ok_or_else(missing)?;
+    let sender = accounts.sender.ok_or_else(missing)?;
+    let recipient = accounts.recipient.ok_or_else(missing)?;
+
+    let memo = new_euint128(inco_op(inco, accounts.signer), ciphertext, 0)?;
+
+    match (accounts.sender_allowance, accounts.recipient_allowance) {
+        (Some(sender_allowance), Some(recipient_allowance)) => {
+            for (allowance, allowed) in [
+                (sender_allowance, sender),
+                (recipient_allowance, recipient),
+            ] {
+                grant(inco, accounts.signer, system_program, allowance, allowed, memo.0)?;
+            }
+        }
+        (None, None) => {}
+        _ => return Err(missing()),
+    }
+
+    Ok(memo)
+}
+
+/// Grant `owner` decryption access to a new balance handle if its allowance is passed
+/// Without one nothing is granted, so CPI callers on a tight trace budget can skip it
+fn grant_owner<'info>(
+    inco_lightning_program: &AccountInfo<'info>,
+    signer: &AccountInfo<'info>,
+    system_program: Option<&AccountInfo<'info>>,
+    allowance: Option<&AccountInfo<'info>>,
+    owner: Option<&AccountInfo<'info>>,
+    balance: Euint128,
+) -> Result<()> {
+    let Some(allowance) = allowance else {
+        return Ok(());
+    };
+    let missing = || error!(CustomError::MissingAllowanceAccounts);
+    let system_program = system_program.ok_or_else(missing)?;
+    let owner = owner.ok_or_else(missing)?;
+    grant(inco_lightning_program, signer, system_program, allowance, owner, balance.0)
+}
+
+/// Grant `allowed` decryption access to an encrypted handle
+fn grant<'info>(
+    inco_lightning_program: &AccountInfo<'info>,
+    signer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    allowance: &AccountInfo<'info>,
+    allowed: &AccountInfo<'info>,
+    handle: u128,
+) -> Result<()> {
+    let cpi_ctx = CpiContext::new(
+        inco_lightning_program.clone(),
+        Allow {
+            allowance_account: allowance.clone(),
+            signer: signer.clone(),
+            allowed_address: allowed.clone(),
+            system_program: system_program.clone(),
+        },
+    );
+    allow(cpi_ctx, handle, true, allowed.key())
+}
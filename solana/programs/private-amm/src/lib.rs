@@ -1,9 +1,131 @@
 use anchor_lang::prelude::*;
-use inco_lightning::types::Euint128;
+use inco_lightning::cpi::accounts::{Allow, Operation};
+use inco_lightning::cpi::{allow, as_euint128, e_add, e_and, e_eq, e_ge, e_lt, e_mul, e_or, e_select, e_shl, e_shr, e_sub};
+use inco_lightning::types::{Ebool, Euint128};
+use inco_token::cpi::accounts::{BalanceTransfer, BurnFromUser, InitializeOwnedBalance, MintToUser};
+use inco_token::program::IncoToken;
+use inco_token::{COption, IncoMint, UserBalance};
+use std::cell::RefCell;
 
 // Inco Lightning program ID (from their docs)
 pub const INCO_LIGHTNING_ID: Pubkey = pubkey!("5sjEbPiqgZrYwR31ahR6Uk9wf5awoX61YGg7jExQSwaj");
 
+// Pyth Solana receiver program ID (owner of PriceUpdateV2 accounts)
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Basis-point denominator for fees
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Maximum swap fee (10%)
+pub const MAX_FEE_BPS: u16 = 1000;
+
+/// Maximum protocol share of the swap fee (50%)
+pub const MAX_PROTOCOL_FEE_SHARE_BPS: u16 = 5000;
+
+/// Remaining accounts per swap_route hop: pool, output mint, input vault, output vault
+pub const ROUTE_HOP_ACCOUNTS: usize = 4;
+
+/// Maximum number of hops in one swap_route
+/// Solana caps a transaction at 64 instruction-trace entries, and every Fhe
+/// operation and custody transfer is a CPI that takes one or more. Two
+/// constant-product hops with the protocol fee on use 63 of them, and each
+/// further hop would add its curve check, fee split and vault transfer (over
+/// 20 entries), so a third hop cannot fit. Other curves cost more per hop and
+/// are rejected with UnsupportedRouteHop
+pub const MAX_ROUTE_HOPS: usize = 2;
+
+/// StableSwap amplification coefficient bounds
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 10_000;
+
+/// Largest factor by which one ramp may raise or lower the amplification
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+/// Shortest allowed amplification ramp (1 day)
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+
+/// Largest reserve or LP supply the constant-product checks are sound for
+/// Every check multiplies two such values, which cannot reach 2^128 below it.
+/// Pool balances are drawn from confidential mints, so pooling a mint whose
+/// supply may exceed a u64 voids the checks; no swap has the trace entries
+/// to spare for checking it (see MAX_ROUTE_HOPS)
+pub const MAX_POOL_BALANCE: u128 = u64::MAX as u128;
+
+/// Largest reserve, invariant or hint a StableSwap check accepts
+/// Keeps every product the checks form below 2^128, so none can wrap
+pub const STABLE_VALUE_BOUND: u128 = (1 << 63) - 1;
+
+/// How far below the exact invariant sync_invariant may store D
+/// Covers the flooring in its hints; at most this much surplus is left to arbitrage
+pub const STABLE_SYNC_TOLERANCE: u128 = 64;
+
+/// Fixed-point precision (bits) used to apply public fractions to encrypted values
+/// x * (num / den) ~= (x * floor((num << FRACTION_BITS) / den)) >> FRACTION_BITS
+pub const FRACTION_BITS: u32 = 16;
+
+/// Fixed-point precision (bits) of the protocol's cut of each swap input
+/// fee_bps * share_bps / 10^8 is below 1, so the factor stays below 2^48 and inputs
+/// up to 2^80 cannot wrap. FRACTION_BITS would round any cut under 2^-16 (e.g.
+/// a 0.05% protocol share of a 0.3% fee) down to zero
+pub const PROTOCOL_FEE_BITS: u32 = 48;
+
+/// Fixed-point precision (bits) of an encrypted LP share (lp / lp_supply)
+/// Since lp <= lp_supply the share is at most 2^SHARE_BITS
+pub const SHARE_BITS: u32 = 63;
+
+/// Widest oracle confidence interval a pool may accept (10% of the price)
+pub const MAX_ORACLE_CONFIDENCE_BPS: u16 = 1000;
+
+/// Significant bits kept when an oracle price is applied to an encrypted amount
+/// Amounts are u64, so amount * rate stays below 2^128
+pub const RATE_BITS: u32 = 64;
+
+/// Slots per batch-auction epoch (~1 minute)
+pub const BATCH_EPOCH_SLOTS: u64 = 150;
+
+/// Orders one batch can hold
+pub const MAX_BATCH_ORDERS: usize = 16;
+
+/// Slots after an epoch ends during which it may be settled (~10 minutes)
+/// Once this passes unsettled, each order can be refunded instead
+pub const BATCH_SETTLE_WINDOW_SLOTS: u64 = 1500;
+
+/// Fixed-point precision (bits) of the encrypted batch clearing price
+/// Prices are hinted as u64, so the reserve ratio must stay below 2^32 either way
+pub const BATCH_PRICE_BITS: u32 = 32;
+
+/// Fixed-point precision (bits) of an encrypted limit price (output per input unit)
+/// The fixed-point value must fit in 64 bits so amount * price fits in 128
+pub const LIMIT_PRICE_BITS: u32 = 32;
+
+/// Tick bounds for concentrated liquidity; sqrt prices stay within (2^-29, 2^29)
+pub const MIN_TICK: i32 = -400_000;
+pub const MAX_TICK: i32 = 400_000;
+
+/// Initialized ticks per concentrated pool; swap cost grows linearly with it
+pub const MAX_TICKS: usize = 8;
+
+/// Fixed-point precision (bits) of sqrt prices: sqrt(1.0001^tick) * 2^SQRT_PRICE_BITS
+pub const SQRT_PRICE_BITS: u32 = 32;
+
+/// Fixed-point precision (bits) of fee growth per unit of liquidity
+pub const FEE_GROWTH_BITS: u32 = 32;
+
+/// LP tokens locked forever by a pool's first deposit, so the share price
+/// cannot be inflated from a dust supply
+pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
+/// Pool addresses per registry page
+pub const REGISTRY_PAGE_SIZE: usize = 32;
+
+/// Fixed-point precision (bits) of the encrypted reward-per-LP accumulator
+pub const REWARD_PER_LP_BITS: u32 = 32;
+
+/// Fixed-point precision (bits) of the hinted inverse staked LP supply a RewardVault accrues with
+/// The locked MINIMUM_LIQUIDITY (> 2^9) sits in no position and can never be tokenized,
+/// so the staked supply never drops below it and the inverse stays below 2^64
+pub const REWARD_INVERSE_BITS: u32 = 73;
+
 declare_id!("2UgU5dyB9Z7XEGKn3SW8CFz794ajVrSo4fuEJMQdM1t7");
 
 /// Private AMM Program
@@ -13,13 +135,85 @@ declare_id!("2UgU5dyB9Z7XEGKn3SW8CFz794ajVrSo4fuEJMQdM1t7");
 pub mod private_amm {
     use super::*;
 
+    /// Create the factory config (once); the caller becomes its admin
+    /// Only the program's upgrade authority may call this, so the deployment
+    /// cannot be front-run into someone else's factory
+    pub fn initialize_factory(
+        ctx: Context<InitializeFactory>,
+        default_fee_bps: u16,
+        creation_fee_lamports: u64,
+    ) -> Result<()> {
+        require!(default_fee_bps <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        
+        let factory = &mut ctx.accounts.factory;
+        factory.admin = ctx.accounts.admin.key();
+        factory.default_fee_bps = default_fee_bps;
+        factory.protocol_fee_on = false;
+        factory.protocol_fee_share_bps = 0;
+        factory.fee_recipient = ctx.accounts.admin.key();
+        factory.creation_fee_lamports = creation_fee_lamports;
+        factory.pool_count = 0;
+        factory.bump = ctx.bumps.factory;
+        
+        msg!("Factory initialized (default fee {} bps)", default_fee_bps);
+        Ok(())
+    }
+
+    /// Update the factory defaults (factory admin only)
+    /// The protocol fee switch applies to pools created afterwards
+    pub fn update_factory(
+        ctx: Context<UpdateFactory>,
+        default_fee_bps: u16,
+        protocol_fee_on: bool,
+        protocol_fee_share_bps: u16,
+        fee_recipient: Pubkey,
+        creation_fee_lamports: u64,
+    ) -> Result<()> {
+        require!(default_fee_bps <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        require!(protocol_fee_share_bps <= MAX_PROTOCOL_FEE_SHARE_BPS, AmmError::ProtocolFeeTooHigh);
+        
+        let factory = &mut ctx.accounts.factory;
+        factory.default_fee_bps = default_fee_bps;
+        factory.protocol_fee_on = protocol_fee_on;
+        factory.protocol_fee_share_bps = protocol_fee_share_bps;
+        factory.fee_recipient = fee_recipient;
+        factory.creation_fee_lamports = creation_fee_lamports;
+        
+        msg!("Factory updated");
+        Ok(())
+    }
+
+    /// Hand the factory to a new admin (factory admin only)
+    pub fn set_factory_admin(ctx: Context<UpdateFactory>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.factory.admin = new_admin;
+        
+        msg!("Factory admin set to {}", new_admin);
+        Ok(())
+    }
+
     /// Initialize a new private liquidity pool
     /// Creates a trading pair with encrypted reserves
+    /// Mints must be passed in canonical (ascending) order so each pair has one pool
+    /// 
+    /// Pools are created through the factory: the creator pays its creation fee,
+    /// `fee_bps` defaults to the factory's, and the pool is appended to the registry
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
-        fee_bps: u16,  // Fee in basis points (e.g., 30 = 0.3%)
+        fee_bps: Option<u16>,  // Fee in basis points (e.g., 30 = 0.3%), None for the factory default
+        pool_type: PoolType,   // Pricing curve
+        amp: u64,              // StableSwap amplification (ignored for constant product)
     ) -> Result<()> {
-        require!(fee_bps <= 1000, AmmError::FeeTooHigh); // Max 10%
+        let factory = &ctx.accounts.factory;
+        let fee_bps = fee_bps.unwrap_or(factory.default_fee_bps);
+        require!(fee_bps <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        let amp = match pool_type {
+            PoolType::ConstantProduct | PoolType::Oracle | PoolType::Concentrated => 0,
+            PoolType::StableSwap => {
+                require!((MIN_AMP..=MAX_AMP).contains(&amp), AmmError::InvalidAmp);
+                amp
+            }
+        };
+        let now = Clock::get()?.unix_timestamp;
         
         let pool = &mut ctx.accounts.pool;
         pool.token_a_mint = ctx.accounts.token_a_mint.key();
@@ -32,6 +226,78 @@ pub mod private_amm {
         pool.authority = ctx.accounts.authority.key();
         pool.bump = ctx.bumps.pool;
         pool.is_initialized = true;
+        pool.vault_a = ctx.accounts.vault_a.key();
+        pool.vault_b = ctx.accounts.vault_b.key();
+        pool.pending_authority = Pubkey::default();
+        pool.paused = false;
+        if factory.protocol_fee_on {
+            pool.protocol_fee_share_bps = factory.protocol_fee_share_bps;
+            pool.fee_recipient = factory.fee_recipient;
+        } else {
+            pool.protocol_fee_share_bps = 0;
+            pool.fee_recipient = ctx.accounts.authority.key();
+        }
+        pool.protocol_fees_a = Euint128::default();
+        pool.protocol_fees_b = Euint128::default();
+        pool.pool_type = pool_type;
+        pool.initial_amp = amp;
+        pool.future_amp = amp;
+        pool.initial_amp_time = now;
+        pool.future_amp_time = now;
+        pool.oracle = Pubkey::default();
+        pool.oracle_inverted = false;
+        pool.max_price_age = 0;
+        pool.max_confidence_bps = 0;
+        pool.decimals_a = ctx.accounts.token_a_mint.decimals;
+        pool.decimals_b = ctx.accounts.token_b_mint.decimals;
+        pool.lp_mint = Pubkey::default();
+        pool.rewards_enabled = false;
+        pool.locked_liquidity = 0;
+        pool.invariant_ann = Euint128::default();
+        pool.lp_fees_a = Euint128::default();
+        pool.lp_fees_b = Euint128::default();
+        pool.tokenized_lp_supply = Euint128::default();
+        pool.oracle_feed_id = [0; 32];
+        
+        // Create the pool-owned inco_token balances that custody the reserves
+        let token_a_mint = ctx.accounts.token_a_mint.key();
+        let token_b_mint = ctx.accounts.token_b_mint.key();
+        let bump = [ctx.bumps.pool];
+        let seeds: &[&[u8]] = &[b"pool", token_a_mint.as_ref(), token_b_mint.as_ref(), &bump];
+        let signer_seeds = &[seeds];
+        let vaults = [
+            (&ctx.accounts.vault_a, &ctx.accounts.token_a_mint),
+            (&ctx.accounts.vault_b, &ctx.accounts.token_b_mint),
+        ];
+        for (vault, mint) in vaults {
+            let cpi_accounts = InitializeOwnedBalance {
+                user_balance: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                owner: ctx.accounts.pool.to_account_info(),
+                payer: ctx.accounts.authority.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.inco_token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            inco_token::cpi::initialize_owned_balance(cpi_ctx)?;
+        }
+        
+        // Pay the creation fee and register the pool
+        let creation_fee = ctx.accounts.factory.creation_fee_lamports;
+        if creation_fee > 0 {
+            let cpi_accounts = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.fee_collector.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+            anchor_lang::system_program::transfer(cpi_ctx, creation_fee)?;
+        }
+        let factory = &mut ctx.accounts.factory;
+        let page = &mut ctx.accounts.registry_page;
+        page.page = factory.pool_count / REGISTRY_PAGE_SIZE as u64;
+        page.bump = ctx.bumps.registry_page;
+        page.pools.push(ctx.accounts.pool.key());
+        factory.pool_count += 1;
         
         msg!("Initialized private pool: {} <-> {}", 
             ctx.accounts.token_a_mint.key(), 
@@ -41,33 +307,164 @@ pub mod private_amm {
 
     /// Add liquidity to the pool (encrypted amounts)
     /// User deposits encrypted amounts of both tokens and receives encrypted LP tokens
+    /// 
+    /// LP minting follows Uniswap v2:
+    /// - first deposit:  lp = sqrt(amount_a * amount_b) - MINIMUM_LIQUIDITY (locked)
+    /// - later deposits: lp = min(amount_a * S / reserve_a, amount_b * S / reserve_b)
+    /// Any excess of the non-limiting token stays in the pool (benefits LPs)
+    /// 
+    /// The caller supplies lp before the lock as `encrypted_lp_hint` (the floor of
+    /// the root or quotient above), checked with Fhe::check_root or
+    /// Fhe::check_quotient; a wrong hint fails the deposit like a slippage miss.
+    /// 
+    /// `seed_pool` picks the first-deposit formula. It is accepted while the LP
+    /// supply is still zero, so a seed that failed can simply be retried; a seed
+    /// into a funded pool fails, as does a later deposit into an empty one.
+    /// Each amount must fit in a u64, and the LP supply never exceeds
+    /// sqrt(reserve_a * reserve_b), so while the reserves stay within
+    /// MAX_POOL_BALANCE none of the products above can wrap.
+    /// 
+    /// StableSwap pools price shares against their reserves plus banked LP fees.
+    /// A seed must deposit equal amounts (each at most STABLE_VALUE_BOUND / 2):
+    /// balanced reserves hold D = amount_a + amount_b exactly at any amplification,
+    /// so the pool is swappable at once, at the current amplification. A later
+    /// deposit grows D by `encrypted_invariant_hint`, checked to be at most
+    /// D * lp / S so D never outruns the reserves (ignored by other pool types).
+    /// 
+    /// Balances, bounds, hint and `encrypted_min_lp_out` are all checked before
+    /// anything moves; the amounts are pulled into the pool vaults only if every
+    /// check passes, so a failed deposit moves nothing and mints nothing
+    #[allow(clippy::too_many_arguments)]
     pub fn add_liquidity(
         ctx: Context<AddLiquidity>,
-        encrypted_amount_a: u128,  // Encrypted amount of token A
-        encrypted_amount_b: u128,  // Encrypted amount of token B
+        encrypted_amount_a: u128,    // Encrypted amount of token A
+        encrypted_amount_b: u128,    // Encrypted amount of token B
+        encrypted_min_lp_out: u128,  // Encrypted minimum LP tokens to receive
+        encrypted_lp_hint: u128,     // Encrypted LP tokens the deposit is worth (see above)
+        encrypted_invariant_hint: u128, // Encrypted StableSwap invariant growth (see above)
+        seed_pool: bool,             // First deposit into an empty pool (see above)
+        deadline_slot: u64,          // Reject if executed after this slot
     ) -> Result<()> {
+        check_deadline(deadline_slot)?;
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
         let pool = &mut ctx.accounts.pool;
-        let user_position = &mut ctx.accounts.user_position;
+        require!(seed_pool || pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
         
-        // Update pool reserves (encrypted addition via wrap)
-        // In production, use Inco CPI: e_add(reserve_a, amount_a)
-        pool.reserve_a = Euint128::wrap(encrypted_amount_a);
-        pool.reserve_b = Euint128::wrap(encrypted_amount_b);
+        let amount_a = Euint128::wrap(encrypted_amount_a);
+        let amount_b = Euint128::wrap(encrypted_amount_b);
+        let funded = fhe.and(
+            fhe.covers(&ctx.accounts.user_balance_a, amount_a)?,
+            fhe.covers(&ctx.accounts.user_balance_b, amount_b)?,
+        )?;
+        let stable = pool.pool_type == PoolType::StableSwap;
+        // A StableSwap seed's invariant is the sum of the amounts (see above)
+        let cap = if stable && seed_pool { STABLE_VALUE_BOUND / 2 } else { MAX_POOL_BALANCE };
+        let bounded = fhe.ge(fhe.constant(cap)?, fhe.or(amount_a, amount_b)?)?;
+        let mut ok = fhe.and(funded, bounded)?;
         
-        // Update k constant: k = reserve_a * reserve_b
-        // In production, use Inco CPI: e_mul(reserve_a, reserve_b)
-        // For now, store a placeholder
-        pool.k_constant = Euint128::wrap(encrypted_amount_a);
+        let user_position = &mut ctx.accounts.user_position;
+        let rewards = ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards);
+        settle_rewards(&fhe, pool, rewards, user_position, true)?;
         
         // Mint LP tokens to user (encrypted)
-        // LP amount = sqrt(amount_a * amount_b) for initial deposit
-        // For subsequent deposits, proportional to contribution
-        user_position.lp_amount = Euint128::wrap(encrypted_amount_a);
+        let min_lp_out = Euint128::wrap(encrypted_min_lp_out);
+        let lp = Euint128::wrap(encrypted_lp_hint);
+        let zero = fhe.constant(0)?;
+        let mut invariant = None;
+        let minted = if seed_pool {
+            // The LP supply handle is only unset before the first seed
+            if pool.lp_supply.is_initialized() {
+                ok = fhe.and(ok, fhe.eq(pool.lp_supply, zero)?)?;
+            }
+            let product = fhe.mul(amount_a, amount_b)?;
+            let locked = fhe.constant(MINIMUM_LIQUIDITY as u128)?;
+            ok = fhe.and(ok, fhe.check_root(product, lp)?)?;
+            ok = fhe.and(ok, fhe.ge(lp, fhe.add(locked, min_lp_out)?)?)?;
+            pool.locked_liquidity = MINIMUM_LIQUIDITY;
+            if stable {
+                ok = fhe.and(ok, fhe.eq(amount_a, amount_b)?)?;
+                invariant = Some(fhe.add(amount_a, amount_b)?);
+            }
+            fhe.sub(lp, locked)?
+        } else {
+            // Banked StableSwap fees belong to the current LPs
+            let (reserve_a, reserve_b) = if stable {
+                (fhe.accumulate(pool.lp_fees_a, pool.reserve_a)?, fhe.accumulate(pool.lp_fees_b, pool.reserve_b)?)
+            } else {
+                (pool.reserve_a, pool.reserve_b)
+            };
+            // a / Ra <= b / Rb  <=>  a * Rb <= b * Ra, so only one quotient is checked
+            let a_times_rb = fhe.mul(amount_a, reserve_b)?;
+            let b_times_ra = fhe.mul(amount_b, reserve_a)?;
+            let a_limits = fhe.ge(b_times_ra, a_times_rb)?;
+            let amount = fhe.select(a_limits, amount_a, amount_b)?;
+            let reserve = fhe.select(a_limits, reserve_a, reserve_b)?;
+            let numerator = fhe.mul(amount, pool.lp_supply)?;
+            ok = fhe.and(ok, fhe.check_quotient(numerator, reserve, lp, MAX_POOL_BALANCE)?)?;
+            ok = fhe.and(ok, fhe.ge(lp, min_lp_out)?)?;
+            if stable {
+                // Both balances grow by at least lp / S, and so does their invariant
+                let growth = Euint128::wrap(encrypted_invariant_hint);
+                let grown = fhe.add(pool.k_constant, growth)?;
+                let proportional = fhe.ge(fhe.mul(pool.k_constant, lp)?, fhe.mul(growth, pool.lp_supply)?)?;
+                let bounded = fhe.ge(fhe.constant(STABLE_VALUE_BOUND)?, fhe.or(growth, grown)?)?;
+                ok = fhe.and(ok, fhe.and(proportional, bounded)?)?;
+                invariant = Some(grown);
+            }
+            lp
+        };
+        let minted = fhe.select(ok, minted, zero)?;
+        let supply_added = fhe.select(ok, lp, zero)?;
+        
+        // Conditional execution - a failed check pulls nothing
+        let kept_a = fhe.select(ok, amount_a, zero)?;
+        let kept_b = fhe.select(ok, amount_b, zero)?;
+        custody.transfer(
+            &ctx.accounts.user_balance_a.to_account_info(),
+            &ctx.accounts.vault_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &signer,
+            &[],
+            kept_a,
+        )?;
+        custody.transfer(
+            &ctx.accounts.user_balance_b.to_account_info(),
+            &ctx.accounts.vault_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &signer,
+            &[],
+            kept_b,
+        )?;
+        
+        // Update pool reserves (encrypted addition)
+        pool.reserve_a = fhe.accumulate(pool.reserve_a, kept_a)?;
+        pool.reserve_b = fhe.accumulate(pool.reserve_b, kept_b)?;
+        
+        // Update k constant: k = reserve_a * reserve_b (StableSwap: invariant D)
+        // A failed first StableSwap seed leaves D = 0, which keeps swaps closed
+        let kept_k = if pool.k_constant.is_initialized() { pool.k_constant } else { zero };
+        pool.k_constant = match invariant {
+            Some(invariant) => fhe.select(ok, invariant, kept_k)?,
+            None => fhe.mul(pool.reserve_a, pool.reserve_b)?,
+        };
+        if stable && seed_pool {
+            let ann = fhe.constant(pool.amp(Clock::get()?.unix_timestamp) as u128 * 4)?;
+            let kept_ann = if pool.invariant_ann.is_initialized() { pool.invariant_ann } else { zero };
+            pool.invariant_ann = fhe.select(ok, ann, kept_ann)?;
+        }
+        
+        user_position.lp_amount = fhe.accumulate(user_position.lp_amount, minted)?;
         user_position.owner = ctx.accounts.user.key();
         user_position.pool = pool.key();
+        checkpoint_rewards(&fhe, ctx.accounts.rewards.as_deref().map(|rewards| &**rewards), user_position)?;
         
-        // Update total LP supply
-        pool.lp_supply = Euint128::wrap(encrypted_amount_a);
+        // Update total LP supply (including any locked minimum); zero on failure
+        pool.lp_supply = fhe.accumulate(pool.lp_supply, supply_added)?;
         
         msg!("Added liquidity to pool (amounts encrypted)");
         Ok(())
@@ -75,192 +472,2923 @@ pub mod private_amm {
 
     /// Remove liquidity from the pool
     /// Burns encrypted LP tokens and returns encrypted amounts of both tokens
+    /// from the pool vaults to the user's balances
+    /// 
+    /// The caller supplies the burned fraction of the LP supply as
+    /// `encrypted_share_hint`: floor(lp_amount * 2^SHARE_BITS / lp_supply), where
+    /// lp_amount is the encrypted amount capped at the position. It is checked
+    /// with Fhe::check_quotient; a wrong hint burns and pays out nothing.
+    /// With the LP supply and reserves within MAX_POOL_BALANCE, neither
+    /// lp_amount * 2^SHARE_BITS nor reserve * share can wrap, and the floored
+    /// share never pays out more than the burned part of a reserve
+    /// 
+    /// StableSwap withdrawals also take their share of the banked LP fees and
+    /// shrink the invariant D by at least the same share
     pub fn remove_liquidity(
         ctx: Context<RemoveLiquidity>,
-        encrypted_lp_amount: u128,  // Encrypted LP tokens to burn
+        encrypted_lp_amount: u128,   // Encrypted LP tokens to burn
+        encrypted_share_hint: u128,  // Encrypted share of the LP supply burned (see above)
+        deadline_slot: u64,          // Reject if executed after this slot
     ) -> Result<()> {
+        check_deadline(deadline_slot)?;
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let pool_info = ctx.accounts.pool.to_account_info();
         let pool = &mut ctx.accounts.pool;
         let user_position = &mut ctx.accounts.user_position;
+        require!(user_position.lp_amount.is_initialized(), AmmError::InsufficientLiquidity);
+        let rewards = ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards);
+        settle_rewards(&fhe, pool, rewards, user_position, true)?;
+        
+        // Never burn more than the user holds
+        let requested = Euint128::wrap(encrypted_lp_amount);
+        let lp_amount = fhe.min(requested, user_position.lp_amount)?;
         
         // Calculate proportional share of reserves
         // amount_a_out = (lp_amount / lp_supply) * reserve_a
         // amount_b_out = (lp_amount / lp_supply) * reserve_b
-        // All done via Inco FHE operations
+        // The share is hinted once as a fixed-point fraction and checked
+        let shift = fhe.constant(SHARE_BITS as u128)?;
+        let hint = Euint128::wrap(encrypted_share_hint);
+        let valid = fhe.check_quotient(fhe.shl(lp_amount, shift)?, pool.lp_supply, hint, 1 << SHARE_BITS)?;
+        let zero = fhe.constant(0)?;
+        let lp_amount = fhe.select(valid, lp_amount, zero)?;
+        let share = fhe.select(valid, hint, zero)?;
+        let mut amount_a_out = fhe.apply_share(pool.reserve_a, share)?;
+        let mut amount_b_out = fhe.apply_share(pool.reserve_b, share)?;
+        
+        pool.reserve_a = fhe.sub(pool.reserve_a, amount_a_out)?;
+        pool.reserve_b = fhe.sub(pool.reserve_b, amount_b_out)?;
+        if pool.pool_type == PoolType::StableSwap {
+            // Keep D * (1 - share), rounded down, so it stays within the reserves
+            let whole = fhe.constant(1 << SHARE_BITS)?;
+            pool.k_constant = fhe.apply_share(pool.k_constant, fhe.sub(whole, share)?)?;
+            if pool.lp_fees_a.is_initialized() {
+                let fees = fhe.apply_share(pool.lp_fees_a, share)?;
+                pool.lp_fees_a = fhe.sub(pool.lp_fees_a, fees)?;
+                amount_a_out = fhe.add(amount_a_out, fees)?;
+            }
+            if pool.lp_fees_b.is_initialized() {
+                let fees = fhe.apply_share(pool.lp_fees_b, share)?;
+                pool.lp_fees_b = fhe.sub(pool.lp_fees_b, fees)?;
+                amount_b_out = fhe.add(amount_b_out, fees)?;
+            }
+        } else {
+            pool.k_constant = fhe.mul(pool.reserve_a, pool.reserve_b)?;
+        }
+        pool.lp_supply = fhe.sub(pool.lp_supply, lp_amount)?;
         
         // Update user's LP balance (encrypted subtraction)
-        user_position.lp_amount = Euint128::default();
+        user_position.lp_amount = fhe.sub(user_position.lp_amount, lp_amount)?;
+        checkpoint_rewards(&fhe, ctx.accounts.rewards.as_deref().map(|rewards| &**rewards), user_position)?;
+        
+        // Pay out of the vaults, signed by the pool PDA
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.transfer(
+            &ctx.accounts.vault_a,
+            &ctx.accounts.user_balance_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &pool_info,
+            &[seeds],
+            amount_a_out,
+        )?;
+        custody.transfer(
+            &ctx.accounts.vault_b,
+            &ctx.accounts.user_balance_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &pool_info,
+            &[seeds],
+            amount_b_out,
+        )?;
         
-        // The actual token transfer would happen via CPI to token program
-        // with decrypted amounts from Inco co-validator
+        emit!(LiquidityRemoved {
+            pool: pool.key(),
+            owner: user_position.owner,
+            lp_burned: lp_amount,
+            amount_a: amount_a_out,
+            amount_b: amount_b_out,
+        });
         
         msg!("Removed liquidity from pool (amounts encrypted)");
         Ok(())
     }
 
+    /// Move part of the caller's LP position to another user's position
+    /// Moves zero if the caller holds less than the encrypted amount
+    pub fn transfer_position(ctx: Context<TransferPosition>, encrypted_amount: u128) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        
+        let pool = &ctx.accounts.pool;
+        let source = &mut ctx.accounts.user_position;
+        let dest = &mut ctx.accounts.recipient_position;
+        require!(source.lp_amount.is_initialized(), AmmError::InsufficientLiquidity);
+        settle_rewards(&fhe, pool, ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards), source, false)?;
+        settle_rewards(&fhe, pool, ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards), dest, false)?;
+        let zero = fhe.constant(0)?;
+        let amount = Euint128::wrap(encrypted_amount);
+        let sufficient = fhe.ge(source.lp_amount, amount)?;
+        let moved = fhe.select(sufficient, amount, zero)?;
+        source.lp_amount = fhe.sub(source.lp_amount, moved)?;
+        dest.lp_amount = fhe.accumulate(dest.lp_amount, moved)?;
+        dest.owner = ctx.accounts.recipient.key();
+        dest.pool = pool.key();
+        
+        let rewards = ctx.accounts.rewards.as_deref().map(|rewards| &**rewards);
+        checkpoint_rewards(&fhe, rewards, source)?;
+        checkpoint_rewards(&fhe, rewards, dest)?;
+        
+        emit!(PositionTransferred {
+            pool: dest.pool,
+            from: source.owner,
+            to: dest.owner,
+            amount: moved,
+        });
+        
+        msg!("LP position transferred (amount encrypted)");
+        Ok(())
+    }
+
+    /// Register the cToken that tokenized LP shares are minted as (pool authority only)
+    /// The mint's authority must be the pool PDA; it can only be set once
+    pub fn set_lp_mint(ctx: Context<SetLpMint>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.lp_mint == Pubkey::default(), AmmError::LpMintAlreadySet);
+        pool.lp_mint = ctx.accounts.lp_mint.key();
+        
+        msg!("LP mint set to {}", pool.lp_mint);
+        Ok(())
+    }
+
+    /// Turn part of the caller's LP position into LP cTokens in their balance
+    /// The tokens move like any inco_token balance and redeem 1:1 back into a position;
+    /// while tokenized they earn no liquidity-mining rewards and leave the staked
+    /// LP supply the rewards are shared over
+    pub fn tokenize_position(ctx: Context<TokenizePosition>, encrypted_amount: u128) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        let user_position = &mut ctx.accounts.user_position;
+        require!(user_position.lp_amount.is_initialized(), AmmError::InsufficientLiquidity);
+        let rewards = ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards);
+        settle_rewards(&fhe, &ctx.accounts.pool, rewards, user_position, true)?;
+        let amount = fhe.min(Euint128::wrap(encrypted_amount), user_position.lp_amount)?;
+        user_position.lp_amount = fhe.sub(user_position.lp_amount, amount)?;
+        checkpoint_rewards(&fhe, ctx.accounts.rewards.as_deref().map(|rewards| &**rewards), user_position)?;
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.tokenized_lp_supply = fhe.accumulate(pool.tokenized_lp_supply, amount)?;
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.mint(
+            &ctx.accounts.user_lp_balance,
+            &ctx.accounts.lp_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            amount,
+        )?;
+        
+        msg!("LP position tokenized (amount encrypted)");
+        Ok(())
+    }
+
+    /// Burn LP cTokens from the caller's balance and credit their LP position
+    /// Burns zero if the balance is short
+    pub fn redeem_position_tokens(ctx: Context<RedeemPositionTokens>, encrypted_amount: u128) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        let burned = custody.burn(
+            &ctx.accounts.user_lp_balance,
+            &ctx.accounts.lp_mint.to_account_info(),
+            &signer,
+            Euint128::wrap(encrypted_amount),
+        )?;
+        
+        // Only tokenize_position mints LP cTokens, so a burn implies a tokenized supply
+        let pool = &mut ctx.accounts.pool;
+        if pool.tokenized_lp_supply.is_initialized() {
+            pool.tokenized_lp_supply = fhe.sub(pool.tokenized_lp_supply, burned)?;
+        }
+        
+        let user_position = &mut ctx.accounts.user_position;
+        let rewards = ctx.accounts.rewards.as_deref_mut().map(|rewards| &mut **rewards);
+        settle_rewards(&fhe, &ctx.accounts.pool, rewards, user_position, true)?;
+        user_position.lp_amount = fhe.accumulate(user_position.lp_amount, burned)?;
+        user_position.owner = ctx.accounts.user.key();
+        user_position.pool = ctx.accounts.pool.key();
+        checkpoint_rewards(&fhe, ctx.accounts.rewards.as_deref().map(|rewards| &**rewards), user_position)?;
+        
+        msg!("LP tokens redeemed into position (amount encrypted)");
+        Ok(())
+    }
+
     /// Execute a private swap
     /// All amounts are encrypted - no one can see trade size!
     /// 
     /// Uses constant product formula: x * y = k
     /// dy = y - (k / (x + dx))
     /// 
-    /// All math happens via Inco FHE CPI calls
+    /// Inco Lightning cannot divide, so the caller supplies the output as an
+    /// encrypted hint and the pool only checks it against its curve
+    /// (`encrypted_hints`, PoolType::hint_count of them; see quote_swap)
+    /// 
+    /// The quote, slippage and the user's balance are checked before anything
+    /// moves; the input is then pulled into the pool vault only if all pass, so
+    /// nothing has to be refunded. The output stays in the vault, recorded on a
+    /// per-swap receipt (seeded by `nonce`), until claim_swap_output pays it out
+    /// 
+    /// Oracle pools also need the pool's configured price feed as `oracle`;
+    /// a swap larger than the output inventory fails like a slippage miss.
+    /// Concentrated pools need their `tick_grid`; a swap past the last
+    /// initialized tick fails the same way
     pub fn swap(
         ctx: Context<Swap>,
         encrypted_amount_in: u128,   // Encrypted input amount
         encrypted_min_out: u128,     // Encrypted minimum output (slippage)
-        direction: bool,             // true = A→B, false = B→A
+        encrypted_hints: Vec<u128>,  // Encrypted curve hints for the pool type
+        nonce: u64,                  // Caller-chosen receipt index, unique per (pool, user)
+        deadline_slot: u64,          // Reject if executed after this slot
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+        check_deadline(deadline_slot)?;
         
-        // Get current reserves based on direction
-        let (reserve_in, reserve_out) = if direction {
-            (pool.reserve_a, pool.reserve_b)
-        } else {
-            (pool.reserve_b, pool.reserve_a)
-        };
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        require!(ctx.accounts.pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+        
+        // Direction follows the input/output mints (validated against the pool)
+        let direction = ctx.accounts.input_mint.key() == ctx.accounts.pool.token_a_mint;
+        let requested = Euint128::wrap(encrypted_amount_in);
+        let min_out = Euint128::wrap(encrypted_min_out);
+        let hints: Vec<Euint128> = encrypted_hints.into_iter().map(Euint128::wrap).collect();
+        let pool = &mut ctx.accounts.pool;
         
         // ═══════════════════════════════════════════════════════════════
-        // ENCRYPTED SWAP CALCULATION (via Inco FHE CPI in production)
-        // ═══════════════════════════════════════════════════════════════
-        // 
-        // Step 1: new_reserve_in = reserve_in + amount_in
-        //         (Inco CPI: e_add)
-        //
-        // Step 2: new_reserve_out = k / new_reserve_in
-        //         (Inco CPI: e_div - need to implement or use e_mul with inverse)
-        //
-        // Step 3: amount_out = reserve_out - new_reserve_out
-        //         (Inco CPI: e_sub)
-        //
-        // Step 4: amount_out_after_fee = amount_out * (10000 - fee_bps) / 10000
-        //         (Inco CPI: e_mul, e_rem or precomputed)
-        //
-        // Step 5: Verify amount_out_after_fee >= min_out
-        //         (Inco CPI: e_ge)
-        //
-        // Step 6: Conditional execution based on slippage check
-        //         (Inco CPI: e_select)
+        // ENCRYPTED SWAP CALCULATION (via Inco FHE CPI)
         // ═══════════════════════════════════════════════════════════════
+        // Steps 1-2: check the hinted output against the curve (see quote_swap)
+        let oracle = ctx.accounts.oracle.as_ref().map(|oracle| oracle.to_account_info());
+        let grid = ctx.accounts.tick_grid.as_deref().map(|grid| &**grid);
+        let quote = quote_swap(&fhe, pool, direction, requested, &hints, oracle.as_ref(), grid)?;
         
-        // For this implementation, we store the encrypted values directly
-        // The actual FHE computation happens when Inco CPI is available
-        let encrypted_amount_out = Euint128::wrap(encrypted_amount_in);
+        // Step 3: Verify amount_out >= min_out and that the user can pay
+        let funded = fhe.covers(&ctx.accounts.user_balance_in, requested)?;
+        let swap_ok = fhe.and(quote.accepts(&fhe, min_out)?, funded)?;
         
-        // Update reserves
-        if direction {
-            pool.reserve_a = Euint128::wrap(encrypted_amount_in);
-            pool.reserve_b = encrypted_amount_out;
-        } else {
-            pool.reserve_b = Euint128::wrap(encrypted_amount_in);
-            pool.reserve_a = encrypted_amount_out;
+        // Step 4: Conditional execution - a failed check moves nothing
+        let zero = fhe.constant(0)?;
+        let amount_in = fhe.select(swap_ok, requested, zero)?;
+        custody.transfer(
+            &ctx.accounts.user_balance_in.to_account_info(),
+            &ctx.accounts.vault_in,
+            &ctx.accounts.input_mint.to_account_info(),
+            &signer,
+            &[],
+            amount_in,
+        )?;
+        let amount_out = apply_swap(&fhe, pool, direction, amount_in, &quote, swap_ok, zero)?;
+        if let (Some(step), Some(grid)) = (&quote.range, ctx.accounts.tick_grid.as_mut()) {
+            apply_range_step(&fhe, grid, direction, step, swap_ok, zero)?;
         }
         
-        // Store result in user's swap result account
-        let swap_result = &mut ctx.accounts.swap_result;
-        swap_result.amount_out = encrypted_amount_out;
-        swap_result.owner = ctx.accounts.user.key();
-        swap_result.is_complete = true;
+        // Record the swap on its own receipt; the output is claimed separately
+        let receipt = &mut ctx.accounts.swap_receipt;
+        receipt.owner = ctx.accounts.user.key();
+        receipt.pool = pool.key();
+        receipt.nonce = nonce;
+        receipt.amount_in = amount_in;
+        receipt.amount_out = amount_out;
+        receipt.success = swap_ok;
+        receipt.slot = Clock::get()?.slot;
+        receipt.a_to_b = direction;
+        receipt.bump = ctx.bumps.swap_receipt;
         
         msg!("Private swap executed (all amounts encrypted!)");
         msg!("Direction: {}", if direction { "A → B" } else { "B → A" });
         Ok(())
     }
 
-    /// Get pool info (only public data)
-    pub fn get_pool_info(ctx: Context<GetPoolInfo>) -> Result<()> {
-        let pool = &ctx.accounts.pool;
-        msg!("Pool: {} <-> {}", pool.token_a_mint, pool.token_b_mint);
-        msg!("Fee: {} bps", pool.fee_bps);
-        msg!("Reserves: ENCRYPTED (privacy preserved!)");
+    /// Swap through an ordered route of pools in one transaction
+    /// 
+    /// `remaining_accounts` holds ROUTE_HOP_ACCOUNTS per hop, in route order:
+    /// [pool (mut), output mint, pool's input vault (mut), pool's output vault (mut)]
+    /// Each hop's encrypted output moves straight into the next pool's vault;
+    /// only the final output is checked against `encrypted_min_out`. If that
+    /// check, any hop's hint or the user's balance fails, every hop moves zero
+    /// and the input never leaves the user.
+    /// `encrypted_hints` holds each hop's swap hints in route order.
+    /// Only constant-product pools can be routed through (UnsupportedRouteHop
+    /// otherwise): the other curves need more trace entries per hop than
+    /// MAX_ROUTE_HOPS leaves
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        encrypted_amount_in: u128,   // Encrypted input amount
+        encrypted_min_out: u128,     // Encrypted minimum output of the final hop
+        encrypted_hints: Vec<u128>,  // Encrypted curve hints of every hop, in route order
+        deadline_slot: u64,          // Reject if executed after this slot
+    ) -> Result<()> {
+        check_deadline(deadline_slot)?;
+        
+        let remaining = ctx.remaining_accounts;
+        require!(
+            !remaining.is_empty() && remaining.len().is_multiple_of(ROUTE_HOP_ACCOUNTS),
+            AmmError::InvalidRoute
+        );
+        let hop_count = remaining.len() / ROUTE_HOP_ACCOUNTS;
+        require!(hop_count <= MAX_ROUTE_HOPS, AmmError::InvalidRoute);
+        
+        // Load and validate every hop before moving any funds
+        let mut hops: Vec<RouteHop<'info>> = Vec::with_capacity(hop_count);
+        let mut mint_in = ctx.accounts.input_mint.key();
+        for accounts in remaining.chunks(ROUTE_HOP_ACCOUNTS) {
+            let (pool_info, mint_out, vault_in, vault_out) =
+                (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+            require!(pool_info.is_writable, AmmError::InvalidRoute);
+            require!(
+                hops.iter().all(|hop| hop.pool.key() != pool_info.key()),
+                AmmError::InvalidRoute
+            );
+            
+            let pool: Account<'info, PrivatePool> = Account::try_from(pool_info)?;
+            require!(pool.is_initialized, AmmError::PoolNotInitialized);
+            require!(!pool.paused, AmmError::PoolPaused);
+            require!(pool.pool_type == PoolType::ConstantProduct, AmmError::UnsupportedRouteHop);
+            require!(pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+            require!(mint_out.key() != mint_in, AmmError::InvalidDirection);
+            require!(pool.vault_for(&mint_in) == Some(vault_in.key()), AmmError::InvalidVault);
+            require!(pool.vault_for(&mint_out.key()) == Some(vault_out.key()), AmmError::InvalidVault);
+            
+            hops.push(RouteHop {
+                a_to_b: mint_in == pool.token_a_mint,
+                pool,
+                mint_out: mint_out.clone(),
+                vault_in: vault_in.clone(),
+                vault_out: vault_out.clone(),
+            });
+            mint_in = mint_out.key();
+        }
+        require!(mint_in == ctx.accounts.output_mint.key(), AmmError::InvalidRoute);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        // Chain the hinted quotes: each hop's output is the next hop's input
+        let requested = Euint128::wrap(encrypted_amount_in);
+        let mut hints = encrypted_hints.into_iter().map(Euint128::wrap);
+        let mut quotes = Vec::with_capacity(hop_count);
+        let mut amount = requested;
+        for hop in &hops {
+            let hop_hints: Vec<Euint128> = hints.by_ref().take(hop.pool.pool_type.hint_count()).collect();
+            let quote = quote_swap(&fhe, &hop.pool, hop.a_to_b, amount, &hop_hints, None, None)?;
+            amount = quote.amount_out;
+            quotes.push(quote);
+        }
+        require!(hints.next().is_none(), AmmError::InvalidHints);
+        
+        // One slippage check on the final leg, every hop's hint and the user's
+        // balance gate the whole route
+        let min_out = Euint128::wrap(encrypted_min_out);
+        let mut route_ok = quotes[hop_count - 1].accepts(&fhe, min_out)?;
+        for quote in &quotes[..hop_count - 1] {
+            route_ok = fhe.and(route_ok, quote.fillable)?;
+        }
+        route_ok = fhe.and(route_ok, fhe.covers(&ctx.accounts.user_balance_in, requested)?)?;
+        let zero = fhe.constant(0)?;
+        let amount_in = fhe.select(route_ok, requested, zero)?;
+        
+        // Pull the input into the first pool; zero if the route failed
+        custody.transfer(
+            &ctx.accounts.user_balance_in.to_account_info(),
+            &hops[0].vault_in,
+            &ctx.accounts.input_mint.to_account_info(),
+            &signer,
+            &[],
+            amount_in,
+        )?;
+        
+        // Settle each hop and forward its output to the next pool's vault,
+        // or to the user on the final leg
+        let mut destinations: Vec<AccountInfo<'info>> =
+            hops.iter().skip(1).map(|hop| hop.vault_in.clone()).collect();
+        destinations.push(ctx.accounts.user_balance_out.to_account_info());
+        
+        let mut hop_in = amount_in;
+        for ((hop, quote), dest) in hops.iter_mut().zip(&quotes).zip(&destinations) {
+            let hop_out = apply_swap(&fhe, &mut hop.pool, hop.a_to_b, hop_in, quote, route_ok, zero)?;
+            
+            let bump = [hop.pool.bump];
+            let seeds: &[&[u8]] = &[b"pool", hop.pool.token_a_mint.as_ref(), hop.pool.token_b_mint.as_ref(), &bump];
+            custody.transfer(
+                &hop.vault_out,
+                dest,
+                &hop.mint_out,
+                &hop.pool.to_account_info(),
+                &[seeds],
+                hop_out,
+            )?;
+            hop.pool.exit(&crate::ID)?;
+            hop_in = hop_out;
+        }
+        
+        emit!(RouteSwapped {
+            owner: ctx.accounts.user.key(),
+            input_mint: ctx.accounts.input_mint.key(),
+            output_mint: ctx.accounts.output_mint.key(),
+            hops: hop_count as u8,
+            amount_in,
+            amount_out: hop_in,
+            success: route_ok,
+        });
+        
+        msg!("Private route swap executed across {} pools", hop_count);
         Ok(())
     }
-}
-
-// ============================================================================
-// ACCOUNT STRUCTURES
-// ============================================================================
 
-/// Private Liquidity Pool
-/// All monetary values are encrypted using Inco FHE
-#[account]
-pub struct PrivatePool {
-    /// Token A mint address
-    pub token_a_mint: Pubkey,
-    /// Token B mint address
-    pub token_b_mint: Pubkey,
-    /// Encrypted reserve of token A
-    pub reserve_a: Euint128,
-    /// Encrypted reserve of token B
-    pub reserve_b: Euint128,
-    /// Encrypted constant product (k = reserve_a * reserve_b)
-    pub k_constant: Euint128,
-    /// Encrypted total LP token supply
-    pub lp_supply: Euint128,
-    /// Fee in basis points (0.01% units) - public
-    pub fee_bps: u16,
-    /// Pool authority
-    pub authority: Pubkey,
-    /// PDA bump
-    pub bump: u8,
-    /// Initialization flag
-    pub is_initialized: bool,
-}
+    /// Pay a swap's output from the pool vault into the user's balance
+    /// Closes the receipt and refunds its rent; a failed swap pays out zero
+    pub fn claim_swap_output(ctx: Context<ClaimSwapOutput>) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let pool = &ctx.accounts.pool;
+        let receipt = &ctx.accounts.swap_receipt;
+        
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.transfer(
+            &ctx.accounts.vault_out,
+            &ctx.accounts.user_balance_out,
+            &ctx.accounts.output_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            receipt.amount_out,
+        )?;
+        
+        msg!("Claimed swap output for receipt {}", receipt.nonce);
+        Ok(())
+    }
 
-impl PrivatePool {
-    // 32 + 32 + 16 + 16 + 16 + 16 + 2 + 32 + 1 + 1 = 164 bytes
-    pub const LEN: usize = 32 + 32 + 16 + 16 + 16 + 16 + 2 + 32 + 1 + 1;
-}
+    /// Queue an encrypted order in the current batch-auction epoch
+    /// 
+    /// The order sells `encrypted_amount_a` of token A and `encrypted_amount_b`
+    /// of token B (normally one is zero); both legs are always deposited, so
+    /// the trade direction stays hidden. Orders in one epoch execute together
+    /// at a single price when settle_batch runs, so their order within the
+    /// epoch does not matter
+    pub fn submit_batch_order(
+        ctx: Context<SubmitBatchOrder>,
+        epoch: u64,                // Current epoch: slot / BATCH_EPOCH_SLOTS
+        encrypted_amount_a: u128,  // Encrypted amount of token A to sell
+        encrypted_amount_b: u128,  // Encrypted amount of token B to sell
+    ) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        require!(epoch == slot / BATCH_EPOCH_SLOTS, AmmError::BatchClosed);
+        require!(ctx.accounts.pool.pool_type == PoolType::ConstantProduct, AmmError::UnsupportedPoolType);
+        require!(ctx.accounts.pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        // Deposit both legs; a short balance moves zero
+        let amount_a = custody.transfer(
+            &ctx.accounts.user_balance_a,
+            &ctx.accounts.vault_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &signer,
+            &[],
+            Euint128::wrap(encrypted_amount_a),
+        )?;
+        let amount_b = custody.transfer(
+            &ctx.accounts.user_balance_b,
+            &ctx.accounts.vault_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &signer,
+            &[],
+            Euint128::wrap(encrypted_amount_b),
+        )?;
+        
+        let batch = &mut ctx.accounts.batch;
+        if batch.pool == Pubkey::default() {
+            batch.pool = ctx.accounts.pool.key();
+            batch.epoch = epoch;
+            batch.end_slot = (epoch + 1) * BATCH_EPOCH_SLOTS;
+            batch.bump = ctx.bumps.batch;
+        }
+        require!(batch.orders.len() < MAX_BATCH_ORDERS, AmmError::BatchFull);
+        
+        batch.total_a_in = fhe.accumulate(batch.total_a_in, amount_a)?;
+        batch.total_b_in = fhe.accumulate(batch.total_b_in, amount_b)?;
+        batch.orders.push(BatchOrder {
+            owner: ctx.accounts.user.key(),
+            amount_a_in: amount_a,
+            amount_b_in: amount_b,
+            claimed: false,
+        });
+        
+        msg!("Queued batch order {} for epoch {}", batch.orders.len() - 1, epoch);
+        Ok(())
+    }
 
-/// User's LP position in a pool (encrypted)
-#[account]
-pub struct UserPosition {
-    /// Owner of this position
-    pub owner: Pubkey,
-    /// Pool this position is in
-    pub pool: Pubkey,
-    /// Encrypted LP token balance
-    pub lp_amount: Euint128,
-}
+    /// Clear every order of an ended epoch at one uniform price
+    /// 
+    /// With X_a, X_b the batch's encrypted sell totals, all orders trade at
+    /// p = (reserve_b + X_b) / (reserve_a + X_a) B per A. Opposing orders
+    /// match each other and only the net imbalance trades against the pool;
+    /// at this price the pool's constant product is unchanged before fees.
+    /// 
+    /// Anyone may settle: the caller's identity is not checked, the hints are.
+    /// Both prices are encrypted hints (BATCH_PRICE_BITS fixed point, rounded
+    /// down) checked against the depths in both directions, so no settler can
+    /// choose a price. The prices net of the pool fee are stored for
+    /// claim_batch_order, and the reserves move by the batch totals, which
+    /// round in the pool's favour against the sum of the orders. If a hint is
+    /// wrong the reserves stay put and every order claims its deposits back,
+    /// so a bad settler can at worst cancel the epoch, never mis-price it
+    pub fn settle_batch(
+        ctx: Context<SettleBatch>,
+        encrypted_price_a_in_b: u128,  // Encrypted hint: floor(depth_b * 2^BATCH_PRICE_BITS / depth_a)
+        encrypted_price_b_in_a: u128,  // Encrypted hint: floor(depth_a * 2^BATCH_PRICE_BITS / depth_b)
+    ) -> Result<()> {
+        let batch = &mut ctx.accounts.batch;
+        let slot = Clock::get()?.slot;
+        require!(slot >= batch.end_slot, AmmError::BatchNotEnded);
+        require!(slot < batch.end_slot + BATCH_SETTLE_WINDOW_SLOTS, AmmError::BatchExpired);
+        
+        let pool = &mut ctx.accounts.pool;
+        batch.settled = true;
+        if batch.orders.is_empty() {
+            return Ok(());
+        }
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.settler.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let shift = fhe.constant(BATCH_PRICE_BITS as u128)?;
+        
+        // Uniform clearing price in both directions; depths below 2^64 keep
+        // the shifted numerators and the quotient checks from wrapping
+        let depth_a = fhe.add(pool.reserve_a, batch.total_a_in)?;
+        let depth_b = fhe.add(pool.reserve_b, batch.total_b_in)?;
+        let price_a_in_b = Euint128::wrap(encrypted_price_a_in_b);
+        let price_b_in_a = Euint128::wrap(encrypted_price_b_in_a);
+        let bounded = fhe.ge(fhe.constant(u64::MAX as u128)?, fhe.or(depth_a, depth_b)?)?;
+        let a_in_b_ok = fhe.check_quotient(fhe.shl(depth_b, shift)?, depth_a, price_a_in_b, u64::MAX as u128)?;
+        let b_in_a_ok = fhe.check_quotient(fhe.shl(depth_a, shift)?, depth_b, price_b_in_a, u64::MAX as u128)?;
+        let ok = fhe.and(bounded, fhe.and(a_in_b_ok, b_in_a_ok)?)?;
+        
+        // Orders are paid at the net prices; flooring each order separately
+        // never pays out more than flooring the totals below
+        let keep_bps = BPS_DENOMINATOR - pool.fee_bps as u128;
+        batch.price_a_in_b = fhe.scale(price_a_in_b, keep_bps, BPS_DENOMINATOR)?;
+        batch.price_b_in_a = fhe.scale(price_b_in_a, keep_bps, BPS_DENOMINATOR)?;
+        batch.settle_ok = ok;
+        let gross_a = fhe.shr(fhe.mul(batch.total_b_in, price_b_in_a)?, shift)?;
+        let gross_b = fhe.shr(fhe.mul(batch.total_a_in, price_a_in_b)?, shift)?;
+        let paid_a = fhe.shr(fhe.mul(batch.total_b_in, batch.price_b_in_a)?, shift)?;
+        let paid_b = fhe.shr(fhe.mul(batch.total_a_in, batch.price_a_in_b)?, shift)?;
+        
+        // Everything sold joins the reserves and everything bought leaves them;
+        // fees stay with LPs apart from the protocol's cut
+        let mut reserve_a = fhe.sub(depth_a, paid_a)?;
+        let mut reserve_b = fhe.sub(depth_b, paid_b)?;
+        if pool.protocol_fee_share_bps > 0 {
+            let share = pool.protocol_fee_share_bps as u128;
+            let zero = fhe.constant(0)?;
+            let protocol_a = fhe.scale(fhe.sub(gross_a, paid_a)?, share, BPS_DENOMINATOR)?;
+            let protocol_b = fhe.scale(fhe.sub(gross_b, paid_b)?, share, BPS_DENOMINATOR)?;
+            let protocol_a = fhe.select(ok, protocol_a, zero)?;
+            let protocol_b = fhe.select(ok, protocol_b, zero)?;
+            reserve_a = fhe.sub(reserve_a, protocol_a)?;
+            reserve_b = fhe.sub(reserve_b, protocol_b)?;
+            pool.protocol_fees_a = fhe.accumulate(pool.protocol_fees_a, protocol_a)?;
+            pool.protocol_fees_b = fhe.accumulate(pool.protocol_fees_b, protocol_b)?;
+        }
+        pool.reserve_a = fhe.select(ok, reserve_a, pool.reserve_a)?;
+        pool.reserve_b = fhe.select(ok, reserve_b, pool.reserve_b)?;
+        pool.k_constant = fhe.mul(pool.reserve_a, pool.reserve_b)?;
+        
+        emit!(BatchSettled {
+            pool: pool.key(),
+            epoch: batch.epoch,
+            orders: batch.orders.len() as u8,
+        });
+        
+        msg!("Settled batch epoch {} ({} orders)", batch.epoch, batch.orders.len());
+        Ok(())
+    }
 
-impl UserPosition {
-    pub const LEN: usize = 32 + 32 + 16; // 80 bytes
-}
+    /// Pay a settled batch order's outputs into the owner's balances
+    /// 
+    /// Outputs are the order's inputs at the batch's net prices; if settlement
+    /// failed its hint check, the inputs are returned instead. Both legs are
+    /// always transferred, so the claim hides the direction too
+    pub fn claim_batch_order(ctx: Context<ClaimBatchOrder>, index: u8) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        
+        let batch = &mut ctx.accounts.batch;
+        require!(batch.settled, AmmError::BatchNotSettled);
+        let order = batch.take_order(index, ctx.accounts.user.key())?;
+        
+        let shift = fhe.constant(BATCH_PRICE_BITS as u128)?;
+        let out_a = fhe.shr(fhe.mul(order.amount_b_in, batch.price_b_in_a)?, shift)?;
+        let out_b = fhe.shr(fhe.mul(order.amount_a_in, batch.price_a_in_b)?, shift)?;
+        let amount_a = fhe.select(batch.settle_ok, out_a, order.amount_a_in)?;
+        let amount_b = fhe.select(batch.settle_ok, out_b, order.amount_b_in)?;
+        
+        let epoch = batch.epoch;
+        pay_batch_order(ctx.accounts, amount_a, amount_b)?;
+        
+        msg!("Claimed batch order {} of epoch {}", index, epoch);
+        Ok(())
+    }
 
-/// Swap result account (encrypted output)
-#[account]
-pub struct SwapResult {
-    /// Owner of this swap result
-    pub owner: Pubkey,
-    /// Encrypted output amount
-    pub amount_out: Euint128,
-    /// Whether swap completed successfully
-    pub is_complete: bool,
-}
+    /// Return a batch order's deposits when its batch was never settled
+    /// Opens once BATCH_SETTLE_WINDOW_SLOTS have passed after the epoch, which
+    /// is also when settle_batch closes, so a batch is either settled or refunded
+    pub fn refund_batch_order(ctx: Context<ClaimBatchOrder>, index: u8) -> Result<()> {
+        let batch = &mut ctx.accounts.batch;
+        require!(!batch.settled, AmmError::BatchAlreadySettled);
+        require!(
+            Clock::get()?.slot >= batch.end_slot + BATCH_SETTLE_WINDOW_SLOTS,
+            AmmError::BatchNotExpired
+        );
+        let order = batch.take_order(index, ctx.accounts.user.key())?;
+        
+        let epoch = batch.epoch;
+        pay_batch_order(ctx.accounts, order.amount_a_in, order.amount_b_in)?;
+        
+        msg!("Refunded batch order {} of epoch {}", index, epoch);
+        Ok(())
+    }
 
-impl SwapResult {
-    pub const LEN: usize = 32 + 16 + 1; // 49 bytes
-}
+    /// Rest an encrypted limit order on the pool
+    /// 
+    /// The input is escrowed in the pool vault. `encrypted_limit_price` is the
+    /// minimum output per input unit, as a LIMIT_PRICE_BITS fixed-point number,
+    /// so neither size nor price is revealed
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        encrypted_amount_in: u128,     // Encrypted input amount
+        encrypted_limit_price: u128,   // Encrypted minimum output per input (fixed point)
+        nonce: u64,                    // Caller-chosen order index, unique per (pool, user)
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.pool.pool_type != PoolType::Concentrated,
+            AmmError::UnsupportedPoolType
+        );
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        // Escrow the input; a short balance moves zero
+        let escrowed = custody.transfer(
+            &ctx.accounts.user_balance_in,
+            &ctx.accounts.vault_in,
+            &ctx.accounts.input_mint.to_account_info(),
+            &signer,
+            &[],
+            Euint128::wrap(encrypted_amount_in),
+        )?;
+        
+        let order = &mut ctx.accounts.limit_order;
+        order.owner = ctx.accounts.user.key();
+        order.pool = ctx.accounts.pool.key();
+        order.nonce = nonce;
+        order.a_to_b = ctx.accounts.input_mint.key() == ctx.accounts.pool.token_a_mint;
+        order.amount_in = escrowed;
+        order.limit_price = Euint128::wrap(encrypted_limit_price);
+        order.amount_out = fhe.constant(0)?;
+        order.filled = Ebool::default();
+        order.bump = ctx.bumps.limit_order;
+        
+        msg!("Limit order {} placed (size and price encrypted)", nonce);
+        Ok(())
+    }
 
-// ============================================================================
-// INSTRUCTION CONTEXTS
-// ============================================================================
+    /// Try to fill a resting limit order against the pool
+    /// 
+    /// The whole escrow swaps only if the hinted output (`encrypted_hints`, as
+    /// for swap) passes the curve check and meets the encrypted limit; otherwise
+    /// e_select moves zero and the order stays intact. Unlike a swap, whose
+    /// trader guards a low hint with min_out, a constant-product fill must hint
+    /// the exact output (hint <= out < hint + 1), so a filler cannot hand the
+    /// order's surplus over its limit to the LPs.
+    /// Any signer may submit, but the hint needs the decrypted order and
+    /// reserves, so in practice only the owner, or a keeper the owner has
+    /// granted decryption allowances, can fill; anyone else only moves zero.
+    /// The output stays in the vault, owed to the order, until cancel_order
+    pub fn try_fill(ctx: Context<TryFill>, encrypted_hints: Vec<u128>) -> Result<()> {
+        require!(ctx.accounts.pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.filler.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let pool = &mut ctx.accounts.pool;
+        let order = &mut ctx.accounts.limit_order;
+        
+        // min_out = amount_in * limit_price
+        let shift = fhe.constant(LIMIT_PRICE_BITS as u128)?;
+        let min_out = fhe.shr(fhe.mul(order.amount_in, order.limit_price)?, shift)?;
+        
+        let oracle = ctx.accounts.oracle.as_ref().map(|oracle| oracle.to_account_info());
+        let hints: Vec<Euint128> = encrypted_hints.into_iter().map(Euint128::wrap).collect();
+        let quote = quote_swap(&fhe, pool, order.a_to_b, order.amount_in, &hints, oracle.as_ref(), None)?;
+        let mut price_ok = quote.accepts(&fhe, min_out)?;
+        if pool.pool_type == PoolType::ConstantProduct {
+            let (reserve_in, reserve_out) = if order.a_to_b {
+                (pool.reserve_a, pool.reserve_b)
+            } else {
+                (pool.reserve_b, pool.reserve_a)
+            };
+            let exact = constant_product_exact(
+                &fhe,
+                pool.k_constant,
+                reserve_in,
+                reserve_out,
+                order.amount_in,
+                quote.amount_out,
+                pool.fee_bps,
+            )?;
+            price_ok = fhe.and(price_ok, exact)?;
+        }
+        
+        let zero = fhe.constant(0)?;
+        let amount_in = fhe.select(price_ok, order.amount_in, zero)?;
+        let amount_out = apply_swap(&fhe, pool, order.a_to_b, amount_in, &quote, price_ok, zero)?;
+        
+        order.amount_in = fhe.sub(order.amount_in, amount_in)?;
+        order.amount_out = fhe.add(order.amount_out, amount_out)?;
+        order.filled = if order.filled.is_initialized() {
+            fhe.either(order.filled, price_ok)?
+        } else {
+            price_ok
+        };
+        
+        msg!("Tried to fill limit order {} (outcome encrypted)", order.nonce);
+        Ok(())
+    }
+
+    /// Close a limit order, returning the unfilled escrow and any filled output
+    /// Works while the pool is paused
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let pool = &ctx.accounts.pool;
+        let order = &ctx.accounts.limit_order;
+        
+        let (amount_a, amount_b) = if order.a_to_b {
+            (order.amount_in, order.amount_out)
+        } else {
+            (order.amount_out, order.amount_in)
+        };
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.transfer(
+            &ctx.accounts.vault_a,
+            &ctx.accounts.user_balance_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            amount_a,
+        )?;
+        custody.transfer(
+            &ctx.accounts.vault_b,
+            &ctx.accounts.user_balance_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            amount_b,
+        )?;
+        
+        msg!("Limit order {} closed", order.nonce);
+        Ok(())
+    }
+
+    /// Create the tick grid of a concentrated pool (pool authority only)
+    /// The price starts at `initial_tick` until liquidity and swaps move it
+    pub fn initialize_tick_grid(
+        ctx: Context<InitializeTickGrid>,
+        tick_spacing: u16,
+        initial_tick: i32,
+    ) -> Result<()> {
+        require!(tick_spacing > 0, AmmError::InvalidTickRange);
+        require!((MIN_TICK..=MAX_TICK).contains(&initial_tick), AmmError::InvalidTickRange);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.authority.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        
+        let grid = &mut ctx.accounts.tick_grid;
+        grid.pool = ctx.accounts.pool.key();
+        grid.tick_spacing = tick_spacing;
+        grid.sqrt_price = fhe.constant(sqrt_price_at_tick(initial_tick))?;
+        grid.inverse_sqrt_price = fhe.constant(inverse_sqrt_price(sqrt_price_at_tick(initial_tick)))?;
+        grid.ticks = Vec::new();
+        grid.liquidity = Vec::new();
+        grid.fee_growth_a = Vec::new();
+        grid.fee_growth_b = Vec::new();
+        grid.bump = ctx.bumps.tick_grid;
+        
+        msg!("Tick grid created (spacing {}, initial tick {})", tick_spacing, initial_tick);
+        Ok(())
+    }
+
+    /// Open or add to a concentrated-liquidity position between two ticks
+    /// 
+    /// Deposits the token amounts `encrypted_liquidity` needs at the current
+    /// (encrypted) price, rounded up. The liquidity must fit in a u64; if it does
+    /// not, either amount exceeds its encrypted maximum or the user's balance
+    /// cannot cover it, nothing moves and nothing is added.
+    /// Missing ticks are added to the grid (at most MAX_TICKS in total)
+    pub fn open_range_position(
+        ctx: Context<OpenRangePosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+        encrypted_liquidity: u128,   // Encrypted liquidity to add
+        encrypted_max_a: u128,       // Encrypted maximum token A to deposit
+        encrypted_max_b: u128,       // Encrypted maximum token B to deposit
+        deadline_slot: u64,          // Reject if executed after this slot
+    ) -> Result<()> {
+        check_deadline(deadline_slot)?;
+        let grid = &mut ctx.accounts.tick_grid;
+        let spacing = grid.tick_spacing as i32;
+        require!(
+            tick_lower < tick_upper
+                && tick_lower >= MIN_TICK
+                && tick_upper <= MAX_TICK
+                && tick_lower % spacing == 0
+                && tick_upper % spacing == 0,
+            AmmError::InvalidTickRange
+        );
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let zero = fhe.constant(0)?;
+        
+        grid.insert_tick(tick_lower, zero)?;
+        grid.insert_tick(tick_upper, zero)?;
+        
+        let liquidity = Euint128::wrap(encrypted_liquidity);
+        let (required_a, required_b) =
+            range_amounts(&fhe, grid.sqrt_price, grid.inverse_sqrt_price, liquidity, tick_lower, tick_upper, true)?;
+        let within_a = fhe.ge(Euint128::wrap(encrypted_max_a), required_a)?;
+        let within_b = fhe.ge(Euint128::wrap(encrypted_max_b), required_b)?;
+        let funded = fhe.and(
+            fhe.covers(&ctx.accounts.user_balance_a, required_a)?,
+            fhe.covers(&ctx.accounts.user_balance_b, required_b)?,
+        )?;
+        // Swaps check bucket liquidity against the same bound
+        let bounded = fhe.ge(fhe.constant(u64::MAX as u128)?, liquidity)?;
+        let ok = fhe.and(fhe.and(within_a, within_b)?, fhe.and(funded, bounded)?)?;
+        let kept_a = fhe.select(ok, required_a, zero)?;
+        let kept_b = fhe.select(ok, required_b, zero)?;
+        let added = fhe.select(ok, liquidity, zero)?;
+        
+        // Conditional execution - a failed check pulls nothing
+        let mint_a = ctx.accounts.token_a_mint.to_account_info();
+        let mint_b = ctx.accounts.token_b_mint.to_account_info();
+        custody.transfer(&ctx.accounts.user_balance_a.to_account_info(), &ctx.accounts.vault_a, &mint_a, &signer, &[], kept_a)?;
+        custody.transfer(&ctx.accounts.user_balance_b.to_account_info(), &ctx.accounts.vault_b, &mint_b, &signer, &[], kept_b)?;
+        
+        let pool = &mut ctx.accounts.pool;
+        
+        let position = &mut ctx.accounts.range_position;
+        if position.owner == Pubkey::default() {
+            position.owner = ctx.accounts.user.key();
+            position.pool = pool.key();
+            position.tick_lower = tick_lower;
+            position.tick_upper = tick_upper;
+            position.liquidity = zero;
+            position.bump = ctx.bumps.range_position;
+        }
+        settle_range_fees(&fhe, grid, position, zero)?;
+        position.liquidity = fhe.add(position.liquidity, added)?;
+        
+        let (lower, upper) = grid.bucket_range(tick_lower, tick_upper)?;
+        for bucket in &mut grid.liquidity[lower..upper] {
+            *bucket = fhe.add(*bucket, added)?;
+        }
+        pool.reserve_a = fhe.accumulate(pool.reserve_a, kept_a)?;
+        pool.reserve_b = fhe.accumulate(pool.reserve_b, kept_b)?;
+        pool.k_constant = fhe.mul(pool.reserve_a, pool.reserve_b)?;
+        pool.lp_supply = fhe.accumulate(pool.lp_supply, added)?;
+        
+        msg!("Range position [{}, {}) updated (liquidity encrypted)", tick_lower, tick_upper);
+        Ok(())
+    }
+
+    /// Pay out the fees a range position has earned
+    pub fn collect_range_fees(ctx: Context<CollectRangeFees>) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let zero = fhe.constant(0)?;
+        
+        let position = &mut ctx.accounts.range_position;
+        settle_range_fees(&fhe, &ctx.accounts.tick_grid, position, zero)?;
+        
+        let pool = &ctx.accounts.pool;
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.transfer(
+            &ctx.accounts.vault_a,
+            &ctx.accounts.user_balance_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            position.fees_owed_a,
+        )?;
+        custody.transfer(
+            &ctx.accounts.vault_b,
+            &ctx.accounts.user_balance_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            position.fees_owed_b,
+        )?;
+        position.fees_owed_a = zero;
+        position.fees_owed_b = zero;
+        
+        msg!("Collected range position fees");
+        Ok(())
+    }
+
+    /// Withdraw all of a range position's liquidity and fees, then close it
+    /// Amounts are valued at the current price, rounded down; works while paused
+    pub fn close_range_position(ctx: Context<CloseRangePosition>, deadline_slot: u64) -> Result<()> {
+        check_deadline(deadline_slot)?;
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let zero = fhe.constant(0)?;
+        
+        let grid = &mut ctx.accounts.tick_grid;
+        let position = &mut ctx.accounts.range_position;
+        settle_range_fees(&fhe, grid, position, zero)?;
+        
+        let liquidity = position.liquidity;
+        let (amount_a, amount_b) = range_amounts(
+            &fhe,
+            grid.sqrt_price,
+            grid.inverse_sqrt_price,
+            liquidity,
+            position.tick_lower,
+            position.tick_upper,
+            false,
+        )?;
+        let (lower, upper) = grid.bucket_range(position.tick_lower, position.tick_upper)?;
+        for bucket in &mut grid.liquidity[lower..upper] {
+            *bucket = fhe.sub(*bucket, liquidity)?;
+        }
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.reserve_a = fhe.sub(pool.reserve_a, amount_a)?;
+        pool.reserve_b = fhe.sub(pool.reserve_b, amount_b)?;
+        pool.k_constant = fhe.mul(pool.reserve_a, pool.reserve_b)?;
+        pool.lp_supply = fhe.sub(pool.lp_supply, liquidity)?;
+        
+        let payout_a = fhe.add(amount_a, position.fees_owed_a)?;
+        let payout_b = fhe.add(amount_b, position.fees_owed_b)?;
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+        custody.transfer(
+            &ctx.accounts.vault_a,
+            &ctx.accounts.user_balance_a,
+            &ctx.accounts.token_a_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            payout_a,
+        )?;
+        custody.transfer(
+            &ctx.accounts.vault_b,
+            &ctx.accounts.user_balance_b,
+            &ctx.accounts.token_b_mint.to_account_info(),
+            &pool.to_account_info(),
+            &[seeds],
+            payout_b,
+        )?;
+        
+        msg!("Range position [{}, {}) closed", position.tick_lower, position.tick_upper);
+        Ok(())
+    }
+
+    /// Start liquidity-mining rewards for a pool (pool authority only)
+    /// 
+    /// `emission_rate` reward tokens per second are shared among LP positions
+    /// pro rata until `end_time`; fund the reward vault with fund_rewards and
+    /// keep its staked LP supply current with sync_reward_supply. Concentrated
+    /// pools earn through range fees instead
+    pub fn initialize_rewards(
+        ctx: Context<InitializeRewards>,
+        emission_rate: u64,
+        end_time: i64,
+    ) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.authority.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let now = Clock::get()?.unix_timestamp;
+        
+        let rewards = &mut ctx.accounts.rewards;
+        rewards.pool = ctx.accounts.pool.key();
+        rewards.reward_mint = ctx.accounts.reward_mint.key();
+        rewards.vault = ctx.accounts.reward_vault.key();
+        rewards.emission_rate = emission_rate;
+        rewards.end_time = end_time;
+        rewards.last_update_time = now;
+        rewards.reward_per_lp = fhe.constant(0)?;
+        rewards.bump = ctx.bumps.rewards;
+        ctx.accounts.pool.rewards_enabled = true;
+        
+        // The reward vault is an inco_token balance owned by the rewards PDA
+        let pool_key = ctx.accounts.pool.key();
+        let bump = [ctx.bumps.rewards];
+        let seeds: &[&[u8]] = &[b"rewards", pool_key.as_ref(), &bump];
+        let signer_seeds = &[seeds];
+        let cpi_accounts = InitializeOwnedBalance {
+            user_balance: ctx.accounts.reward_vault.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            owner: ctx.accounts.rewards.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.inco_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        inco_token::cpi::initialize_owned_balance(cpi_ctx)?;
+        
+        msg!("Rewards started: {} per second until {}", emission_rate, end_time);
+        Ok(())
+    }
+
+    /// Deposit reward tokens into the reward vault (pool authority only)
+    pub fn fund_rewards(ctx: Context<FundRewards>, encrypted_amount: u128) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        custody.transfer(
+            &ctx.accounts.funder_balance,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.reward_mint.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &[],
+            Euint128::wrap(encrypted_amount),
+        )?;
+        
+        msg!("Reward vault funded (amount encrypted)");
+        Ok(())
+    }
+
+    /// Change the reward emission rate and end time (pool authority only)
+    /// Rewards up to now accrue at the old rate first, so the LP supply must be synced
+    pub fn set_reward_emission(
+        ctx: Context<SetRewardEmission>,
+        emission_rate: u64,
+        end_time: i64,
+    ) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.authority.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        
+        let pool = &ctx.accounts.pool;
+        let rewards = &mut ctx.accounts.rewards;
+        require!(rewards.is_synced(pool), AmmError::RewardSupplyNotSynced);
+        rewards.accrue(&fhe, pool, Clock::get()?.unix_timestamp)?;
+        rewards.emission_rate = emission_rate;
+        rewards.end_time = end_time;
+        
+        msg!("Reward emission set to {} per second until {}", emission_rate, end_time);
+        Ok(())
+    }
+
+    /// Point the reward accumulator at the pool's current staked LP supply
+    /// 
+    /// Inco Lightning has no division, so accrual multiplies by an encrypted
+    /// inverse, floor(2^REWARD_INVERSE_BITS / staked), hinted by the caller
+    /// and checked here, where staked = lp_supply - tokenized_lp_supply. Anyone
+    /// able to decrypt the supply (e.g. a keeper the authority shares it with)
+    /// may sync. Every staked supply change accrues at the old supply first and
+    /// leaves the inverse stale; the emissions since are held until this runs,
+    /// and the supply they were earned at is the one synced here. A wrong hint
+    /// on a stale vault forfeits the held emissions; on a synced vault it
+    /// keeps the current inverse, so a bad caller cannot stop accrual
+    pub fn sync_reward_supply(
+        ctx: Context<SyncRewardSupply>,
+        encrypted_inverse_supply: u128,  // Encrypted hint: floor(2^REWARD_INVERSE_BITS / staked)
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.caller.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let now = Clock::get()?.unix_timestamp;
+        
+        let staked = if pool.tokenized_lp_supply.is_initialized() {
+            fhe.sub(pool.lp_supply, pool.tokenized_lp_supply)?
+        } else {
+            pool.lp_supply
+        };
+        let inverse = Euint128::wrap(encrypted_inverse_supply);
+        let numerator = fhe.constant(1 << REWARD_INVERSE_BITS)?;
+        let ok = fhe.check_quotient(numerator, staked, inverse, u64::MAX as u128)?;
+        
+        let rewards = &mut ctx.accounts.rewards;
+        let fallback = if rewards.is_synced(pool) && rewards.inverse_lp_supply.is_initialized() {
+            // Emissions so far belong to the current inverse, which a wrong hint keeps
+            rewards.accrue(&fhe, pool, now)?;
+            rewards.inverse_lp_supply
+        } else {
+            fhe.constant(0)?
+        };
+        rewards.inverse_lp_supply = fhe.select(ok, inverse, fallback)?;
+        rewards.synced_lp_supply = pool.lp_supply;
+        rewards.synced_tokenized_lp_supply = pool.tokenized_lp_supply;
+        rewards.accrue(&fhe, pool, now)?;
+        
+        msg!("Reward LP supply synced");
+        Ok(())
+    }
+
+    /// Pay the caller's accrued liquidity-mining rewards into their balance
+    /// Whatever an underfunded vault cannot pay stays owed
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.user.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        
+        let user_position = &mut ctx.accounts.user_position;
+        settle_rewards(&fhe, &ctx.accounts.pool, Some(&mut ctx.accounts.rewards), user_position, false)?;
+        require!(user_position.rewards_owed.is_initialized(), AmmError::NoRewards);
+        
+        let rewards = &ctx.accounts.rewards;
+        let bump = [rewards.bump];
+        let seeds: &[&[u8]] = &[b"rewards", rewards.pool.as_ref(), &bump];
+        let paid = custody.transfer(
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.user_reward_balance,
+            &ctx.accounts.reward_mint.to_account_info(),
+            &rewards.to_account_info(),
+            &[seeds],
+            user_position.rewards_owed,
+        )?;
+        user_position.rewards_owed = fhe.sub(user_position.rewards_owed, paid)?;
+        
+        msg!("Rewards claimed (amount encrypted)");
+        Ok(())
+    }
+
+    /// Update the swap fee (pool authority only)
+    pub fn set_fee(ctx: Context<PoolAdmin>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.fee_bps = fee_bps;
+        
+        msg!("Pool fee set to {} bps", fee_bps);
+        Ok(())
+    }
+
+    /// Set a pool's protocol share of the swap fee and who may collect it (factory admin only)
+    /// `share_bps` is a fraction of the fee itself, e.g. 2000 = 20% of fees
+    pub fn set_protocol_fee(
+        ctx: Context<SetProtocolFee>,
+        share_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(share_bps <= MAX_PROTOCOL_FEE_SHARE_BPS, AmmError::ProtocolFeeTooHigh);
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.protocol_fee_share_bps = share_bps;
+        pool.fee_recipient = fee_recipient;
+        
+        msg!("Protocol fee share set to {} bps of swap fees", share_bps);
+        Ok(())
+    }
+
+    /// Sweep accrued protocol fees from the vaults into the fee recipient's balances
+    /// Allowed while paused, like withdrawals
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let token_program = ctx.accounts.inco_token_program.to_account_info();
+        let custody = Custody::new(&token_program, &inco);
+        let pool_info = ctx.accounts.pool.to_account_info();
+        let pool = &mut ctx.accounts.pool;
+        
+        let (token_a_mint, token_b_mint) = (pool.token_a_mint, pool.token_b_mint);
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", token_a_mint.as_ref(), token_b_mint.as_ref(), &bump];
+        if pool.protocol_fees_a.is_initialized() {
+            custody.transfer(
+                &ctx.accounts.vault_a,
+                &ctx.accounts.recipient_balance_a,
+                &ctx.accounts.token_a_mint.to_account_info(),
+                &pool_info,
+                &[seeds],
+                pool.protocol_fees_a,
+            )?;
+            pool.protocol_fees_a = Euint128::default();
+        }
+        if pool.protocol_fees_b.is_initialized() {
+            custody.transfer(
+                &ctx.accounts.vault_b,
+                &ctx.accounts.recipient_balance_b,
+                &ctx.accounts.token_b_mint.to_account_info(),
+                &pool_info,
+                &[seeds],
+                pool.protocol_fees_b,
+            )?;
+            pool.protocol_fees_b = Euint128::default();
+        }
+        
+        msg!("Collected protocol fees (amounts encrypted)");
+        Ok(())
+    }
+
+    /// Propose a new pool authority; takes effect once they call accept_authority
+    /// Proposing Pubkey::default() cancels a pending transfer
+    pub fn transfer_authority(ctx: Context<PoolAdmin>, new_authority: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.pending_authority = new_authority;
+        
+        msg!("Pending pool authority: {}", new_authority);
+        Ok(())
+    }
+
+    /// Accept a pending authority transfer (signed by the proposed authority)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.new_authority.key();
+        pool.pending_authority = Pubkey::default();
+        
+        msg!("Pool authority transferred to {}", pool.authority);
+        Ok(())
+    }
+
+    /// Pause swaps and deposits for incident response
+    /// Withdrawals stay open so LPs can always exit
+    pub fn pause(ctx: Context<PoolAdmin>) -> Result<()> {
+        ctx.accounts.pool.paused = true;
+        msg!("Pool paused");
+        Ok(())
+    }
+
+    /// Resume swaps and deposits
+    pub fn unpause(ctx: Context<PoolAdmin>) -> Result<()> {
+        ctx.accounts.pool.paused = false;
+        msg!("Pool unpaused");
+        Ok(())
+    }
+
+    /// Ramp a StableSwap pool's amplification linearly to `future_amp`
+    /// Ramps last at least MIN_RAMP_DURATION and change A by at most MAX_AMP_CHANGE x.
+    /// 
+    /// Unlike Curve, swaps do not read the ramp directly: the stored D only holds
+    /// at the amplification it was synced at, and re-solving it needs hints. Swaps
+    /// price at the amplification of the last seed or sync_invariant, so the pool
+    /// follows the ramp in steps, one per sync. Anyone may sync, so keepers (or
+    /// any trader) set the step size; each step moves A at most as far as the
+    /// linear ramp has since the previous one, and the ramp itself stays linear
+    pub fn ramp_amp(ctx: Context<PoolAdmin>, future_amp: u64, future_time: i64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.pool_type == PoolType::StableSwap, AmmError::NotStablePool);
+        
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= pool.future_amp_time, AmmError::RampInProgress);
+        require!(future_time >= now + MIN_RAMP_DURATION, AmmError::InvalidRamp);
+        require!((MIN_AMP..=MAX_AMP).contains(&future_amp), AmmError::InvalidAmp);
+        
+        let current_amp = pool.amp(now);
+        require!(
+            future_amp <= current_amp * MAX_AMP_CHANGE && future_amp * MAX_AMP_CHANGE >= current_amp,
+            AmmError::InvalidAmp
+        );
+        
+        pool.initial_amp = current_amp;
+        pool.future_amp = future_amp;
+        pool.initial_amp_time = now;
+        pool.future_amp_time = future_time;
+        
+        msg!("Ramping amplification {} -> {} by {}", current_amp, future_amp, future_time);
+        Ok(())
+    }
+
+    /// Store a StableSwap pool's invariant D for its current balances (permissionless)
+    /// 
+    /// Banked LP fees are folded into the reserves first, and the current
+    /// amplification is taken for the swaps that follow. Swaps keep working
+    /// between syncs (seeds and deposits set D themselves); a sync compounds the
+    /// banked fees and moves the pool along an amplification ramp (see ramp_amp).
+    /// 
+    /// Curve solves D with bounded Newton iterations, each of which divides.
+    /// Inco Lightning has no division, so instead the caller supplies D and four
+    /// encrypted hints computed off-chain: [p1, p2] bound the curve's cubic term from above at D and
+    /// [q1, q2] from below at D + STABLE_SYNC_TOLERANCE (see stable_above_curve
+    /// and stable_below_curve), so the stored D is exact up to the tolerance.
+    /// Balances and D must be at most STABLE_VALUE_BOUND. A wrong hint leaves
+    /// the pool as it was
+    pub fn sync_invariant(
+        ctx: Context<SyncInvariant>,
+        encrypted_invariant: u128,    // Encrypted invariant D of the folded balances
+        encrypted_hints: Vec<u128>,   // Encrypted [p1, p2, q1, q2] (see above)
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.pool_type == PoolType::StableSwap, AmmError::NotStablePool);
+        require!(pool.lp_supply.is_initialized(), AmmError::InsufficientLiquidity);
+        require!(encrypted_hints.len() == 4, AmmError::InvalidHints);
+        
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.caller.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        let zero = fhe.constant(0)?;
+        let hints: Vec<Euint128> = encrypted_hints.into_iter().map(Euint128::wrap).collect();
+        
+        let x = fhe.accumulate(pool.lp_fees_a, pool.reserve_a)?;
+        let y = fhe.accumulate(pool.lp_fees_b, pool.reserve_b)?;
+        let d = Euint128::wrap(encrypted_invariant);
+        let amp = pool.amp(Clock::get()?.unix_timestamp);
+        let ann = fhe.constant(amp as u128 * 4)?;
+        let bounded = fhe.ge(fhe.constant(STABLE_VALUE_BOUND)?, fhe.or(fhe.or(x, y)?, d)?)?;
+        let above = stable_above_curve(&fhe, ann, x, y, d, [hints[0], hints[1]])?;
+        let ceiling = fhe.add(d, fhe.constant(STABLE_SYNC_TOLERANCE)?)?;
+        let below = stable_below_curve(&fhe, ann, x, y, ceiling, [hints[2], hints[3]])?;
+        let ok = fhe.and(bounded, fhe.and(above, below)?)?;
+        
+        pool.k_constant = fhe.select(ok, d, pool.k_constant)?;
+        // Left at zero by a failed first sync, which D = 0 keeps unswappable
+        let synced_ann = if pool.invariant_ann.is_initialized() { pool.invariant_ann } else { zero };
+        pool.invariant_ann = fhe.select(ok, ann, synced_ann)?;
+        if pool.lp_fees_a.is_initialized() {
+            pool.reserve_a = fhe.select(ok, x, pool.reserve_a)?;
+            pool.lp_fees_a = fhe.select(ok, zero, pool.lp_fees_a)?;
+        }
+        if pool.lp_fees_b.is_initialized() {
+            pool.reserve_b = fhe.select(ok, y, pool.reserve_b)?;
+            pool.lp_fees_b = fhe.select(ok, zero, pool.lp_fees_b)?;
+        }
+        
+        msg!("StableSwap invariant synced at amplification {}", amp);
+        Ok(())
+    }
+
+    /// Point an Oracle pool at its price feed and set the feed's acceptance limits
+    /// 
+    /// The feed prices token A in units of token B, or B in units of A when
+    /// `inverted`. Prices older than `max_price_age` seconds, or with a
+    /// confidence interval wider than `max_confidence_bps` of the price, are rejected,
+    /// as is any update for a feed other than `feed_id`
+    pub fn set_oracle(
+        ctx: Context<PoolAdmin>,
+        oracle: Pubkey,
+        feed_id: [u8; 32],
+        inverted: bool,
+        max_price_age: u64,
+        max_confidence_bps: u16,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.pool_type == PoolType::Oracle, AmmError::NotOraclePool);
+        require!(oracle != Pubkey::default(), AmmError::InvalidOracle);
+        require!(max_price_age > 0, AmmError::InvalidOracle);
+        require!(max_confidence_bps <= MAX_ORACLE_CONFIDENCE_BPS, AmmError::InvalidOracle);
+        
+        pool.oracle = oracle;
+        pool.oracle_feed_id = feed_id;
+        pool.oracle_inverted = inverted;
+        pool.max_price_age = max_price_age;
+        pool.max_confidence_bps = max_confidence_bps;
+        
+        msg!("Oracle set to {} (max age {}s, max confidence {} bps)", oracle, max_price_age, max_confidence_bps);
+        Ok(())
+    }
+
+    /// Create a mock price feed with the PriceUpdateV2 layout, for local testing
+    /// Oracle pools accept it exactly like a fully verified Pyth update, so it
+    /// only exists in builds with the `mock-oracle` feature
+    #[cfg(feature = "mock-oracle")]
+    pub fn initialize_mock_price_feed(
+        ctx: Context<InitializeMockPriceFeed>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.price_feed;
+        feed.write_authority = ctx.accounts.authority.key();
+        feed.verification_level = VerificationLevel::Full;
+        feed.price_message = PriceFeedMessage {
+            feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time: clock.unix_timestamp,
+            prev_publish_time: clock.unix_timestamp,
+            ema_price: price,
+            ema_conf: conf,
+        };
+        feed.posted_slot = clock.slot;
+        
+        msg!("Mock price feed created: {} x 10^{}", price, exponent);
+        Ok(())
+    }
+
+    /// Publish a new price to a mock feed, stamped with the current time
+    #[cfg(feature = "mock-oracle")]
+    pub fn set_mock_price(
+        ctx: Context<SetMockPrice>,
+        price: i64,
+        conf: u64,
+        exponent: i32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let message = &mut ctx.accounts.price_feed.price_message;
+        message.prev_publish_time = message.publish_time;
+        message.price = price;
+        message.conf = conf;
+        message.exponent = exponent;
+        message.publish_time = clock.unix_timestamp;
+        message.ema_price = price;
+        message.ema_conf = conf;
+        ctx.accounts.price_feed.posted_slot = clock.slot;
+        
+        msg!("Mock price updated: {} x 10^{}", price, exponent);
+        Ok(())
+    }
+
+    /// Freeze the amplification at its current ramped value
+    pub fn stop_ramp_amp(ctx: Context<PoolAdmin>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.pool_type == PoolType::StableSwap, AmmError::NotStablePool);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let current_amp = pool.amp(now);
+        pool.initial_amp = current_amp;
+        pool.future_amp = current_amp;
+        pool.initial_amp_time = now;
+        pool.future_amp_time = now;
+        
+        msg!("Amplification fixed at {}", current_amp);
+        Ok(())
+    }
+
+    /// Verify a hinted swap output without executing the swap
+    /// 
+    /// There is no encrypted division, so the pool cannot compute an output on
+    /// its own: like swap, it checks the caller's hints against the curve. The
+    /// hinted output (after fee) and the fee are returned as return data, so
+    /// routers can read them after a CPI; both are zero where swap would reject
+    /// the hint or lack the inventory. Constant-product hints must be the exact
+    /// output (as in try_fill); on other curves a passing hint is a lower bound
+    /// the pool would pay. Pass the Inco allowance PDAs of the two handles to
+    /// let the caller decrypt them (simulate once without them to learn the handles)
+    pub fn verify_quote(
+        ctx: Context<VerifyQuote>,
+        encrypted_amount_in: u128,   // Encrypted input amount
+        a_to_b: bool,                // Direction: true sells token A for token B
+        encrypted_hints: Vec<u128>,  // Encrypted curve hints, as for swap
+    ) -> Result<QuoteResult> {
+        let inco = ctx.accounts.inco_lightning_program.to_account_info();
+        let signer = ctx.accounts.caller.to_account_info();
+        let fhe = Fhe::new(&inco, &signer);
+        
+        let oracle = ctx.accounts.oracle.as_ref().map(|oracle| oracle.to_account_info());
+        let grid = ctx.accounts.tick_grid.as_deref().map(|grid| &**grid);
+        let amount_in = Euint128::wrap(encrypted_amount_in);
+        let hints: Vec<Euint128> = encrypted_hints.into_iter().map(Euint128::wrap).collect();
+        let pool = &ctx.accounts.pool;
+        let quote = quote_swap(&fhe, pool, a_to_b, amount_in, &hints, oracle.as_ref(), grid)?;
+        let mut verified = quote.fillable;
+        if pool.pool_type == PoolType::ConstantProduct {
+            let (reserve_in, reserve_out) = if a_to_b {
+                (pool.reserve_a, pool.reserve_b)
+            } else {
+                (pool.reserve_b, pool.reserve_a)
+            };
+            let exact = constant_product_exact(
+                &fhe,
+                pool.k_constant,
+                reserve_in,
+                reserve_out,
+                amount_in,
+                quote.amount_out,
+                pool.fee_bps,
+            )?;
+            verified = fhe.and(verified, exact)?;
+        }
+        let fee = fhe.sub(amount_in, input_after_fee(&fhe, amount_in, pool.fee_bps)?)?;
+        let zero = fhe.constant(0)?;
+        let amount_out = fhe.select(verified, quote.amount_out, zero)?;
+        let fee = fhe.select(verified, fee, zero)?;
+        
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let grants = [
+            (amount_out, ctx.accounts.amount_out_allowance.as_ref()),
+            (fee, ctx.accounts.fee_allowance.as_ref()),
+        ];
+        for (handle, allowance) in grants {
+            if let Some(allowance) = allowance {
+                fhe.allow(handle, allowance, &signer, &system_program)?;
+            }
+        }
+        
+        Ok(QuoteResult { amount_out, fee })
+    }
+
+    /// Get pool info (only public data)
+    pub fn get_pool_info(ctx: Context<GetPoolInfo>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        msg!("Pool: {} <-> {}", pool.token_a_mint, pool.token_b_mint);
+        msg!("Fee: {} bps", pool.fee_bps);
+        msg!("Type: {:?}", pool.pool_type);
+        if pool.pool_type == PoolType::StableSwap {
+            msg!("Amplification: {}", pool.amp(Clock::get()?.unix_timestamp));
+        }
+        if pool.pool_type == PoolType::Oracle {
+            msg!("Oracle: {} (inverted: {})", pool.oracle, pool.oracle_inverted);
+        }
+        msg!("Paused: {}", pool.paused);
+        msg!("Locked liquidity: {} LP", pool.locked_liquidity);
+        msg!("Reserves: ENCRYPTED (privacy preserved!)");
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ACCOUNT STRUCTURES
+// ============================================================================
+
+/// Private Liquidity Pool
+/// All monetary values are encrypted using Inco FHE
+#[account]
+pub struct PrivatePool {
+    /// Token A mint address
+    pub token_a_mint: Pubkey,
+    /// Token B mint address
+    pub token_b_mint: Pubkey,
+    /// Encrypted reserve of token A
+    pub reserve_a: Euint128,
+    /// Encrypted reserve of token B
+    pub reserve_b: Euint128,
+    /// Encrypted constant product (k = reserve_a * reserve_b)
+    /// StableSwap pools keep their invariant D here instead (see sync_invariant)
+    pub k_constant: Euint128,
+    /// Encrypted total LP token supply
+    pub lp_supply: Euint128,
+    /// Fee in basis points (0.01% units) - public
+    pub fee_bps: u16,
+    /// Pool authority
+    pub authority: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+    /// Initialization flag
+    pub is_initialized: bool,
+    /// Pool-owned inco_token balance holding token A
+    pub vault_a: Pubkey,
+    /// Pool-owned inco_token balance holding token B
+    pub vault_b: Pubkey,
+    /// Proposed authority awaiting accept_authority (default = none)
+    pub pending_authority: Pubkey,
+    /// Swaps and deposits are blocked while paused
+    pub paused: bool,
+    /// Protocol share of the swap fee, in basis points of the fee - public
+    pub protocol_fee_share_bps: u16,
+    /// Account allowed to collect protocol fees
+    pub fee_recipient: Pubkey,
+    /// Encrypted protocol fees accrued in token A (held in vault_a)
+    pub protocol_fees_a: Euint128,
+    /// Encrypted protocol fees accrued in token B (held in vault_b)
+    pub protocol_fees_b: Euint128,
+    /// Pricing curve
+    pub pool_type: PoolType,
+    /// StableSwap amplification at the start of the current ramp
+    pub initial_amp: u64,
+    /// StableSwap amplification at the end of the current ramp
+    pub future_amp: u64,
+    /// Unix timestamp the current ramp started
+    pub initial_amp_time: i64,
+    /// Unix timestamp the current ramp ends
+    pub future_amp_time: i64,
+    /// Price feed account for Oracle pools (default = not configured)
+    pub oracle: Pubkey,
+    /// Whether the feed prices token B in units of token A
+    pub oracle_inverted: bool,
+    /// Oldest accepted price, in seconds
+    pub max_price_age: u64,
+    /// Widest accepted confidence interval, in basis points of the price
+    pub max_confidence_bps: u16,
+    /// Token A mint decimals
+    pub decimals_a: u8,
+    /// Token B mint decimals
+    pub decimals_b: u8,
+    /// cToken that tokenized LP shares are minted as (default until set_lp_mint)
+    pub lp_mint: Pubkey,
+    /// Whether LP changes must settle against the pool's RewardVault
+    pub rewards_enabled: bool,
+    /// LP tokens locked by the first deposit (MINIMUM_LIQUIDITY once seeding
+    /// was attempted; the locked tokens exist once the encrypted supply is nonzero)
+    pub locked_liquidity: u64,
+    /// Encrypted 4 * A the stored StableSwap invariant was synced at (unset until
+    /// the first seed)
+    pub invariant_ann: Euint128,
+    /// Encrypted StableSwap LP fees in token A, held in vault_a outside the
+    /// reserves until sync_invariant compounds them
+    pub lp_fees_a: Euint128,
+    /// Encrypted StableSwap LP fees in token B, held in vault_b likewise
+    pub lp_fees_b: Euint128,
+    /// Encrypted LP supply held as LP cTokens rather than in positions (part of
+    /// lp_supply, but outside the staked supply that earns rewards)
+    pub tokenized_lp_supply: Euint128,
+    /// Price feed id the oracle account must carry
+    pub oracle_feed_id: [u8; 32],
+}
+
+impl PrivatePool {
+    // 32 + 32 + 16 + 16 + 16 + 16 + 2 + 32 + 1 + 1 + 32 + 32 + 32 + 1 + 2 + 32 + 16 + 16
+    //   + 1 + 8 + 8 + 8 + 8 + 32 + 1 + 8 + 2 + 1 + 1 + 32 + 1 + 8 + 16 + 16 + 16 + 16 + 32 = 542 bytes
+    pub const LEN: usize =
+        32 + 32 + 16 + 16 + 16 + 16 + 2 + 32 + 1 + 1 + 32 + 32 + 32 + 1 + 2 + 32 + 16 + 16
+        + 1 + 8 + 8 + 8 + 8 + 32 + 1 + 8 + 2 + 1 + 1 + 32 + 1 + 8 + 16 + 16 + 16 + 16 + 32;
+
+    /// StableSwap amplification at `now`, linearly interpolated along the ramp
+    pub fn amp(&self, now: i64) -> u64 {
+        if now >= self.future_amp_time {
+            return self.future_amp;
+        }
+        let elapsed = (now - self.initial_amp_time) as u128;
+        let duration = (self.future_amp_time - self.initial_amp_time) as u128;
+        let (initial, future) = (self.initial_amp as u128, self.future_amp as u128);
+        let amp = if future > initial {
+            initial + (future - initial) * elapsed / duration
+        } else {
+            initial - (initial - future) * elapsed / duration
+        };
+        amp as u64
+    }
+
+    /// Mint bought by a swap in the given direction
+    pub fn output_mint(&self, a_to_b: bool) -> Pubkey {
+        if a_to_b {
+            self.token_b_mint
+        } else {
+            self.token_a_mint
+        }
+    }
+
+    /// Vault holding the given mint, if it is one of the pool's tokens
+    pub fn vault_for(&self, mint: &Pubkey) -> Option<Pubkey> {
+        if *mint == self.token_a_mint {
+            Some(self.vault_a)
+        } else if *mint == self.token_b_mint {
+            Some(self.vault_b)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pricing curve of a pool
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PoolType {
+    /// x * y = k
+    ConstantProduct = 0,
+    /// Curve-style invariant with an amplification coefficient, for pegged pairs
+    StableSwap = 1,
+    /// Fills at an external oracle price against the pool's inventory
+    Oracle = 2,
+    /// Liquidity concentrated in tick ranges (see TickGrid)
+    Concentrated = 3,
+}
+
+impl PoolType {
+    /// Encrypted hints a swap against this curve takes (see quote_swap)
+    pub fn hint_count(&self) -> usize {
+        match self {
+            PoolType::ConstantProduct => 1,
+            PoolType::StableSwap => 3,
+            PoolType::Concentrated => 4,
+            PoolType::Oracle => 0,
+        }
+    }
+}
+
+/// Global pool-creation config, a singleton PDA
+#[account]
+pub struct Factory {
+    /// May update the factory
+    pub admin: Pubkey,
+    /// Fee for pools created without an explicit fee
+    pub default_fee_bps: u16,
+    /// Whether new pools start with the protocol fee below
+    pub protocol_fee_on: bool,
+    /// Protocol share of the swap fee for new pools
+    pub protocol_fee_share_bps: u16,
+    /// Protocol fee recipient for new pools
+    pub fee_recipient: Pubkey,
+    /// Lamports paid to the admin for each pool created
+    pub creation_fee_lamports: u64,
+    /// Pools created so far (the next registry slot)
+    pub pool_count: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Factory {
+    // 32 + 2 + 1 + 2 + 32 + 8 + 8 + 1 = 86 bytes
+    pub const LEN: usize = 32 + 2 + 1 + 2 + 32 + 8 + 8 + 1;
+}
+
+/// One page of the on-chain pool registry, filled in creation order
+/// Page n holds pools n * REGISTRY_PAGE_SIZE .. (n + 1) * REGISTRY_PAGE_SIZE
+#[account]
+pub struct PoolRegistryPage {
+    /// Page index
+    pub page: u64,
+    /// Pool addresses
+    pub pools: Vec<Pubkey>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PoolRegistryPage {
+    // 8 + (4 + 32 * 32) + 1 = 1037 bytes
+    pub const LEN: usize = 8 + (4 + REGISTRY_PAGE_SIZE * 32) + 1;
+}
+
+/// Initialized ticks of a concentrated pool and the liquidity between them
+/// 
+/// Bucket k spans ticks[k]..ticks[k + 1]. Each bucket holds the encrypted
+/// liquidity of every range position covering it and its fee growth per unit
+/// of liquidity; the price itself stays encrypted
+#[account]
+pub struct TickGrid {
+    /// Pool this grid belongs to
+    pub pool: Pubkey,
+    /// Position ticks must be multiples of this
+    pub tick_spacing: u16,
+    /// Encrypted current sqrt price (SQRT_PRICE_BITS fixed point)
+    pub sqrt_price: Euint128,
+    /// Encrypted 1 / sqrt_price (SQRT_PRICE_BITS fixed point), kept alongside it
+    /// since range amounts need both and Inco Lightning cannot divide
+    pub inverse_sqrt_price: Euint128,
+    /// Initialized ticks, ascending
+    pub ticks: Vec<i32>,
+    /// Encrypted liquidity per bucket
+    pub liquidity: Vec<Euint128>,
+    /// Encrypted token A fees per unit of liquidity, per bucket (FEE_GROWTH_BITS)
+    pub fee_growth_a: Vec<Euint128>,
+    /// Encrypted token B fees per unit of liquidity, per bucket (FEE_GROWTH_BITS)
+    pub fee_growth_b: Vec<Euint128>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl TickGrid {
+    // 32 + 2 + 16 + 16 + (4 + 8 * 4) + 3 * (4 + 7 * 16) + 1 = 451 bytes
+    pub const LEN: usize =
+        32 + 2 + 16 + 16 + (4 + MAX_TICKS * 4) + 3 * (4 + (MAX_TICKS - 1) * 16) + 1;
+
+    /// Buckets lower..upper covered by a range between two initialized ticks
+    pub fn bucket_range(&self, tick_lower: i32, tick_upper: i32) -> Result<(usize, usize)> {
+        let lower = self.ticks.binary_search(&tick_lower);
+        let upper = self.ticks.binary_search(&tick_upper);
+        match (lower, upper) {
+            (Ok(lower), Ok(upper)) => Ok((lower, upper)),
+            _ => err!(AmmError::InvalidTickRange),
+        }
+    }
+
+    /// Add a tick, splitting the bucket it falls in
+    /// Both halves keep the bucket's liquidity; the upper half's fee growth
+    /// starts at zero, so the growth summed over any existing range is unchanged
+    pub fn insert_tick(&mut self, tick: i32, zero: Euint128) -> Result<()> {
+        let index = match self.ticks.binary_search(&tick) {
+            Ok(_) => return Ok(()),
+            Err(index) => index,
+        };
+        require!(self.ticks.len() < MAX_TICKS, AmmError::TickGridFull);
+        let existing = self.ticks.len();
+        self.ticks.insert(index, tick);
+        
+        if existing == 0 {
+            return Ok(());
+        }
+        let (bucket, liquidity) = if index == 0 || index == existing {
+            // New outermost bucket: nothing covers it yet
+            (index.min(existing - 1), zero)
+        } else {
+            (index, self.liquidity[index - 1])
+        };
+        self.liquidity.insert(bucket, liquidity);
+        self.fee_growth_a.insert(bucket, zero);
+        self.fee_growth_b.insert(bucket, zero);
+        Ok(())
+    }
+}
+
+/// Concentrated-liquidity position between two ticks (encrypted liquidity)
+#[account]
+pub struct RangePosition {
+    /// Owner of this position
+    pub owner: Pubkey,
+    /// Pool this position is in
+    pub pool: Pubkey,
+    /// Lower tick (inclusive)
+    pub tick_lower: i32,
+    /// Upper tick (exclusive)
+    pub tick_upper: i32,
+    /// Encrypted liquidity
+    pub liquidity: Euint128,
+    /// Encrypted token A fee growth inside the range at the last settlement
+    pub fee_growth_inside_a: Euint128,
+    /// Encrypted token B fee growth inside the range at the last settlement
+    pub fee_growth_inside_b: Euint128,
+    /// Encrypted token A fees earned and not yet collected
+    pub fees_owed_a: Euint128,
+    /// Encrypted token B fees earned and not yet collected
+    pub fees_owed_b: Euint128,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl RangePosition {
+    // 32 + 32 + 4 + 4 + 16 * 5 + 1 = 153 bytes
+    pub const LEN: usize = 32 + 32 + 4 + 4 + 16 * 5 + 1;
+}
+
+/// Pyth pull-oracle price update (same layout and discriminator as the
+/// receiver program's PriceUpdateV2), also created by initialize_mock_price_feed
+/// in `mock-oracle` builds
+#[account]
+pub struct PriceUpdateV2 {
+    /// Account allowed to post updates
+    pub write_authority: Pubkey,
+    /// How many guardian signatures the update was verified with
+    pub verification_level: VerificationLevel,
+    /// The price itself
+    pub price_message: PriceFeedMessage,
+    /// Slot the update was posted in
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    // 32 + 2 + 84 + 8 = 126 bytes
+    pub const LEN: usize = 32 + 2 + PriceFeedMessage::LEN + 8;
+}
+
+/// Wormhole verification of a price update
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// Pyth price feed message: price = price * 10^exponent
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+impl PriceFeedMessage {
+    // 32 + 8 + 8 + 4 + 8 + 8 + 8 + 8 = 84 bytes
+    pub const LEN: usize = 32 + 8 + 8 + 4 + 8 + 8 + 8 + 8;
+}
+
+/// User's LP position in a pool (encrypted)
+#[account]
+pub struct UserPosition {
+    /// Owner of this position
+    pub owner: Pubkey,
+    /// Pool this position is in
+    pub pool: Pubkey,
+    /// Encrypted LP token balance
+    pub lp_amount: Euint128,
+    /// Encrypted rewards already accounted for: lp_amount * reward_per_lp at the last settlement
+    pub reward_debt: Euint128,
+    /// Encrypted rewards earned and not yet claimed
+    pub rewards_owed: Euint128,
+}
+
+impl UserPosition {
+    pub const LEN: usize = 32 + 32 + 16 + 16 + 16; // 112 bytes
+}
+
+/// Liquidity-mining rewards of one pool
+/// 
+/// The emission rate is public; each position's share of it stays encrypted
+/// through the encrypted reward-per-LP accumulator
+#[account]
+pub struct RewardVault {
+    /// Pool whose LPs earn these rewards
+    pub pool: Pubkey,
+    /// Reward token
+    pub reward_mint: Pubkey,
+    /// inco_token balance holding the rewards (owned by this account)
+    pub vault: Pubkey,
+    /// Reward tokens emitted per second
+    pub emission_rate: u64,
+    /// Emission stops at this unix timestamp
+    pub end_time: i64,
+    /// Timestamp reward_per_lp was last brought up to date
+    pub last_update_time: i64,
+    /// Encrypted rewards per LP token since the start (REWARD_PER_LP_BITS fixed point)
+    pub reward_per_lp: Euint128,
+    /// Encrypted floor(2^REWARD_INVERSE_BITS / staked LP supply), or zero if its hint failed
+    pub inverse_lp_supply: Euint128,
+    /// Pool lp_supply handle inverse_lp_supply was checked against (set by sync_reward_supply)
+    pub synced_lp_supply: Euint128,
+    /// Pool tokenized_lp_supply handle inverse_lp_supply was checked against
+    pub synced_tokenized_lp_supply: Euint128,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl RewardVault {
+    // 32 + 32 + 32 + 8 + 8 + 8 + 16 + 16 + 16 + 16 + 1 = 185 bytes
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 16 + 16 + 16 + 16 + 1;
+
+    /// Whether inverse_lp_supply belongs to the pool's staked LP supply
+    /// Every supply update issues a new handle, so comparing handles is enough
+    pub fn is_synced(&self, pool: &PrivatePool) -> bool {
+        pool.lp_supply == self.synced_lp_supply
+            && pool.tokenized_lp_supply == self.synced_tokenized_lp_supply
+    }
+
+    /// Add the emissions since the last update to reward_per_lp
+    /// 
+    /// Emissions before the first sync or under a failed hint are forfeited;
+    /// while the inverse is stale they are held for the next sync_reward_supply
+    pub fn accrue(&mut self, fhe: &Fhe, pool: &PrivatePool, now: i64) -> Result<()> {
+        if !self.is_synced(pool) {
+            return Ok(());
+        }
+        let until = now.min(self.end_time);
+        if until > self.last_update_time && self.inverse_lp_supply.is_initialized() {
+            let elapsed = (until - self.last_update_time) as u128;
+            // emitted < 2^64 keeps emitted * inverse_lp_supply below 2^128
+            let emitted = (self.emission_rate as u128)
+                .checked_mul(elapsed)
+                .filter(|emitted| *emitted <= u64::MAX as u128)
+                .ok_or(error!(AmmError::EmissionOverflow))?;
+            let shift = fhe.constant((REWARD_INVERSE_BITS - REWARD_PER_LP_BITS) as u128)?;
+            let scaled = fhe.mul(self.inverse_lp_supply, fhe.constant(emitted)?)?;
+            self.reward_per_lp = fhe.add(self.reward_per_lp, fhe.shr(scaled, shift)?)?;
+        }
+        self.last_update_time = self.last_update_time.max(now);
+        Ok(())
+    }
+
+    /// Rewards `lp_amount` has earned since the start: lp_amount * reward_per_lp
+    pub fn accrued(&self, fhe: &Fhe, lp_amount: Euint128) -> Result<Euint128> {
+        let shift = fhe.constant(REWARD_PER_LP_BITS as u128)?;
+        fhe.shr(fhe.mul(lp_amount, self.reward_per_lp)?, shift)
+    }
+}
+
+/// Per-swap receipt (encrypted amounts), closed by claim_swap_output
+#[account]
+pub struct SwapReceipt {
+    /// Owner of this receipt
+    pub owner: Pubkey,
+    /// Pool the swap executed against
+    pub pool: Pubkey,
+    /// Caller-chosen index that makes the receipt PDA unique
+    pub nonce: u64,
+    /// Encrypted input amount actually swapped (zero if slippage failed)
+    pub amount_in: Euint128,
+    /// Encrypted output amount owed to the owner
+    pub amount_out: Euint128,
+    /// Encrypted flag: whether the slippage check passed
+    pub success: Ebool,
+    /// Slot the swap executed in
+    pub slot: u64,
+    /// true = A→B, false = B→A
+    pub a_to_b: bool,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SwapReceipt {
+    // 32 + 32 + 8 + 16 + 16 + 16 + 8 + 1 + 1 = 130 bytes
+    pub const LEN: usize = 32 + 32 + 8 + 16 + 16 + 16 + 8 + 1 + 1;
+}
+
+/// Resting limit order with escrowed input (encrypted size and price)
+#[account]
+pub struct LimitOrder {
+    /// Owner of this order
+    pub owner: Pubkey,
+    /// Pool the order rests on
+    pub pool: Pubkey,
+    /// Caller-chosen index that makes the order PDA unique
+    pub nonce: u64,
+    /// true = sells A for B, false = sells B for A
+    pub a_to_b: bool,
+    /// Encrypted input still escrowed (zero once filled)
+    pub amount_in: Euint128,
+    /// Encrypted minimum output per input unit (LIMIT_PRICE_BITS fixed point)
+    pub limit_price: Euint128,
+    /// Encrypted output owed to the owner
+    pub amount_out: Euint128,
+    /// Encrypted flag: whether any try_fill executed (unset before the first)
+    pub filled: Ebool,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    // 32 + 32 + 8 + 1 + 16 + 16 + 16 + 16 + 1 = 138 bytes
+    pub const LEN: usize = 32 + 32 + 8 + 1 + 16 + 16 + 16 + 16 + 1;
+}
+
+/// Order queue of one batch-auction epoch on a pool
+#[account]
+pub struct BatchAuction {
+    /// Pool the batch settles against
+    pub pool: Pubkey,
+    /// Epoch index (slot / BATCH_EPOCH_SLOTS)
+    pub epoch: u64,
+    /// First slot after the epoch; orders close and settlement opens here
+    pub end_slot: u64,
+    /// Whether settle_batch has run
+    pub settled: bool,
+    /// Encrypted total of token A sold by all orders
+    pub total_a_in: Euint128,
+    /// Encrypted total of token B sold by all orders
+    pub total_b_in: Euint128,
+    /// Encrypted clearing price of token A in B, net of the pool fee (set on settlement)
+    pub price_a_in_b: Euint128,
+    /// Encrypted clearing price of token B in A, net of the pool fee (set on settlement)
+    pub price_b_in_a: Euint128,
+    /// Encrypted flag: the settlement price hints checked out
+    pub settle_ok: Ebool,
+    /// Queued orders, at most MAX_BATCH_ORDERS
+    pub orders: Vec<BatchOrder>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl BatchAuction {
+    // 32 + 8 + 8 + 1 + 16 + 16 + 16 + 16 + 16 + (4 + 16 * 65) + 1 = 1174 bytes
+    pub const LEN: usize =
+        32 + 8 + 8 + 1 + 16 + 16 + 16 + 16 + 16 + (4 + MAX_BATCH_ORDERS * BatchOrder::LEN) + 1;
+
+    /// Mark `owner`'s order at `index` claimed, returning it
+    pub fn take_order(&mut self, index: u8, owner: Pubkey) -> Result<BatchOrder> {
+        let order = self
+            .orders
+            .get_mut(index as usize)
+            .ok_or(error!(AmmError::InvalidOrder))?;
+        require!(order.owner == owner, AmmError::InvalidOwner);
+        require!(!order.claimed, AmmError::OrderAlreadyClaimed);
+        order.claimed = true;
+        Ok(*order)
+    }
+}
+
+/// One encrypted order in a batch auction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BatchOrder {
+    /// Owner of this order
+    pub owner: Pubkey,
+    /// Encrypted token A sold
+    pub amount_a_in: Euint128,
+    /// Encrypted token B sold
+    pub amount_b_in: Euint128,
+    /// Whether the order has been claimed or refunded
+    pub claimed: bool,
+}
+
+impl BatchOrder {
+    pub const LEN: usize = 32 + 16 + 16 + 1; // 65 bytes
+}
+
+// ============================================================================
+// INSTRUCTION CONTEXTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PrivatePool::LEN,
+        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(mut, seeds = [b"factory"], bump = factory.bump)]
+    pub factory: Box<Account<'info, Factory>>,
+    
+    /// Registry page the pool is appended to (page pool_count / REGISTRY_PAGE_SIZE)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolRegistryPage::LEN,
+        seeds = [
+            b"registry",
+            &(factory.pool_count / REGISTRY_PAGE_SIZE as u64).to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
+    
+    /// CHECK: Receives the creation fee - validated via address constraint
+    #[account(mut, address = factory.admin)]
+    pub fee_collector: UncheckedAccount<'info>,
+    
+    /// Token A mint (the lower of the two mint addresses)
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    /// Token B mint (the higher of the two mint addresses)
+    #[account(
+        constraint = token_b_mint.key() != token_a_mint.key() @ AmmError::IdenticalMints,
+        constraint = token_a_mint.key() < token_b_mint.key() @ AmmError::MintsNotCanonical,
+    )]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: Pool's token A balance PDA - created and validated by inco_token
+    #[account(mut)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B balance PDA - created and validated by inco_token
+    #[account(mut)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+        constraint = pool.pool_type != PoolType::Concentrated @ AmmError::UnsupportedPoolType,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::LEN,
+        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    /// Liquidity-mining rewards, required once the pool has them
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Option<Box<Account<'info, RewardVault>>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// User's token A balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_a.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_a.mint == token_a_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_a: Box<Account<'info, UserBalance>>,
+    
+    /// User's token B balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_b.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_b.mint == token_b_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_b: Box<Account<'info, UserBalance>>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = pool.fee_recipient == fee_recipient.key() @ AmmError::InvalidAuthority,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: Fee recipient's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub recipient_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Fee recipient's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub recipient_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    pub fee_recipient: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = pool.pool_type != PoolType::Concentrated @ AmmError::UnsupportedPoolType,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    /// Liquidity-mining rewards, required once the pool has them
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Option<Box<Account<'info, RewardVault>>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferPosition<'info> {
+    #[account(constraint = pool.is_initialized @ AmmError::PoolNotInitialized)]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::LEN,
+        seeds = [b"position", pool.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_position: Account<'info, UserPosition>,
+    
+    /// Liquidity-mining rewards, required once the pool has them
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Option<Box<Account<'info, RewardVault>>>,
+    
+    /// CHECK: Recipient wallet, only used to derive its position
+    #[account(constraint = recipient.key() != user.key() @ AmmError::InvalidOwner)]
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLpMint<'info> {
+    #[account(
+        mut,
+        constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        constraint = lp_mint.is_initialized @ AmmError::InvalidLpMint,
+        constraint = lp_mint.mint_authority == COption::Some(pool.key()) @ AmmError::InvalidLpMint,
+    )]
+    pub lp_mint: Box<Account<'info, IncoMint>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TokenizePosition<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = pool.lp_mint != Pubkey::default() @ AmmError::InvalidLpMint,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_position.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    /// Liquidity-mining rewards, required once the pool has them
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Option<Box<Account<'info, RewardVault>>>,
+    
+    #[account(mut, address = pool.lp_mint)]
+    pub lp_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's LP token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_lp_balance: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemPositionTokens<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = pool.lp_mint != Pubkey::default() @ AmmError::InvalidLpMint,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::LEN,
+        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    /// Liquidity-mining rewards, required once the pool has them
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Option<Box<Account<'info, RewardVault>>>,
+    
+    #[account(mut, address = pool.lp_mint)]
+    pub lp_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's LP token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_lp_balance: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(encrypted_amount_in: u128, encrypted_min_out: u128, encrypted_hints: Vec<u128>, nonce: u64)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SwapReceipt::LEN,
+        seeds = [b"swap_receipt", pool.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub swap_receipt: Box<Account<'info, SwapReceipt>>,
+    
+    /// Mint of the token being sold
+    #[account(
+        constraint = pool.vault_for(&input_mint.key()).is_some() @ AmmError::InvalidDirection,
+    )]
+    pub input_mint: Box<Account<'info, IncoMint>>,
+    
+    /// Mint of the token being bought
+    #[account(
+        constraint = output_mint.key() != input_mint.key() @ AmmError::InvalidDirection,
+        constraint = pool.vault_for(&output_mint.key()).is_some() @ AmmError::InvalidDirection,
+    )]
+    pub output_mint: Box<Account<'info, IncoMint>>,
+    
+    /// User's input token balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_in.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_in.mint == input_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_in: Box<Account<'info, UserBalance>>,
+    
+    /// CHECK: Pool's input token vault - validated against the pool
+    #[account(
+        mut,
+        constraint = pool.vault_for(&input_mint.key()) == Some(vault_in.key()) @ AmmError::InvalidVault,
+    )]
+    pub vault_in: UncheckedAccount<'info>,
+    
+    /// CHECK: Price feed, required by Oracle pools - validated against the pool in oracle_rate
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Tick grid, required by concentrated pools
+    #[account(
+        mut,
+        seeds = [b"tick_grid", pool.key().as_ref()],
+        bump = tick_grid.bump,
+    )]
+    pub tick_grid: Option<Box<Account<'info, TickGrid>>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    /// Mint sold into the first hop
+    pub input_mint: Box<Account<'info, IncoMint>>,
+    
+    /// Mint bought from the final hop
+    pub output_mint: Box<Account<'info, IncoMint>>,
+    
+    /// User's input token balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_in.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_in.mint == input_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_in: Box<Account<'info, UserBalance>>,
+    
+    /// CHECK: User's output token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_out: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSwapOutput<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"swap_receipt", pool.key().as_ref(), user.key().as_ref(), &swap_receipt.nonce.to_le_bytes()],
+        bump = swap_receipt.bump,
+        constraint = swap_receipt.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub swap_receipt: Box<Account<'info, SwapReceipt>>,
+    
+    /// Mint bought by the swap
+    #[account(address = pool.output_mint(swap_receipt.a_to_b) @ AmmError::InvalidDirection)]
+    pub output_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's output token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_out: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's output token vault - validated against the pool
+    #[account(
+        mut,
+        constraint = pool.vault_for(&output_mint.key()) == Some(vault_out.key()) @ AmmError::InvalidVault,
+    )]
+    pub vault_out: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct SubmitBatchOrder<'info> {
+    #[account(
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + BatchAuction::LEN,
+        seeds = [b"batch", pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump,
+    )]
+    pub batch: Box<Account<'info, BatchAuction>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBatch<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"batch", pool.key().as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump,
+        constraint = !batch.settled @ AmmError::BatchAlreadySettled,
+    )]
+    pub batch: Box<Account<'info, BatchAuction>>,
+    
+    /// Anyone may settle, given price hints that check out
+    #[account(mut)]
+    pub settler: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncInvariant<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    /// Anyone may sync, given hints that check out
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBatchOrder<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"batch", pool.key().as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump,
+    )]
+    pub batch: Box<Account<'info, BatchAuction>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(encrypted_amount_in: u128, encrypted_limit_price: u128, nonce: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LimitOrder::LEN,
+        seeds = [b"limit_order", pool.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+    
+    /// Mint of the token being sold
+    #[account(
+        constraint = pool.vault_for(&input_mint.key()).is_some() @ AmmError::InvalidDirection,
+    )]
+    pub input_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's input token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_in: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's input token vault - validated against the pool
+    #[account(
+        mut,
+        constraint = pool.vault_for(&input_mint.key()) == Some(vault_in.key()) @ AmmError::InvalidVault,
+    )]
+    pub vault_in: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TryFill<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        seeds = [b"limit_order", pool.key().as_ref(), limit_order.owner.as_ref(), &limit_order.nonce.to_le_bytes()],
+        bump = limit_order.bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+    
+    /// CHECK: Price feed, required by Oracle pools - validated against the pool in oracle_rate
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Anyone may try to fill a resting order
+    #[account(mut)]
+    pub filler: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"limit_order", pool.key().as_ref(), user.key().as_ref(), &limit_order.nonce.to_le_bytes()],
+        bump = limit_order.bump,
+        constraint = limit_order.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
 
 #[derive(Accounts)]
-pub struct InitializePool<'info> {
+pub struct InitializeTickGrid<'info> {
+    #[account(
+        constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority,
+        constraint = pool.pool_type == PoolType::Concentrated @ AmmError::UnsupportedPoolType,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
     #[account(
         init,
         payer = authority,
-        space = 8 + PrivatePool::LEN,
-        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        space = 8 + TickGrid::LEN,
+        seeds = [b"tick_grid", pool.key().as_ref()],
         bump,
     )]
-    pub pool: Account<'info, PrivatePool>,
-    
-    /// CHECK: Token A mint
-    pub token_a_mint: AccountInfo<'info>,
-    
-    /// CHECK: Token B mint
-    pub token_b_mint: AccountInfo<'info>,
+    pub tick_grid: Box<Account<'info, TickGrid>>,
     
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -273,40 +3401,305 @@ pub struct InitializePool<'info> {
 }
 
 #[derive(Accounts)]
-pub struct AddLiquidity<'info> {
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenRangePosition<'info> {
     #[account(
         mut,
         constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+        constraint = !pool.paused @ AmmError::PoolPaused,
     )]
     pub pool: Account<'info, PrivatePool>,
     
+    #[account(
+        mut,
+        seeds = [b"tick_grid", pool.key().as_ref()],
+        bump = tick_grid.bump,
+    )]
+    pub tick_grid: Box<Account<'info, TickGrid>>,
+    
     #[account(
         init_if_needed,
         payer = user,
-        space = 8 + UserPosition::LEN,
-        seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
+        space = 8 + RangePosition::LEN,
+        seeds = [
+            b"range_position",
+            pool.key().as_ref(),
+            user.key().as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
         bump,
     )]
-    pub user_position: Account<'info, UserPosition>,
+    pub range_position: Box<Account<'info, RangePosition>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// User's token A balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_a.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_a.mint == token_a_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_a: Box<Account<'info, UserBalance>>,
+    
+    /// User's token B balance PDA (seeds checked by inco_token)
+    #[account(
+        mut,
+        constraint = user_balance_b.user == user.key() @ AmmError::InvalidBalance,
+        constraint = user_balance_b.mint == token_b_mint.key() @ AmmError::InvalidBalance,
+    )]
+    pub user_balance_b: Box<Account<'info, UserBalance>>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
     
     #[account(mut)]
     pub user: Signer<'info>,
     
     pub system_program: Program<'info, System>,
     
+    pub inco_token_program: Program<'info, IncoToken>,
+    
     /// CHECK: Inco Lightning program - validated via address constraint
     #[account(address = INCO_LIGHTNING_ID)]
     pub inco_lightning_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveLiquidity<'info> {
+pub struct CollectRangeFees<'info> {
+    #[account(
+        mut,
+        constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        seeds = [b"tick_grid", pool.key().as_ref()],
+        bump = tick_grid.bump,
+    )]
+    pub tick_grid: Box<Account<'info, TickGrid>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"range_position",
+            pool.key().as_ref(),
+            user.key().as_ref(),
+            &range_position.tick_lower.to_le_bytes(),
+            &range_position.tick_upper.to_le_bytes(),
+        ],
+        bump = range_position.bump,
+        constraint = range_position.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub range_position: Box<Account<'info, RangePosition>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRangePosition<'info> {
     #[account(
         mut,
         constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
     )]
     pub pool: Account<'info, PrivatePool>,
     
+    #[account(
+        mut,
+        seeds = [b"tick_grid", pool.key().as_ref()],
+        bump = tick_grid.bump,
+    )]
+    pub tick_grid: Box<Account<'info, TickGrid>>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            b"range_position",
+            pool.key().as_ref(),
+            user.key().as_ref(),
+            &range_position.tick_lower.to_le_bytes(),
+            &range_position.tick_upper.to_le_bytes(),
+        ],
+        bump = range_position.bump,
+        constraint = range_position.owner == user.key() @ AmmError::InvalidOwner,
+    )]
+    pub range_position: Box<Account<'info, RangePosition>>,
+    
+    #[account(address = pool.token_a_mint)]
+    pub token_a_mint: Box<Account<'info, IncoMint>>,
+    
+    #[account(address = pool.token_b_mint)]
+    pub token_b_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's token A balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_a: UncheckedAccount<'info>,
+    
+    /// CHECK: User's token B balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_balance_b: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token A vault - validated via address constraint
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool's token B vault - validated via address constraint
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewards<'info> {
+    #[account(
+        mut,
+        constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority,
+        constraint = pool.pool_type != PoolType::Concentrated @ AmmError::UnsupportedPoolType,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RewardVault::LEN,
+        seeds = [b"rewards", pool.key().as_ref()],
+        bump,
+    )]
+    pub rewards: Box<Account<'info, RewardVault>>,
+    
+    pub reward_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: Reward vault balance PDA - created and validated by inco_token
+    #[account(mut)]
+    pub reward_vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority)]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Box<Account<'info, RewardVault>>,
+    
+    #[account(address = rewards.reward_mint)]
+    pub reward_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: Funder's reward token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub funder_balance: UncheckedAccount<'info>,
+    
+    /// CHECK: Reward vault - validated via address constraint
+    #[account(mut, address = rewards.vault)]
+    pub reward_vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardEmission<'info> {
+    #[account(constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority)]
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Box<Account<'info, RewardVault>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncRewardSupply<'info> {
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Box<Account<'info, RewardVault>>,
+    
+    /// Anyone may sync, given an inverse hint that checks out
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    pub pool: Account<'info, PrivatePool>,
+    
+    #[account(mut, seeds = [b"rewards", pool.key().as_ref()], bump = rewards.bump)]
+    pub rewards: Box<Account<'info, RewardVault>>,
+    
     #[account(
         mut,
         seeds = [b"position", pool.key().as_ref(), user.key().as_ref()],
@@ -315,40 +3708,225 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub user_position: Account<'info, UserPosition>,
     
+    #[account(address = rewards.reward_mint)]
+    pub reward_mint: Box<Account<'info, IncoMint>>,
+    
+    /// CHECK: User's reward token balance PDA - validated by inco_token
+    #[account(mut)]
+    pub user_reward_balance: UncheckedAccount<'info>,
+    
+    /// CHECK: Reward vault - validated via address constraint
+    #[account(mut, address = rewards.vault)]
+    pub reward_vault: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub user: Signer<'info>,
+    
+    pub inco_token_program: Program<'info, IncoToken>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Swap<'info> {
+pub struct InitializeFactory<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ AmmError::InvalidAuthority)]
+    pub program: Program<'info, crate::program::PrivateAmm>,
+    
+    /// The program's data account; the admin must be its upgrade authority
+    /// (checked before the factory is created)
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AmmError::InvalidAuthority)]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Factory::LEN,
+        seeds = [b"factory"],
+        bump,
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFactory<'info> {
+    #[account(
+        mut,
+        seeds = [b"factory"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ AmmError::InvalidAuthority,
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ AmmError::InvalidAuthority,
+    )]
+    pub factory: Account<'info, Factory>,
+    
     #[account(
         mut,
         constraint = pool.is_initialized @ AmmError::PoolNotInitialized,
     )]
     pub pool: Account<'info, PrivatePool>,
     
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyQuote<'info> {
+    #[account(constraint = pool.is_initialized @ AmmError::PoolNotInitialized)]
+    pub pool: Account<'info, PrivatePool>,
+    
+    /// CHECK: Price feed, required by Oracle pools - validated against the pool in oracle_rate
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Tick grid, required by concentrated pools
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + SwapResult::LEN,
-        seeds = [b"swap_result", pool.key().as_ref(), user.key().as_ref()],
+        seeds = [b"tick_grid", pool.key().as_ref()],
+        bump = tick_grid.bump,
+    )]
+    pub tick_grid: Option<Box<Account<'info, TickGrid>>>,
+    
+    /// CHECK: Inco allowance PDA for (amount_out, caller) - validated by Inco Lightning
+    #[account(mut)]
+    pub amount_out_allowance: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Inco allowance PDA for (fee, caller) - validated by Inco Lightning
+    #[account(mut)]
+    pub fee_allowance: Option<UncheckedAccount<'info>>,
+    
+    /// Receives decryption access; pays for the allowances
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: Inco Lightning program - validated via address constraint
+    #[account(address = INCO_LIGHTNING_ID)]
+    pub inco_lightning_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolInfo<'info> {
+    pub pool: Account<'info, PrivatePool>,
+}
+
+#[derive(Accounts)]
+pub struct PoolAdmin<'info> {
+    #[account(
+        mut,
+        constraint = pool.authority == authority.key() @ AmmError::InvalidAuthority,
+    )]
+    pub pool: Account<'info, PrivatePool>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[cfg(feature = "mock-oracle")]
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct InitializeMockPriceFeed<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceUpdateV2::LEN,
+        seeds = [b"mock_price", authority.key().as_ref(), feed_id.as_ref()],
         bump,
     )]
-    pub swap_result: Account<'info, SwapResult>,
+    pub price_feed: Account<'info, PriceUpdateV2>,
     
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "mock-oracle")]
+#[derive(Accounts)]
+pub struct SetMockPrice<'info> {
+    #[account(
+        mut,
+        constraint = price_feed.write_authority == authority.key() @ AmmError::InvalidAuthority,
+    )]
+    pub price_feed: Account<'info, PriceUpdateV2>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = pool.pending_authority == new_authority.key() @ AmmError::InvalidAuthority,
+    )]
+    pub pool: Account<'info, PrivatePool>,
     
-    /// CHECK: Inco Lightning program - validated via address constraint
-    #[account(address = INCO_LIGHTNING_ID)]
-    pub inco_lightning_program: AccountInfo<'info>,
+    pub new_authority: Signer<'info>,
 }
 
-#[derive(Accounts)]
-pub struct GetPoolInfo<'info> {
-    pub pool: Account<'info, PrivatePool>,
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// Return data of verify_quote
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct QuoteResult {
+    /// Encrypted output after the pool fee
+    pub amount_out: Euint128,
+    /// Encrypted fee, in the input token
+    pub fee: Euint128,
+}
+
+/// Emitted by remove_liquidity with the encrypted amounts owed to the LP
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_burned: Euint128,
+    pub amount_a: Euint128,
+    pub amount_b: Euint128,
+}
+
+/// Emitted by transfer_position with the encrypted amount moved
+#[event]
+pub struct PositionTransferred {
+    pub pool: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: Euint128,
+}
+
+/// Emitted by settle_batch; per-order amounts are only computed on claim
+#[event]
+pub struct BatchSettled {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub orders: u8,
+}
+
+/// Emitted by swap_route; intermediate hop amounts are never recorded
+#[event]
+pub struct RouteSwapped {
+    pub owner: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub hops: u8,
+    pub amount_in: Euint128,
+    pub amount_out: Euint128,
+    pub success: Ebool,
 }
 
 // ============================================================================
@@ -373,4 +3951,1298 @@ pub enum AmmError {
     InvalidDirection,
     #[msg("Pool already initialized")]
     PoolAlreadyInitialized,
+    #[msg("Pool mints must be different")]
+    IdenticalMints,
+    #[msg("Pool mints must be in canonical order (token_a_mint < token_b_mint)")]
+    MintsNotCanonical,
+    #[msg("Vault does not belong to this pool")]
+    InvalidVault,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Protocol fee share too high (max 50% of the swap fee)")]
+    ProtocolFeeTooHigh,
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+    #[msg("Invalid swap route")]
+    InvalidRoute,
+    #[msg("Pool is not a StableSwap pool")]
+    NotStablePool,
+    #[msg("StableSwap invariant has not been synced")]
+    InvariantNotSynced,
+    #[msg("Amplification out of range")]
+    InvalidAmp,
+    #[msg("Amplification ramp too short")]
+    InvalidRamp,
+    #[msg("Amplification ramp already in progress")]
+    RampInProgress,
+    #[msg("Pool is not an Oracle pool")]
+    NotOraclePool,
+    #[msg("Pool oracle not configured")]
+    OracleNotConfigured,
+    #[msg("Invalid oracle account or settings")]
+    InvalidOracle,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Oracle confidence interval too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Oracle price out of range")]
+    InvalidOraclePrice,
+    #[msg("Pool type does not support this operation")]
+    UnsupportedPoolType,
+    #[msg("Batch epoch is not open")]
+    BatchClosed,
+    #[msg("Batch epoch has not ended")]
+    BatchNotEnded,
+    #[msg("Batch is full")]
+    BatchFull,
+    #[msg("Batch already settled")]
+    BatchAlreadySettled,
+    #[msg("Batch not settled yet")]
+    BatchNotSettled,
+    #[msg("Invalid batch order")]
+    InvalidOrder,
+    #[msg("Batch order already claimed")]
+    OrderAlreadyClaimed,
+    #[msg("Invalid tick range")]
+    InvalidTickRange,
+    #[msg("Tick grid is full")]
+    TickGridFull,
+    #[msg("Concentrated pools need their tick grid")]
+    TickGridRequired,
+    #[msg("LP mint must be minted by the pool")]
+    InvalidLpMint,
+    #[msg("LP mint already set")]
+    LpMintAlreadySet,
+    #[msg("Pool rewards account required")]
+    RewardsRequired,
+    #[msg("Reward emission overflow")]
+    EmissionOverflow,
+    #[msg("No rewards to claim")]
+    NoRewards,
+    #[msg("Wrong number of hints for the pool type")]
+    InvalidHints,
+    #[msg("Token balance has never been funded")]
+    InsufficientBalance,
+    #[msg("Token balance does not belong to the user or mint")]
+    InvalidBalance,
+    #[msg("Batch settlement window has closed")]
+    BatchExpired,
+    #[msg("Batch settlement window has not closed")]
+    BatchNotExpired,
+    #[msg("Reward LP supply must be synced first")]
+    RewardSupplyNotSynced,
+    #[msg("Oracle account carries a different price feed")]
+    OracleFeedMismatch,
+    #[msg("Only constant-product pools can be routed through")]
+    UnsupportedRouteHop,
+}
+
+/// Reject transactions that land after the caller's deadline
+/// Slippage is only checked homomorphically, so a stale fill would otherwise
+/// silently execute at zero instead of failing
+fn check_deadline(deadline_slot: u64) -> Result<()> {
+    require!(Clock::get()?.slot <= deadline_slot, AmmError::DeadlineExceeded);
+    Ok(())
+}
+
+/// Pay both legs of a batch order from the vaults to its owner
+fn pay_batch_order(accounts: &ClaimBatchOrder, amount_a: Euint128, amount_b: Euint128) -> Result<()> {
+    let inco = accounts.inco_lightning_program.to_account_info();
+    let token_program = accounts.inco_token_program.to_account_info();
+    let custody = Custody::new(&token_program, &inco);
+    let pool = &accounts.pool;
+    
+    let bump = [pool.bump];
+    let seeds: &[&[u8]] = &[b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref(), &bump];
+    custody.transfer(
+        &accounts.vault_a,
+        &accounts.user_balance_a,
+        &accounts.token_a_mint.to_account_info(),
+        &pool.to_account_info(),
+        &[seeds],
+        amount_a,
+    )?;
+    custody.transfer(
+        &accounts.vault_b,
+        &accounts.user_balance_b,
+        &accounts.token_b_mint.to_account_info(),
+        &pool.to_account_info(),
+        &[seeds],
+        amount_b,
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// FHE HELPERS
+// ============================================================================
+
+/// Thin wrapper over Inco Lightning CPIs for one signer
+/// 
+/// Every method is a single CPI except the `check_*` helpers, which take a
+/// handful. A transaction may hold at most 64 instruction-trace entries and
+/// each CPI is one, so the budget is counted in CPIs rather than compute units
+pub struct Fhe<'a, 'info> {
+    program: &'a AccountInfo<'info>,
+    signer: &'a AccountInfo<'info>,
+    /// Constants already encrypted by this instruction, reused instead of re-issued
+    constants: RefCell<Vec<(u128, Euint128)>>,
+}
+
+impl<'a, 'info> Fhe<'a, 'info> {
+    pub fn new(program: &'a AccountInfo<'info>, signer: &'a AccountInfo<'info>) -> Self {
+        Self { program, signer, constants: RefCell::new(Vec::new()) }
+    }
+
+    fn ctx(&self) -> CpiContext<'static, 'static, 'static, 'info, Operation<'info>> {
+        CpiContext::new(
+            self.program.clone(),
+            Operation { signer: self.signer.clone() },
+        )
+    }
+
+    /// Trivially encrypt a public constant (once per value and instruction)
+    pub fn constant(&self, value: u128) -> Result<Euint128> {
+        if let Some(&(_, handle)) = self.constants.borrow().iter().find(|(known, _)| *known == value) {
+            return Ok(handle);
+        }
+        let handle = as_euint128(self.ctx(), value)?;
+        self.constants.borrow_mut().push((value, handle));
+        Ok(handle)
+    }
+
+    /// Let `allowed` decrypt `handle`; the signer pays for the `allowance` PDA
+    pub fn allow(
+        &self,
+        handle: Euint128,
+        allowance: &AccountInfo<'info>,
+        allowed: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.program.clone(),
+            Allow {
+                allowance_account: allowance.clone(),
+                signer: self.signer.clone(),
+                allowed_address: allowed.clone(),
+                system_program: system_program.clone(),
+            },
+        );
+        allow(cpi_ctx, handle.unwrap(), true, allowed.key())
+    }
+
+    pub fn add(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_add(self.ctx(), lhs, rhs, 0)
+    }
+
+    /// Add to a stored value, treating a never-written (zero) handle as 0
+    pub fn accumulate(&self, stored: Euint128, amount: Euint128) -> Result<Euint128> {
+        if !stored.is_initialized() {
+            return Ok(amount);
+        }
+        self.add(stored, amount)
+    }
+
+    pub fn sub(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_sub(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn mul(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_mul(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn shl(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_shl(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn shr(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_shr(self.ctx(), lhs, rhs, 0)
+    }
+
+    /// Bitwise OR; at most max(lhs, rhs) rounded up to a power of two, less one
+    pub fn or(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        e_or(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn ge(&self, lhs: Euint128, rhs: Euint128) -> Result<Ebool> {
+        e_ge(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn lt(&self, lhs: Euint128, rhs: Euint128) -> Result<Ebool> {
+        e_lt(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn eq(&self, lhs: Euint128, rhs: Euint128) -> Result<Ebool> {
+        e_eq(self.ctx(), lhs, rhs, 0)
+    }
+
+    pub fn select(&self, condition: Ebool, if_true: Euint128, if_false: Euint128) -> Result<Euint128> {
+        e_select(self.ctx(), condition, if_true, if_false, 0)
+    }
+
+    /// Logical AND of two encrypted flags
+    pub fn and(&self, lhs: Ebool, rhs: Ebool) -> Result<Ebool> {
+        let both = e_and(self.ctx(), Euint128::wrap(lhs.unwrap()), Euint128::wrap(rhs.unwrap()), 0)?;
+        Ok(Ebool::wrap(both.unwrap()))
+    }
+
+    /// Logical OR of two encrypted flags
+    pub fn either(&self, lhs: Ebool, rhs: Ebool) -> Result<Ebool> {
+        let any = e_or(self.ctx(), Euint128::wrap(lhs.unwrap()), Euint128::wrap(rhs.unwrap()), 0)?;
+        Ok(Ebool::wrap(any.unwrap()))
+    }
+
+    /// Encrypted minimum of two values
+    pub fn min(&self, lhs: Euint128, rhs: Euint128) -> Result<Euint128> {
+        let lhs_larger = self.ge(lhs, rhs)?;
+        self.select(lhs_larger, rhs, lhs)
+    }
+
+    /// Encrypted flag: `balance` holds at least `amount`
+    /// Checked before pulling funds, so the transfer cannot come up short
+    pub fn covers(&self, balance: &UserBalance, amount: Euint128) -> Result<Ebool> {
+        require!(balance.encrypted_balance.is_initialized(), AmmError::InsufficientBalance);
+        self.ge(balance.encrypted_balance, amount)
+    }
+
+    /// Encrypted flag: `quotient` is floor(numerator / denominator)
+    ///
+    /// Inco Lightning has no division, so callers supply the quotient as an
+    /// encrypted hint, computed off-chain by whoever can decrypt the operands,
+    /// and it is checked here as q * d <= n < (q + 1) * d (7 CPIs). The product
+    /// must not wrap: `max` bounds the quotient so max * d stays below 2^128.
+    /// A zero denominator never checks out
+    pub fn check_quotient(
+        &self,
+        numerator: Euint128,
+        denominator: Euint128,
+        quotient: Euint128,
+        max: u128,
+    ) -> Result<Ebool> {
+        let product = self.mul(quotient, denominator)?;
+        let below = self.ge(numerator, product)?;
+        let remainder = self.sub(numerator, product)?;
+        let tight = self.lt(remainder, denominator)?;
+        let bounded = self.ge(self.constant(max)?, quotient)?;
+        self.and(self.and(below, tight)?, bounded)
+    }
+
+    /// Encrypted flag: `root` is floor(sqrt(value)), for a hinted root
+    /// Checks r * r <= x < (r + 1) * (r + 1) as x - r * r <= 2 * r (9 CPIs);
+    /// roots of 2^64 or more are rejected so r * r cannot wrap
+    pub fn check_root(&self, value: Euint128, root: Euint128) -> Result<Ebool> {
+        let square = self.mul(root, root)?;
+        let below = self.ge(value, square)?;
+        let remainder = self.sub(value, square)?;
+        let tight = self.ge(self.add(root, root)?, remainder)?;
+        let bounded = self.ge(self.constant(u64::MAX as u128)?, root)?;
+        self.and(self.and(below, tight)?, bounded)
+    }
+
+    /// value * share for a share of SHARE_BITS fraction bits, rounded down
+    pub fn apply_share(&self, value: Euint128, share: Euint128) -> Result<Euint128> {
+        let shift = self.constant(SHARE_BITS as u128)?;
+        let scaled = self.mul(value, share)?;
+        self.shr(scaled, shift)
+    }
+
+    /// x * (num / den) for a public fraction, rounded down
+    pub fn scale(&self, value: Euint128, num: u128, den: u128) -> Result<Euint128> {
+        let factor = self.constant((num << FRACTION_BITS) / den)?;
+        let shift = self.constant(FRACTION_BITS as u128)?;
+        let scaled = self.mul(value, factor)?;
+        self.shr(scaled, shift)
+    }
+
+    /// value * rate for a public fixed-point rate, rounded down
+    pub fn apply_rate(&self, value: Euint128, rate: &FixedRate) -> Result<Euint128> {
+        let factor = self.constant(rate.factor)?;
+        let shift = self.constant(rate.shift as u128)?;
+        let scaled = self.mul(value, factor)?;
+        self.shr(scaled, shift)
+    }
+
+}
+
+/// Encrypted swap output, after the pool fee
+pub struct SwapQuote {
+    pub amount_out: Euint128,
+    /// Whether the pool holds enough output and every hint checks out
+    pub fillable: Ebool,
+    /// LP share of the fee, for curves that keep it out of their reserves:
+    /// StableSwap pools bank it until sync_invariant, concentrated pools
+    /// credit it as fee growth
+    pub lp_fee: Option<Euint128>,
+    /// Tick-grid updates, for concentrated pools
+    pub range: Option<RangeStep>,
+}
+
+impl SwapQuote {
+    /// Encrypted flag: the quote can be filled and pays at least `min_out`
+    pub fn accepts(&self, fhe: &Fhe, min_out: Euint128) -> Result<Ebool> {
+        let slippage_ok = fhe.ge(self.amount_out, min_out)?;
+        fhe.and(slippage_ok, self.fillable)
+    }
+}
+
+/// Input left to price after the pool fee: amount * (10000 - fee_bps) / 10000
+/// Fees are charged on the input, rounded in the pool's favor
+pub fn input_after_fee(fhe: &Fhe, amount: Euint128, fee_bps: u16) -> Result<Euint128> {
+    fhe.scale(amount, BPS_DENOMINATOR - fee_bps as u128, BPS_DENOMINATOR)
+}
+
+/// Protocol cut of the fee on `amount_in`, in the input token (None when off)
+pub fn protocol_fee(fhe: &Fhe, pool: &PrivatePool, amount_in: Euint128) -> Result<Option<Euint128>> {
+    if pool.protocol_fee_share_bps == 0 {
+        return Ok(None);
+    }
+    let rate = protocol_fee_rate(pool.fee_bps, pool.protocol_fee_share_bps);
+    Ok(Some(fhe.apply_rate(amount_in, &rate)?))
+}
+
+/// Protocol's cut of a swap input, fee_bps * share_bps / 10^8, as a PROTOCOL_FEE_BITS fraction
+pub fn protocol_fee_rate(fee_bps: u16, share_bps: u16) -> FixedRate {
+    let share = fee_bps as u128 * share_bps as u128;
+    FixedRate {
+        factor: (share << PROTOCOL_FEE_BITS) / (BPS_DENOMINATOR * BPS_DENOMINATOR),
+        shift: PROTOCOL_FEE_BITS,
+    }
+}
+
+/// Check a constant-product swap of `amount_in` for the hinted `amount_out`
+///
+/// 1. effective_in    = amount_in * (10000 - fee_bps) / 10000
+/// 2. new_reserve_out = reserve_out - amount_out,   amount_out <= reserve_out
+/// 3. (reserve_in + effective_in) * new_reserve_out >= k
+///
+/// The largest such output is reserve_out - ceil(k / (reserve_in + effective_in)).
+/// A smaller hint also passes and leaves the difference with LPs, so an exact
+/// check would only protect the trader, whose min_out already does that.
+/// The product in step 3 cannot wrap while reserve_in + amount_in is at most
+/// MAX_POOL_BALANCE, as it is while the input mint's supply fits in a u64
+pub fn constant_product_out(
+    fhe: &Fhe,
+    k: Euint128,
+    reserve_in: Euint128,
+    reserve_out: Euint128,
+    amount_in: Euint128,
+    amount_out: Euint128,
+    fee_bps: u16,
+) -> Result<SwapQuote> {
+    let new_reserve_in = fhe.add(reserve_in, input_after_fee(fhe, amount_in, fee_bps)?)?;
+    let new_reserve_out = fhe.sub(reserve_out, amount_out)?;
+    let on_curve = fhe.ge(fhe.mul(new_reserve_in, new_reserve_out)?, k)?;
+    let in_stock = fhe.ge(reserve_out, amount_out)?;
+    let fillable = fhe.and(on_curve, in_stock)?;
+    Ok(SwapQuote { amount_out, fillable, lp_fee: None, range: None })
+}
+
+/// Encrypted flag: `amount_out` is the largest output constant_product_out accepts
+///
+/// (reserve_in + effective_in) * (reserve_out - amount_out - 1) < k, so one more
+/// unit would break the curve. Together with constant_product_out this pins the
+/// hint to the exact output; a hint that passes that check is below reserve_out,
+/// so the subtraction cannot wrap
+pub fn constant_product_exact(
+    fhe: &Fhe,
+    k: Euint128,
+    reserve_in: Euint128,
+    reserve_out: Euint128,
+    amount_in: Euint128,
+    amount_out: Euint128,
+    fee_bps: u16,
+) -> Result<Ebool> {
+    let new_reserve_in = fhe.add(reserve_in, input_after_fee(fhe, amount_in, fee_bps)?)?;
+    let next_out = fhe.add(amount_out, fhe.constant(1)?)?;
+    fhe.lt(fhe.mul(new_reserve_in, fhe.sub(reserve_out, next_out)?)?, k)
+}
+
+/// Encrypted flag: balances (x, y) hold at least StableSwap invariant `d`
+///
+/// Ann·(x + y) + D = Ann·D + D³ / (4·x·y),  Ann = 4·A
+/// The left side minus the right grows with the balances and shrinks with D,
+/// so it is at least zero exactly when D is at most the balances' invariant.
+/// Inco Lightning has no division, so the cubic term is bounded from above by
+/// two hints, p1 >= D² / (2x) and p2 >= p1·D / (2y), and
+/// Ann·(x + y) + D >= Ann·D + p2 is checked instead (20 CPIs). `d` and the
+/// hints must be at most STABLE_VALUE_BOUND so the products cannot wrap
+pub fn stable_above_curve(
+    fhe: &Fhe,
+    ann: Euint128,
+    x: Euint128,
+    y: Euint128,
+    d: Euint128,
+    hints: [Euint128; 2],
+) -> Result<Ebool> {
+    let [p1, p2] = hints;
+    let first = fhe.ge(fhe.mul(p1, fhe.add(x, x)?)?, fhe.mul(d, d)?)?;
+    let second = fhe.ge(fhe.mul(p2, fhe.add(y, y)?)?, fhe.mul(p1, d)?)?;
+    let bounded = fhe.ge(fhe.constant(STABLE_VALUE_BOUND)?, fhe.or(p1, p2)?)?;
+    let lhs = fhe.add(fhe.mul(ann, fhe.add(x, y)?)?, d)?;
+    let rhs = fhe.add(fhe.mul(ann, d)?, p2)?;
+    let holds = fhe.ge(lhs, rhs)?;
+    fhe.and(fhe.and(first, second)?, fhe.and(bounded, holds)?)
+}
+
+/// Encrypted flag: balances (x, y) hold less than StableSwap invariant `d`
+///
+/// The mirror of stable_above_curve, with the cubic term bounded from below:
+/// q1 <= D² / (2x), q2 <= q1·D / (2y), and Ann·(x + y) + D < Ann·D + q2.
+/// `x`, `y`, `d` and the hints must be at most STABLE_VALUE_BOUND
+pub fn stable_below_curve(
+    fhe: &Fhe,
+    ann: Euint128,
+    x: Euint128,
+    y: Euint128,
+    d: Euint128,
+    hints: [Euint128; 2],
+) -> Result<Ebool> {
+    let [q1, q2] = hints;
+    let first = fhe.ge(fhe.mul(d, d)?, fhe.mul(q1, fhe.add(x, x)?)?)?;
+    let second = fhe.ge(fhe.mul(q1, d)?, fhe.mul(q2, fhe.add(y, y)?)?)?;
+    let bounded = fhe.ge(fhe.constant(STABLE_VALUE_BOUND)?, fhe.or(q1, q2)?)?;
+    let lhs = fhe.add(fhe.mul(ann, fhe.add(x, y)?)?, d)?;
+    let rhs = fhe.add(fhe.mul(ann, d)?, q2)?;
+    let holds = fhe.lt(lhs, rhs)?;
+    fhe.and(fhe.and(first, second)?, fhe.and(bounded, holds)?)
+}
+
+/// Check a StableSwap swap of `amount_in` for the hinted output
+///
+/// 1. effective_in = amount_in * (10000 - fee_bps) / 10000
+/// 2. the post-swap balances (reserve_in + effective_in, reserve_out - amount_out)
+///    still hold the synced invariant D (stable_above_curve, hints[1..])
+///
+/// D only moves at seeds, sync_invariant and deposits or withdrawals, so swaps cannot
+/// drain the pool even though it is never recomputed here. The LP share of
+/// the fee is kept out of the reserves, which would otherwise drift above D,
+/// and banked until the next sync
+pub fn stable_swap_out(
+    fhe: &Fhe,
+    pool: &PrivatePool,
+    reserve_in: Euint128,
+    reserve_out: Euint128,
+    amount_in: Euint128,
+    hints: &[Euint128],
+) -> Result<SwapQuote> {
+    require!(pool.invariant_ann.is_initialized(), AmmError::InvariantNotSynced);
+    let d = pool.k_constant;
+    let amount_out = hints[0];
+    let effective_in = input_after_fee(fhe, amount_in, pool.fee_bps)?;
+    let new_reserve_in = fhe.add(reserve_in, effective_in)?;
+    let new_reserve_out = fhe.sub(reserve_out, amount_out)?;
+    let on_curve = stable_above_curve(fhe, pool.invariant_ann, new_reserve_in, new_reserve_out, d, [hints[1], hints[2]])?;
+    let in_stock = fhe.ge(reserve_out, amount_out)?;
+    // An empty pool syncs to D = 0, which any balances hold
+    let live = fhe.ge(d, fhe.constant(1)?)?;
+    let fillable = fhe.and(fhe.and(on_curve, in_stock)?, live)?;
+    
+    let fee = fhe.sub(amount_in, effective_in)?;
+    let lp_fee = match protocol_fee(fhe, pool, amount_in)? {
+        Some(protocol_fee) => fhe.sub(fee, protocol_fee)?,
+        None => fee,
+    };
+    Ok(SwapQuote { amount_out, fillable, lp_fee: Some(lp_fee), range: None })
+}
+
+/// Oracle-priced output for `amount_in`, after the pool fee
+///
+/// 1. amount_out = amount_in * (10000 - fee_bps) / 10000 * rate   (rounded in the pool's favor)
+/// 2. fillable   = reserve_out >= amount_out
+pub fn oracle_swap_out(
+    fhe: &Fhe,
+    reserve_out: Euint128,
+    amount_in: Euint128,
+    rate: &FixedRate,
+    fee_bps: u16,
+) -> Result<SwapQuote> {
+    let amount_out = fhe.apply_rate(input_after_fee(fhe, amount_in, fee_bps)?, rate)?;
+    let fillable = fhe.ge(reserve_out, amount_out)?;
+    Ok(SwapQuote { amount_out, fillable, lp_fee: None, range: None })
+}
+
+/// Quote a swap of `amount_in` against the pool in the given direction
+/// 
+/// `hints` are the pool type's encrypted hints (PoolType::hint_count):
+/// a constant-product swap takes its output, a StableSwap swap its output and
+/// two bounds on the curve's cubic term (stable_above_curve), a concentrated
+/// swap its output, new sqrt price, its inverse and the LP fee growth
+/// (concentrated_swap_out). They are computed off-chain by
+/// whoever can decrypt the reserves and checked here; a wrong hint makes the
+/// quote unfillable. `oracle` is only read by Oracle pools, `grid` only by
+/// concentrated pools
+pub fn quote_swap(
+    fhe: &Fhe,
+    pool: &PrivatePool,
+    a_to_b: bool,
+    amount_in: Euint128,
+    hints: &[Euint128],
+    oracle: Option<&AccountInfo>,
+    grid: Option<&TickGrid>,
+) -> Result<SwapQuote> {
+    require!(hints.len() == pool.pool_type.hint_count(), AmmError::InvalidHints);
+    let (reserve_in, reserve_out) = if a_to_b {
+        (pool.reserve_a, pool.reserve_b)
+    } else {
+        (pool.reserve_b, pool.reserve_a)
+    };
+    match pool.pool_type {
+        PoolType::ConstantProduct => constant_product_out(
+            fhe,
+            pool.k_constant,
+            reserve_in,
+            reserve_out,
+            amount_in,
+            hints[0],
+            pool.fee_bps,
+        ),
+        PoolType::StableSwap => stable_swap_out(fhe, pool, reserve_in, reserve_out, amount_in, hints),
+        PoolType::Oracle => {
+            let rate = oracle_rate(pool, oracle, a_to_b)?;
+            oracle_swap_out(fhe, reserve_out, amount_in, &rate, pool.fee_bps)
+        }
+        PoolType::Concentrated => {
+            let grid = grid.ok_or(error!(AmmError::TickGridRequired))?;
+            concentrated_swap_out(fhe, pool, grid, a_to_b, amount_in, hints)
+        }
+    }
+}
+
+/// Settle a quoted swap against the pool's encrypted reserves
+///
+/// `amount_in` must already be zero when `ok` is false; the output is selected
+/// on `ok` here, and the output owed to the trader is returned.
+/// The fee is part of the input: the protocol's cut leaves the reserves but
+/// stays in the vault until collected; the rest remains with LPs, so k grows
+pub fn apply_swap(
+    fhe: &Fhe,
+    pool: &mut PrivatePool,
+    a_to_b: bool,
+    amount_in: Euint128,
+    quote: &SwapQuote,
+    ok: Ebool,
+    zero: Euint128,
+) -> Result<Euint128> {
+    let amount_out = fhe.select(ok, quote.amount_out, zero)?;
+    let (reserve_in, reserve_out) = if a_to_b {
+        (pool.reserve_a, pool.reserve_b)
+    } else {
+        (pool.reserve_b, pool.reserve_a)
+    };
+    let mut new_reserve_in = fhe.add(reserve_in, amount_in)?;
+    let new_reserve_out = fhe.sub(reserve_out, amount_out)?;
+    
+    // StableSwap and concentrated reserves must stay on their curve, so LP
+    // fees leave them: banked until sync_invariant, or credited through fee
+    // growth by apply_range_step
+    if let Some(lp_fee) = quote.lp_fee {
+        let lp_fee = fhe.select(ok, lp_fee, zero)?;
+        new_reserve_in = fhe.sub(new_reserve_in, lp_fee)?;
+        if pool.pool_type == PoolType::StableSwap {
+            if a_to_b {
+                pool.lp_fees_a = fhe.accumulate(pool.lp_fees_a, lp_fee)?;
+            } else {
+                pool.lp_fees_b = fhe.accumulate(pool.lp_fees_b, lp_fee)?;
+            }
+        }
+    }
+    
+    // Zero with amount_in when the swap failed
+    if let Some(protocol_fee) = protocol_fee(fhe, pool, amount_in)? {
+        new_reserve_in = fhe.sub(new_reserve_in, protocol_fee)?;
+        if a_to_b {
+            pool.protocol_fees_a = fhe.accumulate(pool.protocol_fees_a, protocol_fee)?;
+        } else {
+            pool.protocol_fees_b = fhe.accumulate(pool.protocol_fees_b, protocol_fee)?;
+        }
+    }
+    
+    if a_to_b {
+        pool.reserve_a = new_reserve_in;
+        pool.reserve_b = new_reserve_out;
+    } else {
+        pool.reserve_b = new_reserve_in;
+        pool.reserve_a = new_reserve_out;
+    }
+    if pool.pool_type != PoolType::StableSwap {
+        pool.k_constant = fhe.mul(pool.reserve_a, pool.reserve_b)?;
+    }
+    Ok(amount_out)
+}
+
+/// One validated leg of a swap_route
+struct RouteHop<'info> {
+    pool: Account<'info, PrivatePool>,
+    a_to_b: bool,
+    mint_out: AccountInfo<'info>,
+    vault_in: AccountInfo<'info>,
+    vault_out: AccountInfo<'info>,
+}
+
+// ============================================================================
+// CUSTODY HELPERS
+// ============================================================================
+
+/// Moves encrypted balances through inco_token on behalf of users and pools
+/// No owner allowances are granted: pool PDAs cannot fund allowance accounts
+/// and the swap paths have no trace entries to spare for them
+pub struct Custody<'a, 'info> {
+    token_program: &'a AccountInfo<'info>,
+    inco_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> Custody<'a, 'info> {
+    pub fn new(token_program: &'a AccountInfo<'info>, inco_program: &'a AccountInfo<'info>) -> Self {
+        Self { token_program, inco_program }
+    }
+
+    /// Transfer `amount` from `source` (owned by `owner`) to `dest`
+    /// Returns the handle actually moved, which is zero if `source` was short
+    pub fn transfer(
+        &self,
+        source: &AccountInfo<'info>,
+        dest: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+        owner: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: Euint128,
+    ) -> Result<Euint128> {
+        let cpi_accounts = BalanceTransfer {
+            source_balance: source.clone(),
+            dest_balance: dest.clone(),
+            mint: mint.clone(),
+            owner: owner.clone(),
+            inco_lightning_program: self.inco_program.clone(),
+            source_allowance: None,
+            dest_user: None,
+            dest_allowance: None,
+            system_program: None,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.clone(), cpi_accounts, signer_seeds);
+        Ok(inco_token::cpi::confidential_transfer(cpi_ctx, amount.unwrap())?.get())
+    }
+
+    /// Mint `amount` into `dest`, signed by the mint's `authority`
+    pub fn mint(
+        &self,
+        dest: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: Euint128,
+    ) -> Result<()> {
+        let cpi_accounts = MintToUser {
+            user_balance: dest.clone(),
+            mint: mint.clone(),
+            mint_authority: authority.clone(),
+            inco_lightning_program: self.inco_program.clone(),
+            user: None,
+            user_allowance: None,
+            system_program: None,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.clone(), cpi_accounts, signer_seeds);
+        inco_token::cpi::mint_to_user(cpi_ctx, amount.unwrap())
+    }
+
+    /// Burn `amount` from `source` (owned by `owner`)
+    /// Returns the handle actually burned, which is zero if `source` was short
+    pub fn burn(
+        &self,
+        source: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+        owner: &AccountInfo<'info>,
+        amount: Euint128,
+    ) -> Result<Euint128> {
+        let cpi_accounts = BurnFromUser {
+            user_balance: source.clone(),
+            mint: mint.clone(),
+            user: owner.clone(),
+            inco_lightning_program: self.inco_program.clone(),
+            user_allowance: None,
+            system_program: None,
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.clone(), cpi_accounts);
+        Ok(inco_token::cpi::burn_from_user(cpi_ctx, amount.unwrap())?.get())
+    }
+}
+
+// ============================================================================
+// ORACLE HELPERS
+// ============================================================================
+
+/// Public exchange rate factor / 2^shift, in output units per input unit
+pub struct FixedRate {
+    pub factor: u128,
+    pub shift: u32,
+}
+
+impl FixedRate {
+    /// num / den rounded down to RATE_BITS significant bits
+    ///
+    /// Long division emits fraction bits until the factor is RATE_BITS wide,
+    /// so tiny rates keep their precision. Rates of 2^RATE_BITS or more are rejected
+    pub fn from_ratio(num: u128, den: u128) -> Result<Self> {
+        require!(den > 0 && den <= 1 << 127, AmmError::InvalidOraclePrice);
+        let mut factor = num / den;
+        require!(factor < 1 << RATE_BITS, AmmError::InvalidOraclePrice);
+        let mut remainder = num % den;
+        let mut shift = 0;
+        while shift < RATE_BITS && factor < 1 << (RATE_BITS - 1) {
+            remainder <<= 1;
+            factor <<= 1;
+            if remainder >= den {
+                remainder -= den;
+                factor |= 1;
+            }
+            shift += 1;
+        }
+        Ok(Self { factor, shift })
+    }
+}
+
+/// Exchange rate for a swap on an Oracle pool, from its configured price feed
+///
+/// The feed must be fully verified, no older than `max_price_age` and its
+/// confidence no wider than `max_confidence_bps`. The price is converted to
+/// raw token units using the decimals of both mints. Feeds owned by this
+/// program (mock feeds) are only trusted in `mock-oracle` builds
+pub fn oracle_rate(pool: &PrivatePool, oracle: Option<&AccountInfo>, a_to_b: bool) -> Result<FixedRate> {
+    require!(pool.oracle != Pubkey::default(), AmmError::OracleNotConfigured);
+    let oracle = oracle.ok_or(error!(AmmError::OracleNotConfigured))?;
+    require!(oracle.key() == pool.oracle, AmmError::InvalidOracle);
+    let mock_feed = cfg!(feature = "mock-oracle") && *oracle.owner == crate::ID;
+    require!(*oracle.owner == PYTH_RECEIVER_ID || mock_feed, AmmError::InvalidOracle);
+    
+    let data = oracle.try_borrow_data()?;
+    let update = PriceUpdateV2::try_deserialize(&mut &data[..])?;
+    require!(update.verification_level == VerificationLevel::Full, AmmError::InvalidOracle);
+    
+    let message = update.price_message;
+    require!(message.feed_id == pool.oracle_feed_id, AmmError::OracleFeedMismatch);
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now.saturating_sub(message.publish_time) <= pool.max_price_age as i64,
+        AmmError::StaleOraclePrice
+    );
+    require!(message.price > 0, AmmError::InvalidOraclePrice);
+    let price = message.price as u128;
+    require!(
+        message.conf as u128 * BPS_DENOMINATOR <= price * pool.max_confidence_bps as u128,
+        AmmError::OracleConfidenceTooWide
+    );
+    
+    // Feed prices `base` in units of `quote`: raw quote = raw base * price * 10^(expo + dec_q - dec_b)
+    let (base_decimals, quote_decimals) = if pool.oracle_inverted {
+        (pool.decimals_b, pool.decimals_a)
+    } else {
+        (pool.decimals_a, pool.decimals_b)
+    };
+    let exponent = message.exponent + quote_decimals as i32 - base_decimals as i32;
+    let power = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(error!(AmmError::InvalidOraclePrice))?;
+    let (num, den) = if exponent >= 0 {
+        (price.checked_mul(power).ok_or(error!(AmmError::InvalidOraclePrice))?, 1)
+    } else {
+        (price, power)
+    };
+    
+    // Selling the base token multiplies by the price, buying it divides
+    let sells_base = a_to_b != pool.oracle_inverted;
+    if sells_base {
+        FixedRate::from_ratio(num, den)
+    } else {
+        FixedRate::from_ratio(den, num)
+    }
+}
+
+// ============================================================================
+// CONCENTRATED LIQUIDITY HELPERS
+// ============================================================================
+
+/// sqrt(1.0001)^(2^i) in SQRT_PRICE_BITS fixed point, for |tick| < 2^19
+const SQRT_TICK_POWERS: [u128; 19] = [
+    4_295_182_039,
+    4_295_396_793,
+    4_295_826_332,
+    4_296_685_541,
+    4_298_404_473,
+    4_301_844_400,
+    4_308_732_516,
+    4_322_541_852,
+    4_350_293_443,
+    4_406_332_281,
+    4_520_584_868,
+    4_758_054_286,
+    5_271_071_706,
+    6_469_012_455,
+    9_743_525_213,
+    22_104_076_012,
+    113_758_765_237,
+    3_013_074_553_646,
+    2_113_780_534_320_709,
+];
+
+/// sqrt(1.0001^tick) in SQRT_PRICE_BITS fixed point, by binary exponentiation
+/// Every partial product is at most the result, so nothing overflows within MAX_TICK
+pub fn sqrt_price_at_tick(tick: i32) -> u128 {
+    let magnitude = tick.unsigned_abs();
+    let mut ratio = 1u128 << SQRT_PRICE_BITS;
+    for (bit, power) in SQRT_TICK_POWERS.iter().enumerate() {
+        if magnitude & (1 << bit) != 0 {
+            ratio = (ratio * power) >> SQRT_PRICE_BITS;
+        }
+    }
+    if tick < 0 {
+        inverse_sqrt_price(ratio)
+    } else {
+        ratio
+    }
+}
+
+/// 1 / sqrt price, both in SQRT_PRICE_BITS fixed point, rounded down
+pub fn inverse_sqrt_price(sqrt_price: u128) -> u128 {
+    (1u128 << (2 * SQRT_PRICE_BITS)) / sqrt_price
+}
+
+/// Tick-grid side effects of a concentrated swap, applied by apply_range_step
+pub struct RangeStep {
+    /// Encrypted sqrt price after the swap
+    pub sqrt_price: Euint128,
+    /// Encrypted 1 / sqrt price after the swap
+    pub inverse_sqrt_price: Euint128,
+    /// Encrypted index of the bucket the swap ends in
+    pub bucket: Euint128,
+    /// Encrypted LP fee per unit of the ending bucket's liquidity
+    pub fee_growth: Euint128,
+}
+
+/// Real reserves of a grid with the price sitting on each initialized tick
+///
+/// x[i] = sum over k >= i of L_k * (1/sqrt(P_k) - 1/sqrt(P_k+1))
+/// y[i] = sum over k < i  of L_k * (sqrt(P_k+1) - sqrt(P_k))
+/// The coefficients are public, so no encrypted division is needed
+pub struct CurvePoints {
+    pub x: Vec<Euint128>,
+    pub y: Vec<Euint128>,
+    pub sqrt_prices: Vec<u128>,
+    pub inverse_sqrt_prices: Vec<u128>,
+}
+
+pub fn curve_points(fhe: &Fhe, grid: &TickGrid, zero: Euint128) -> Result<CurvePoints> {
+    let sqrt_prices: Vec<u128> = grid.ticks.iter().map(|&tick| sqrt_price_at_tick(tick)).collect();
+    let inverse_sqrt_prices: Vec<u128> = sqrt_prices.iter().map(|&price| inverse_sqrt_price(price)).collect();
+    let ticks = sqrt_prices.len();
+    let price_rate = |factor: u128| FixedRate { factor, shift: SQRT_PRICE_BITS };
+    
+    let mut x = vec![zero; ticks];
+    let mut y = vec![zero; ticks];
+    for k in (0..ticks - 1).rev() {
+        let width = inverse_sqrt_prices[k].saturating_sub(inverse_sqrt_prices[k + 1]);
+        x[k] = fhe.add(x[k + 1], fhe.apply_rate(grid.liquidity[k], &price_rate(width))?)?;
+    }
+    for k in 0..ticks - 1 {
+        let width = sqrt_prices[k + 1].saturating_sub(sqrt_prices[k]);
+        y[k + 1] = fhe.add(y[k], fhe.apply_rate(grid.liquidity[k], &price_rate(width))?)?;
+    }
+    Ok(CurvePoints { x, y, sqrt_prices, inverse_sqrt_prices })
+}
+
+/// Check a concentrated-liquidity swap of `amount_in` for the hinted output
+///
+/// 1. new_reserve_in = reserve_in + amount_in less the pool fee; fillable if within the grid
+/// 2. bucket         = where new_reserve_in falls between the tick curve points
+///    (picked with e_select, so the crossed ticks stay hidden)
+/// 3. inside the bucket virtual reserves must satisfy v_in * v_out >= L^2,
+///    with new_reserve_out = reserve_out - amount_out not past the bucket's edge
+///
+/// `hints` are [amount_out, sqrt_price, inverse_sqrt_price, fee_growth]. As
+/// for constant-product swaps a smaller output also passes; the other three
+/// must be the exact floors of sqrt(P) = L / x_v = y_v / L, its inverse and
+/// lp_fee / L (Fhe::check_quotient), since range positions are valued and
+/// paid from them. The whole LP fee, in the input token, is credited to the
+/// bucket the swap ends in
+pub fn concentrated_swap_out(
+    fhe: &Fhe,
+    pool: &PrivatePool,
+    grid: &TickGrid,
+    a_to_b: bool,
+    amount_in: Euint128,
+    hints: &[Euint128],
+) -> Result<SwapQuote> {
+    require!(grid.ticks.len() >= 2, AmmError::InsufficientLiquidity);
+    let zero = fhe.constant(0)?;
+    let price_shift = fhe.constant(SQRT_PRICE_BITS as u128)?;
+    let points = curve_points(fhe, grid, zero)?;
+    let buckets = grid.liquidity.len();
+    
+    let (reserve_in, reserve_out, capacity) = if a_to_b {
+        (pool.reserve_a, pool.reserve_b, points.x[0])
+    } else {
+        (pool.reserve_b, pool.reserve_a, points.y[buckets])
+    };
+    let effective_in = input_after_fee(fhe, amount_in, pool.fee_bps)?;
+    let new_in = fhe.add(reserve_in, effective_in)?;
+    let within_grid = fhe.ge(capacity, new_in)?;
+    
+    // Selling A lowers the price: the bucket is the lowest with x[k + 1] <= new x.
+    // Selling B raises it: the highest with y[k] <= new y. Later candidates
+    // override earlier ones, so scan toward that end
+    let scan: Vec<usize> = if a_to_b {
+        (0..buckets).rev().collect()
+    } else {
+        (0..buckets).collect()
+    };
+    let mut selected: Option<[Euint128; 6]> = None;
+    for k in scan {
+        let (in_edge, in_offset, out_edge, out_offset) = if a_to_b {
+            (points.x[k + 1], points.inverse_sqrt_prices[k + 1], points.y[k], points.sqrt_prices[k])
+        } else {
+            (points.y[k], points.sqrt_prices[k], points.x[k + 1], points.inverse_sqrt_prices[k + 1])
+        };
+        let candidate = [
+            grid.liquidity[k],
+            in_edge,
+            fhe.constant(in_offset)?,
+            out_edge,
+            fhe.constant(out_offset)?,
+            fhe.constant(k as u128)?,
+        ];
+        selected = Some(match selected {
+            None => candidate,
+            Some(mut current) => {
+                let lands = fhe.ge(new_in, in_edge)?;
+                for (slot, value) in current.iter_mut().zip(candidate) {
+                    *slot = fhe.select(lands, value, *slot)?;
+                }
+                current
+            }
+        });
+    }
+    let [liquidity, in_edge, in_offset, out_edge, out_offset, bucket] =
+        selected.ok_or(error!(AmmError::InsufficientLiquidity))?;
+    
+    // Virtual reserves inside the bucket: real reserve past the edge plus L / sqrt(P) or L * sqrt(P)
+    let virtual_in = fhe.add(
+        fhe.sub(new_in, in_edge)?,
+        fhe.shr(fhe.mul(liquidity, in_offset)?, price_shift)?,
+    )?;
+    let [amount_out, sqrt_price, inverse_sqrt_price, fee_growth] = [hints[0], hints[1], hints[2], hints[3]];
+    let new_reserve_out = fhe.sub(reserve_out, amount_out)?;
+    let virtual_out = fhe.add(
+        fhe.sub(new_reserve_out, out_edge)?,
+        fhe.shr(fhe.mul(liquidity, out_offset)?, price_shift)?,
+    )?;
+    let on_curve = fhe.ge(fhe.mul(virtual_in, virtual_out)?, fhe.mul(liquidity, liquidity)?)?;
+    let in_bucket = fhe.ge(new_reserve_out, out_edge)?;
+    let in_stock = fhe.ge(reserve_out, amount_out)?;
+    // Keeps L^2 and every quotient check's product below 2^128
+    let max = u64::MAX as u128;
+    let bounded = fhe.ge(fhe.constant(max)?, fhe.or(liquidity, virtual_in)?)?;
+    
+    // sqrt(P) = L / x_v = y_v / L, so one of the price and its inverse is
+    // L / v_in and the other v_in / L
+    let (over_virtual, over_liquidity) = if a_to_b {
+        (sqrt_price, inverse_sqrt_price)
+    } else {
+        (inverse_sqrt_price, sqrt_price)
+    };
+    let priced = fhe.and(
+        fhe.check_quotient(fhe.shl(liquidity, price_shift)?, virtual_in, over_virtual, max)?,
+        fhe.check_quotient(fhe.shl(virtual_in, price_shift)?, liquidity, over_liquidity, max)?,
+    )?;
+    
+    let fee = fhe.sub(amount_in, effective_in)?;
+    let lp_fee = match protocol_fee(fhe, pool, amount_in)? {
+        Some(protocol_fee) => fhe.sub(fee, protocol_fee)?,
+        None => fee,
+    };
+    let growth_shift = fhe.constant(FEE_GROWTH_BITS as u128)?;
+    let credited = fhe.check_quotient(fhe.shl(lp_fee, growth_shift)?, liquidity, fee_growth, max)?;
+    
+    let mut fillable = fhe.and(within_grid, on_curve)?;
+    for flag in [in_bucket, in_stock, bounded, priced, credited] {
+        fillable = fhe.and(fillable, flag)?;
+    }
+    Ok(SwapQuote {
+        amount_out,
+        fillable,
+        lp_fee: Some(lp_fee),
+        range: Some(RangeStep { sqrt_price, inverse_sqrt_price, bucket, fee_growth }),
+    })
+}
+
+/// Move a grid's price and credit LP fee growth after a concentrated swap
+/// A failed swap (`ok` false) leaves the grid unchanged
+pub fn apply_range_step(
+    fhe: &Fhe,
+    grid: &mut TickGrid,
+    a_to_b: bool,
+    step: &RangeStep,
+    ok: Ebool,
+    zero: Euint128,
+) -> Result<()> {
+    grid.sqrt_price = fhe.select(ok, step.sqrt_price, grid.sqrt_price)?;
+    grid.inverse_sqrt_price = fhe.select(ok, step.inverse_sqrt_price, grid.inverse_sqrt_price)?;
+    let growth = fhe.select(ok, step.fee_growth, zero)?;
+    
+    // Fees are paid in the input token
+    let fee_growth = if a_to_b {
+        &mut grid.fee_growth_a
+    } else {
+        &mut grid.fee_growth_b
+    };
+    for (k, bucket_growth) in fee_growth.iter_mut().enumerate() {
+        let active = fhe.eq(step.bucket, fhe.constant(k as u128)?)?;
+        *bucket_growth = fhe.add(*bucket_growth, fhe.select(active, growth, zero)?)?;
+    }
+    Ok(())
+}
+
+/// Token amounts backing `liquidity` between two ticks at `sqrt_price`
+///
+/// With the price clamped into the range:
+/// amount_a = L * (1/sqrt(P) - 1/sqrt(P_upper)),  amount_b = L * (sqrt(P) - sqrt(P_lower))
+/// Rounded up for deposits and down for withdrawals
+pub fn range_amounts(
+    fhe: &Fhe,
+    sqrt_price: Euint128,
+    inverse_price: Euint128,
+    liquidity: Euint128,
+    tick_lower: i32,
+    tick_upper: i32,
+    round_up: bool,
+) -> Result<(Euint128, Euint128)> {
+    let lower = sqrt_price_at_tick(tick_lower);
+    let upper = sqrt_price_at_tick(tick_upper);
+    let width = clamped_width(fhe, sqrt_price, lower, upper)?;
+    
+    // The stored inverse is rounded down, so deposits pay for one more unit.
+    // It is clamped on its own, so it never falls below the upper bound's
+    // inverse even where the two roundings disagree
+    let inverse_price = if round_up {
+        fhe.add(inverse_price, fhe.constant(1)?)?
+    } else {
+        inverse_price
+    };
+    let inverse_width = clamped_width(fhe, inverse_price, inverse_sqrt_price(upper), inverse_sqrt_price(lower))?;
+    let rounding = if round_up { (1u128 << SQRT_PRICE_BITS) - 1 } else { 0 };
+    
+    let shift = fhe.constant(SQRT_PRICE_BITS as u128)?;
+    let rounding = fhe.constant(rounding)?;
+    let amount_a = fhe.shr(fhe.add(fhe.mul(liquidity, inverse_width)?, rounding)?, shift)?;
+    let amount_b = fhe.shr(fhe.add(fhe.mul(liquidity, width)?, rounding)?, shift)?;
+    Ok((amount_a, amount_b))
+}
+
+/// Encrypted `value` clamped into [low, high], less low
+pub fn clamped_width(fhe: &Fhe, value: Euint128, low: u128, high: u128) -> Result<Euint128> {
+    let low = fhe.constant(low)?;
+    let high = fhe.constant(high)?;
+    let value = fhe.select(fhe.ge(value, low)?, value, low)?;
+    let value = fhe.select(fhe.ge(high, value)?, value, high)?;
+    fhe.sub(value, low)
+}
+
+/// Accrue a range position's fees up to the grid's current fee growth
+/// owed += L * (growth inside the range now - growth inside at the last settlement)
+pub fn settle_range_fees(
+    fhe: &Fhe,
+    grid: &TickGrid,
+    position: &mut RangePosition,
+    zero: Euint128,
+) -> Result<()> {
+    let (lower, upper) = grid.bucket_range(position.tick_lower, position.tick_upper)?;
+    let mut inside_a = zero;
+    let mut inside_b = zero;
+    for k in lower..upper {
+        inside_a = fhe.add(inside_a, grid.fee_growth_a[k])?;
+        inside_b = fhe.add(inside_b, grid.fee_growth_b[k])?;
+    }
+    
+    if !position.fee_growth_inside_a.is_initialized() {
+        // New position: nothing earned yet
+        position.fees_owed_a = zero;
+        position.fees_owed_b = zero;
+    } else {
+        let shift = fhe.constant(FEE_GROWTH_BITS as u128)?;
+        let earned_a = fhe.mul(position.liquidity, fhe.sub(inside_a, position.fee_growth_inside_a)?)?;
+        let earned_b = fhe.mul(position.liquidity, fhe.sub(inside_b, position.fee_growth_inside_b)?)?;
+        position.fees_owed_a = fhe.add(position.fees_owed_a, fhe.shr(earned_a, shift)?)?;
+        position.fees_owed_b = fhe.add(position.fees_owed_b, fhe.shr(earned_b, shift)?)?;
+    }
+    position.fee_growth_inside_a = inside_a;
+    position.fee_growth_inside_b = inside_b;
+    Ok(())
+}
+
+// ============================================================================
+// REWARD HELPERS
+// ============================================================================
+
+/// Accrue the pool's emissions and move what `position` has earned into rewards_owed
+/// Call before the position's LP balance changes; errors if the pool has
+/// rewards but they were not passed
+/// 
+/// When the staked LP supply is about to change, emissions held for a stale
+/// inverse are forfeited: they were earned at a supply that is about to be
+/// gone, and paying them at the next synced supply would let liquidity added
+/// just before a sync collect them
+pub fn settle_rewards(
+    fhe: &Fhe,
+    pool: &PrivatePool,
+    rewards: Option<&mut RewardVault>,
+    position: &mut UserPosition,
+    supply_changes: bool,
+) -> Result<()> {
+    if !pool.rewards_enabled {
+        return Ok(());
+    }
+    let rewards = rewards.ok_or(error!(AmmError::RewardsRequired))?;
+    let now = Clock::get()?.unix_timestamp;
+    rewards.accrue(fhe, pool, now)?;
+    if supply_changes {
+        rewards.last_update_time = rewards.last_update_time.max(now);
+    }
+    if !position.lp_amount.is_initialized() {
+        return Ok(());
+    }
+    
+    // A position older than the rewards has no debt yet: it earned everything accrued
+    let accrued = rewards.accrued(fhe, position.lp_amount)?;
+    let earned = if position.reward_debt.is_initialized() {
+        fhe.sub(accrued, position.reward_debt)?
+    } else {
+        accrued
+    };
+    position.rewards_owed = fhe.accumulate(position.rewards_owed, earned)?;
+    position.reward_debt = accrued;
+    Ok(())
+}
+
+/// Restart a position's reward debt at its new LP balance
+/// Call after the change, with the rewards settle_rewards accrued
+pub fn checkpoint_rewards(fhe: &Fhe, rewards: Option<&RewardVault>, position: &mut UserPosition) -> Result<()> {
+    if let Some(rewards) = rewards {
+        if position.lp_amount.is_initialized() {
+            position.reward_debt = rewards.accrued(fhe, position.lp_amount)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(amount: u128, rate: &FixedRate) -> u128 {
+        (amount * rate.factor) >> rate.shift
+    }
+
+    fn isqrt(value: u128) -> u128 {
+        let mut root = (value as f64).sqrt() as u128;
+        while root * root > value {
+            root -= 1;
+        }
+        while (root + 1) * (root + 1) <= value {
+            root += 1;
+        }
+        root
+    }
+
+    /// Deterministic pseudo-random amounts in 1..=max
+    struct Amounts(u64);
+
+    impl Amounts {
+        fn next(&mut self, max: u128) -> u128 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 16) as u128 % max + 1
+        }
+    }
+
+    /// Constant-product swap at the largest output constant_product_out accepts
+    fn swap(reserve_in: &mut u128, reserve_out: &mut u128, amount_in: u128) {
+        let k = *reserve_in * *reserve_out;
+        let effective = amount_in * (BPS_DENOMINATOR - 30) / BPS_DENOMINATOR;
+        let out = *reserve_out - k.div_ceil(*reserve_in + effective);
+        *reserve_in += amount_in;
+        *reserve_out -= out;
+    }
+
+    #[test]
+    fn protocol_fee_keeps_small_shares() {
+        // fee_bps * share_bps = 30 * 5 = 150, which a FRACTION_BITS fraction rounds to zero
+        assert_eq!((150u128 << FRACTION_BITS) / (BPS_DENOMINATOR * BPS_DENOMINATOR), 0);
+        let rate = protocol_fee_rate(30, 5);
+        assert!(rate.factor > 0);
+        // 1.5e-6 of 10^9 is 1500, less the flooring of the factor
+        assert_eq!(apply(1_000_000_000, &rate), 1499);
+        // The smallest nonzero cut still accrues
+        assert!(apply(1_000_000_000, &protocol_fee_rate(1, 1)) > 0);
+    }
+
+    #[test]
+    fn constant_product_check_fits_at_the_pool_limit() {
+        // The input comes out of the same mint supply as the reserve it joins
+        let new_reserve_in = MAX_POOL_BALANCE;
+        let new_reserve_out = MAX_POOL_BALANCE;
+        assert!(new_reserve_in.checked_mul(new_reserve_out).is_some());
+        // k = reserve_a * reserve_b is formed the same way
+        assert!(MAX_POOL_BALANCE.checked_mul(MAX_POOL_BALANCE).is_some());
+        // One unit further and the curve check could wrap
+        assert!((MAX_POOL_BALANCE + 1).checked_mul(MAX_POOL_BALANCE + 1).is_none());
+    }
+
+    #[test]
+    fn lp_supply_stays_below_the_reserves_root() {
+        let (mut reserve_a, mut reserve_b) = (3_000_000_019u128, 5_000_000_029u128);
+        let mut supply = isqrt(reserve_a * reserve_b);
+        let mut amounts = Amounts(7);
+        for step in 0..200 {
+            if step % 2 == 0 {
+                // lp = min(a * S / Ra, b * S / Rb), both amounts kept
+                let (a, b) = (amounts.next(reserve_a / 16), amounts.next(reserve_b / 16));
+                let lp = (a * supply / reserve_a).min(b * supply / reserve_b);
+                assert!(a.checked_mul(supply).is_some() && a.checked_mul(reserve_b).is_some());
+                reserve_a += a;
+                reserve_b += b;
+                supply += lp;
+            } else if step % 4 == 1 {
+                let amount_in = amounts.next(reserve_a / 16);
+                swap(&mut reserve_a, &mut reserve_b, amount_in);
+            } else {
+                let amount_in = amounts.next(reserve_b / 16);
+                swap(&mut reserve_b, &mut reserve_a, amount_in);
+            }
+            // So S is within MAX_POOL_BALANCE whenever both reserves are
+            assert!(supply * supply <= reserve_a * reserve_b);
+        }
+    }
+
+    #[test]
+    fn withdrawal_share_fits_and_never_overpays() {
+        let whole = 1u128 << SHARE_BITS;
+        // lp_amount << SHARE_BITS, share * lp_supply and reserve * share at the limit
+        assert!(MAX_POOL_BALANCE.checked_mul(whole).is_some());
+
+        let mut amounts = Amounts(11);
+        let mut cases = vec![(MAX_POOL_BALANCE, MAX_POOL_BALANCE, MAX_POOL_BALANCE), (MAX_POOL_BALANCE, 1000, 1)];
+        for _ in 0..200 {
+            let supply = amounts.next(MAX_POOL_BALANCE);
+            cases.push((amounts.next(MAX_POOL_BALANCE), supply, amounts.next(supply)));
+        }
+        for (reserve, supply, lp) in cases {
+            let share = (lp << SHARE_BITS) / supply;
+            let out = (reserve * share) >> SHARE_BITS;
+            // out <= reserve * lp / supply; both sides fit below the limit
+            assert!(out * supply <= reserve * lp);
+        }
+    }
+
+    #[test]
+    fn protocol_fee_cannot_wrap() {
+        let rate = protocol_fee_rate(MAX_FEE_BPS, MAX_PROTOCOL_FEE_SHARE_BPS);
+        assert!(rate.factor < 1 << PROTOCOL_FEE_BITS);
+        assert!(((1u128 << 80) - 1).checked_mul(rate.factor).is_some());
+    }
 }
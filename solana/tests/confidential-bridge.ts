@@ -0,0 +1,272 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+    Ed25519Program,
+    Keypair,
+    PublicKey,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    SystemProgram,
+    TransactionInstruction,
+} from "@solana/web3.js";
+import { expect } from "chai";
+import { createHash } from "crypto";
+import { allowancePda, handleOf, testHandle } from "./utils/inco";
+
+describe("confidential-bridge", () => {
+    // Configure the client
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const bridge = anchor.workspace.ConfidentialBridge as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    // Inco Lightning Program ID on Devnet
+    const INCO_LIGHTNING_ID = new PublicKey("5sjEbPiqgZrYwR31ahR6Uk9wf5awoX61YGg7jExQSwaj");
+
+    // Must match DEPOSIT_DOMAIN in programs/confidential-bridge/src/lib.rs
+    const DEPOSIT_DOMAIN = Buffer.from("inco-bridge:deposit");
+
+    // EVM side (ConfidentialERC20 on Base Sepolia)
+    const EVM_CHAIN_ID = new BN(84532);
+    const EVM_TOKEN = Array.from(Buffer.alloc(20, 0xab));
+
+    // Local keypairs act as the attestor set (2-of-3)
+    const attestors = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const THRESHOLD = 2;
+
+    // Key the attestor service uses to decrypt burned amounts
+    const releaseAuthority = Keypair.generate();
+
+    const mint = Keypair.generate();
+    const recipient = provider.wallet.publicKey;
+
+    let bridgeConfig: PublicKey;
+    let recipientBalance: PublicKey;
+
+    // Stand-in for the Inco ciphertext attestors produce for the EVM amount
+    const ciphertextFor = (amount: number) => Buffer.from(`inco-ciphertext:${amount}`);
+
+    // Mirrors confidential_bridge::deposit_message
+    const depositMessage = (sourceTxHash: Buffer, ciphertext: Buffer) =>
+        Buffer.concat([
+            DEPOSIT_DOMAIN,
+            bridgeConfig.toBuffer(),
+            EVM_CHAIN_ID.toArrayLike(Buffer, "le", 8),
+            sourceTxHash,
+            recipient.toBuffer(),
+            createHash("sha256").update(ciphertext).digest(),
+        ]);
+
+    // One ed25519 verify instruction per attesting keypair
+    const attest = (signers: Keypair[], message: Buffer): TransactionInstruction[] =>
+        signers.map((signer) =>
+            Ed25519Program.createInstructionWithPrivateKey({
+                privateKey: signer.secretKey,
+                message,
+            })
+        );
+
+    const completeDeposit = (
+        sourceTxHash: Buffer,
+        ciphertext: Buffer,
+        preInstructions: TransactionInstruction[]
+    ) => {
+        const [processedDeposit] = PublicKey.findProgramAddressSync(
+            [Buffer.from("deposit"), bridgeConfig.toBuffer(), sourceTxHash],
+            bridge.programId
+        );
+        return bridge.methods
+            .completeDeposit(Array.from(sourceTxHash), ciphertext)
+            .accountsPartial({
+                bridgeConfig,
+                processedDeposit,
+                recipientBalance,
+                recipient,
+                mint: mint.publicKey,
+                relayer: provider.wallet.publicKey,
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(preInstructions)
+            .rpc();
+    };
+
+    before(async () => {
+        [bridgeConfig] = PublicKey.findProgramAddressSync(
+            [Buffer.from("bridge_config"), mint.publicKey.toBuffer()],
+            bridge.programId
+        );
+        [recipientBalance] = PublicKey.findProgramAddressSync(
+            [Buffer.from("user_balance"), recipient.toBuffer(), mint.publicKey.toBuffer()],
+            incoToken.programId
+        );
+
+        // Confidential mint the bridge admin controls until initialize_bridge takes it over
+        await incoToken.methods
+            .initializeMint(6, provider.wallet.publicKey, null)
+            .accounts({ mint: mint.publicKey, payer: provider.wallet.publicKey })
+            .signers([mint])
+            .rpc();
+
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: recipient })
+            .rpc();
+    });
+
+    it("Only lets the mint authority bridge the mint", async () => {
+        const outsider = Keypair.generate();
+        await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(outsider.publicKey, 1_000_000_000),
+            "confirmed"
+        );
+        try {
+            await bridge.methods
+                .initializeBridge(
+                    EVM_CHAIN_ID,
+                    EVM_TOKEN,
+                    [outsider.publicKey],
+                    1,
+                    outsider.publicKey
+                )
+                .accounts({ mint: mint.publicKey, admin: outsider.publicKey })
+                .signers([outsider])
+                .rpc();
+            expect.fail("an outsider should not take over the mint");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidMintAuthority");
+        }
+    });
+
+    it("Initializes the bridge with a 2-of-3 attestor set", async () => {
+        await bridge.methods
+            .initializeBridge(
+                EVM_CHAIN_ID,
+                EVM_TOKEN,
+                attestors.map((a) => a.publicKey),
+                THRESHOLD,
+                releaseAuthority.publicKey
+            )
+            .accounts({ mint: mint.publicKey, admin: provider.wallet.publicKey })
+            .rpc();
+
+        const config: any = await (bridge.account as any).bridgeConfig.fetch(bridgeConfig);
+        expect(config.threshold).to.equal(THRESHOLD);
+        expect(config.attestors).to.have.length(3);
+
+        const mintState: any = await (incoToken.account as any).incoMint.fetch(mint.publicKey);
+        expect(JSON.stringify(mintState.mintAuthority)).to.contain(bridgeConfig.toBase58());
+    });
+
+    it("Rejects a deposit below the attestor quorum", async () => {
+        const sourceTxHash = Buffer.alloc(32, 1);
+        const ciphertext = ciphertextFor(1_000_000);
+        const message = depositMessage(sourceTxHash, ciphertext);
+
+        try {
+            await completeDeposit(sourceTxHash, ciphertext, attest(attestors.slice(0, 1), message));
+            expect.fail("deposit below quorum should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("QuorumNotReached");
+        }
+    });
+
+    it("Ignores signatures from keys outside the attestor set", async () => {
+        const sourceTxHash = Buffer.alloc(32, 2);
+        const ciphertext = ciphertextFor(1_000_000);
+        const message = depositMessage(sourceTxHash, ciphertext);
+        const outsiders = [Keypair.generate(), Keypair.generate()];
+
+        try {
+            await completeDeposit(sourceTxHash, ciphertext, attest(outsiders, message));
+            expect.fail("deposit signed by non-attestors should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("QuorumNotReached");
+        }
+    });
+
+    it("Rejects a ciphertext the attestors did not sign", async () => {
+        const sourceTxHash = Buffer.alloc(32, 3);
+        const ciphertext = ciphertextFor(1_000_000);
+        // Attestors signed a different ciphertext than the one submitted
+        const message = depositMessage(sourceTxHash, ciphertextFor(1_000_001));
+
+        try {
+            await completeDeposit(sourceTxHash, ciphertext, attest(attestors.slice(0, 2), message));
+            expect.fail("mismatched attestation should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("QuorumNotReached");
+        }
+    });
+
+    it("Mints against a quorum and rejects replays (requires Inco Lightning)", async () => {
+        const sourceTxHash = Buffer.alloc(32, 4);
+        const ciphertext = ciphertextFor(1_000_000);
+        const message = depositMessage(sourceTxHash, ciphertext);
+
+        await completeDeposit(sourceTxHash, ciphertext, attest(attestors.slice(1), message));
+
+        try {
+            await completeDeposit(sourceTxHash, ciphertext, attest(attestors.slice(1), message));
+            expect.fail("replayed deposit should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("already in use");
+        }
+    });
+
+    it("Grants the release authority the burned amount (requires Inco Lightning)", async () => {
+        const bridgeOut = (accounts: Record<string, PublicKey | null>) =>
+            bridge.methods
+                .bridgeOut(new BN(testHandle(7).toString()), Array.from(Buffer.alloc(20, 0xcd)))
+                .accountsPartial({
+                    bridgeConfig,
+                    userBalance: recipientBalance,
+                    mint: mint.publicKey,
+                    user: recipient,
+                    incoTokenProgram: incoToken.programId,
+                    incoLightningProgram: INCO_LIGHTNING_ID,
+                    ...accounts,
+                });
+
+        // Simulate without the allowance to learn the burned handle
+        const simulated = await bridgeOut({
+            releaseAuthority: null,
+            releaseAllowance: null,
+            systemProgram: null,
+        }).simulate();
+        const message: any = simulated.events.find((e: any) => e.name === "bridgeOutMessage");
+        const burned = handleOf(message.data.amount);
+
+        await bridgeOut({
+            releaseAuthority: releaseAuthority.publicKey,
+            releaseAllowance: allowancePda(burned, releaseAuthority.publicKey),
+            systemProgram: SystemProgram.programId,
+        }).rpc();
+
+        const config: any = await (bridge.account as any).bridgeConfig.fetch(bridgeConfig);
+        expect(config.outboundNonce.toNumber()).to.equal(1);
+    });
+
+    it("Rejects a release authority other than the configured one", async () => {
+        try {
+            await bridge.methods
+                .bridgeOut(new BN(testHandle(7).toString()), Array.from(Buffer.alloc(20, 0xcd)))
+                .accountsPartial({
+                    bridgeConfig,
+                    userBalance: recipientBalance,
+                    mint: mint.publicKey,
+                    user: recipient,
+                    incoTokenProgram: incoToken.programId,
+                    incoLightningProgram: INCO_LIGHTNING_ID,
+                    releaseAuthority: Keypair.generate().publicKey,
+                    releaseAllowance: null,
+                    systemProgram: null,
+                })
+                .rpc();
+            expect.fail("foreign release authority should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidReleaseAuthority");
+        }
+    });
+});
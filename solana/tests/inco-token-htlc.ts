@@ -0,0 +1,186 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { createHash, randomBytes } from "crypto";
+import { INCO_LIGHTNING_ID, handleOf, testHandle } from "./utils/inco";
+
+describe("inco-token hash-time-locked escrows (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const mint = Keypair.generate();
+    const sender = provider.wallet.publicKey;
+    const recipient = Keypair.generate();
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    const balanceOf = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("user_balance"), user.toBuffer(), mint.publicKey.toBuffer()],
+            incoToken.programId
+        )[0];
+    const escrowPda = (hashlock: Buffer) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("htlc"), sender.toBuffer(), hashlock],
+            incoToken.programId
+        )[0];
+    const fetchBalance = (user: PublicKey) => (incoToken.account as any).userBalance.fetch(balanceOf(user));
+
+    // Same SHA-256 hashlock as the EVM counterpart
+    const newSecret = () => {
+        const preimage = randomBytes(32);
+        return { preimage, hashlock: createHash("sha256").update(preimage).digest() };
+    };
+    const waitForSlot = async (slot: number) => {
+        while ((await provider.connection.getSlot()) < slot) {
+            await sleep(400);
+        }
+    };
+
+    const lock = (hashlock: Buffer, expirySlot: number) =>
+        incoToken.methods
+            .lockHtlc(Array.from(hashlock), new BN(testHandle(30).toString()), new BN(expirySlot))
+            .accountsPartial({
+                escrow: escrowPda(hashlock),
+                senderBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                sender,
+                recipient: recipient.publicKey,
+                recipientAllowance: null,
+                senderAllowance: null,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+
+    const claim = (hashlock: Buffer, preimage: Buffer) =>
+        incoToken.methods
+            .claimHtlc(preimage)
+            .accountsPartial({
+                escrow: escrowPda(hashlock),
+                recipientBalance: balanceOf(recipient.publicKey),
+                mint: mint.publicKey,
+                recipient: recipient.publicKey,
+                sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                recipientAllowance: null,
+                systemProgram: null,
+            })
+            .signers([recipient])
+            .rpc();
+
+    const refund = (hashlock: Buffer) =>
+        incoToken.methods
+            .refundHtlc()
+            .accountsPartial({
+                escrow: escrowPda(hashlock),
+                senderBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                senderAllowance: null,
+                systemProgram: null,
+            })
+            .rpc();
+
+    const claimable = newSecret();
+    const expiring = newSecret();
+    let expirySlot: number;
+
+    before(async () => {
+        await incoToken.methods
+            .initializeMint(6, sender, null)
+            .accounts({ mint: mint.publicKey, payer: sender })
+            .signers([mint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: sender })
+            .rpc();
+        await incoToken.methods
+            .initializeOwnedBalance()
+            .accounts({ mint: mint.publicKey, owner: recipient.publicKey, payer: sender })
+            .signers([recipient])
+            .rpc();
+        await incoToken.methods
+            .mintToUser(new BN(testHandle(100).toString()))
+            .accountsPartial({
+                userBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                mintAuthority: sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+    });
+
+    it("Rejects an expiry slot that has already passed", async () => {
+        const { hashlock } = newSecret();
+        try {
+            await lock(hashlock, await provider.connection.getSlot());
+            expect.fail("an expired lock should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidExpiry");
+        }
+    });
+
+    it("Locks funds under a hashlock", async () => {
+        const slot = await provider.connection.getSlot();
+        await lock(claimable.hashlock, slot + 1000);
+        expirySlot = slot + 15;
+        await lock(expiring.hashlock, expirySlot);
+
+        const escrow = await (incoToken.account as any).htlcEscrow.fetch(escrowPda(claimable.hashlock));
+        expect(Buffer.from(escrow.hashlock).equals(claimable.hashlock)).to.be.true;
+        expect(escrow.recipient.toBase58()).to.equal(recipient.publicKey.toBase58());
+        expect(handleOf(escrow.amount)).to.not.equal(0n);
+    });
+
+    it("Rejects a wrong preimage", async () => {
+        try {
+            await claim(claimable.hashlock, randomBytes(32));
+            expect.fail("a wrong preimage should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidPreimage");
+        }
+    });
+
+    it("Rejects a refund before expiry", async () => {
+        try {
+            await refund(claimable.hashlock);
+            expect.fail("a refund before expiry should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("HtlcNotExpired");
+        }
+    });
+
+    it("Releases the escrow to the recipient for the correct preimage", async () => {
+        const before = await fetchBalance(recipient.publicKey);
+        await claim(claimable.hashlock, claimable.preimage);
+
+        expect(await provider.connection.getAccountInfo(escrowPda(claimable.hashlock))).to.be.null;
+        expect(handleOf((await fetchBalance(recipient.publicKey)).encryptedBalance)).to.not.equal(
+            handleOf(before.encryptedBalance)
+        );
+    });
+
+    it("Rejects a claim after expiry, even with the correct preimage", async () => {
+        await waitForSlot(expirySlot);
+        try {
+            await claim(expiring.hashlock, expiring.preimage);
+            expect.fail("a claim after expiry should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("HtlcExpired");
+        }
+    });
+
+    it("Refunds the sender after expiry", async () => {
+        const before = await fetchBalance(sender);
+        await refund(expiring.hashlock);
+
+        expect(await provider.connection.getAccountInfo(escrowPda(expiring.hashlock))).to.be.null;
+        expect(handleOf((await fetchBalance(sender)).encryptedBalance)).to.not.equal(
+            handleOf(before.encryptedBalance)
+        );
+    });
+});
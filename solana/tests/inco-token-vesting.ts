@@ -0,0 +1,199 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf, testHandle } from "./utils/inco";
+
+describe("inco-token vesting (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const mint = Keypair.generate();
+    const grantor = provider.wallet.publicKey;
+    const beneficiary = Keypair.generate();
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    const balanceOf = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("user_balance"), user.toBuffer(), mint.publicKey.toBuffer()],
+            incoToken.programId
+        )[0];
+    const vestingPda = (id: number) =>
+        PublicKey.findProgramAddressSync(
+            [
+                Buffer.from("vesting"),
+                grantor.toBuffer(),
+                beneficiary.publicKey.toBuffer(),
+                mint.publicKey.toBuffer(),
+                new BN(id).toArrayLike(Buffer, "le", 8),
+            ],
+            incoToken.programId
+        )[0];
+    const fetchVesting = (id: number) => (incoToken.account as any).vestingAccount.fetch(vestingPda(id));
+    const fetchBalance = (user: PublicKey) => (incoToken.account as any).userBalance.fetch(balanceOf(user));
+
+    // Schedules are public unix timestamps, compared against the cluster clock
+    const chainTime = async () => (await provider.connection.getBlockTime(await provider.connection.getSlot()))!;
+    const waitUntil = async (ts: number) => {
+        while ((await chainTime()) <= ts) {
+            await sleep(1000);
+        }
+    };
+
+    const createVesting = (id: number, start: number, cliff: number, end: number, revocable: boolean) =>
+        incoToken.methods
+            .createVesting(new BN(id), new BN(testHandle(40).toString()), new BN(start), new BN(cliff), new BN(end), revocable)
+            .accountsPartial({
+                vesting: vestingPda(id),
+                grantorBalance: balanceOf(grantor),
+                mint: mint.publicKey,
+                grantor,
+                beneficiary: beneficiary.publicKey,
+                grantorAllowance: null,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+
+    const claim = (id: number) =>
+        incoToken.methods
+            .claimVested()
+            .accountsPartial({
+                vesting: vestingPda(id),
+                beneficiaryBalance: balanceOf(beneficiary.publicKey),
+                mint: mint.publicKey,
+                beneficiary: beneficiary.publicKey,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                beneficiaryAllowance: null,
+                systemProgram: null,
+            })
+            .signers([beneficiary])
+            .rpc();
+
+    const revoke = (id: number) =>
+        incoToken.methods
+            .revokeVesting()
+            .accountsPartial({
+                vesting: vestingPda(id),
+                grantorBalance: balanceOf(grantor),
+                mint: mint.publicKey,
+                grantor,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                grantorAllowance: null,
+                systemProgram: null,
+            })
+            .rpc();
+
+    let cliff: number;
+
+    before(async () => {
+        await incoToken.methods
+            .initializeMint(6, grantor, null)
+            .accounts({ mint: mint.publicKey, payer: grantor })
+            .signers([mint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: grantor })
+            .rpc();
+        await incoToken.methods
+            .initializeOwnedBalance()
+            .accounts({ mint: mint.publicKey, owner: beneficiary.publicKey, payer: grantor })
+            .signers([beneficiary])
+            .rpc();
+        await incoToken.methods
+            .mintToUser(new BN(testHandle(100).toString()))
+            .accountsPartial({
+                userBalance: balanceOf(grantor),
+                mint: mint.publicKey,
+                mintAuthority: grantor,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+    });
+
+    it("Rejects a schedule whose cliff falls after its end", async () => {
+        const now = await chainTime();
+        try {
+            await createVesting(0, now, now + 200, now + 100, true);
+            expect.fail("cliff after end should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidVestingSchedule");
+        }
+    });
+
+    it("Locks the grant out of the grantor's balance", async () => {
+        const now = await chainTime();
+        cliff = now + 5;
+        const before = await fetchBalance(grantor);
+        await createVesting(1, now, cliff, now + 3600, true);
+        await createVesting(2, now, now, now + 3600, false);
+
+        const grant = await fetchVesting(1);
+        expect(grant.cliffTs.toNumber()).to.equal(cliff);
+        expect(grant.revoked).to.be.false;
+        expect(handleOf(grant.totalAmount)).to.not.equal(0n);
+        expect(handleOf((await fetchBalance(grantor)).encryptedBalance)).to.not.equal(
+            handleOf(before.encryptedBalance)
+        );
+    });
+
+    it("Releases nothing before the cliff", async () => {
+        try {
+            await claim(1);
+            expect.fail("a claim before the cliff should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("CliffNotReached");
+        }
+    });
+
+    it("Releases the linearly vested amount after the cliff, claim by claim", async () => {
+        await waitUntil(cliff);
+        await claim(1);
+        const first = await fetchVesting(1);
+        const credited = handleOf((await fetchBalance(beneficiary.publicKey)).encryptedBalance);
+        expect(credited).to.not.equal(0n);
+
+        // Later claims release what vested since the last one
+        await sleep(2000);
+        await claim(1);
+        const second = await fetchVesting(1);
+        expect(handleOf(second.releasedAmount)).to.not.equal(handleOf(first.releasedAmount));
+        expect(handleOf((await fetchBalance(beneficiary.publicKey)).encryptedBalance)).to.not.equal(credited);
+    });
+
+    it("Revokes the unvested portion back to the grantor", async () => {
+        const before = await fetchBalance(grantor);
+        await revoke(1);
+
+        const grant = await fetchVesting(1);
+        expect(grant.revoked).to.be.true;
+        // The schedule ends now, so what remains is fully vested
+        expect(grant.endTs.toNumber()).to.be.lessThan(cliff + 3600);
+        expect(handleOf((await fetchBalance(grantor)).encryptedBalance)).to.not.equal(
+            handleOf(before.encryptedBalance)
+        );
+
+        // The beneficiary keeps what had vested by the revocation
+        await claim(1);
+    });
+
+    it("Rejects a second revocation", async () => {
+        try {
+            await revoke(1);
+            expect.fail("a grant can only be revoked once");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("VestingAlreadyRevoked");
+        }
+    });
+
+    it("Rejects revoking an irrevocable grant", async () => {
+        try {
+            await revoke(2);
+            expect.fail("an irrevocable grant cannot be revoked");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("VestingNotRevocable");
+        }
+    });
+});
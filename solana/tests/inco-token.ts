@@ -1,7 +1,8 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
-import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
 import { expect } from "chai";
+import { INCO_LIGHTNING_ID, allowancePda, handleOf, simulateAccounts, testHandle } from "./utils/inco";
 
 // Import the IDL (generated after anchor build)
 // import { IncoToken } from "../target/types/inco_token";
@@ -94,3 +95,327 @@ describe("inco-token", () => {
         expect(true).to.be.true;
     });
 });
+
+describe("inco-token batch_transfer (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    // Must match MAX_BATCH_TRANSFERS in programs/inco-token/src/lib.rs
+    const MAX_BATCH_TRANSFERS = 7;
+
+    const mint = Keypair.generate();
+    const sender = provider.wallet.publicKey;
+    const recipients = Array.from({ length: MAX_BATCH_TRANSFERS + 1 }, () => Keypair.generate());
+
+    const balanceOf = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("user_balance"), user.toBuffer(), mint.publicKey.toBuffer()],
+            incoToken.programId
+        )[0];
+
+    const batch = (legs: Keypair[]) =>
+        incoToken.methods
+            .batchTransfer(legs.map((_, i) => new BN(testHandle(i + 1).toString())))
+            .accountsPartial({
+                sourceBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                sourceUser: sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                sourceAllowance: null,
+                systemProgram: null,
+            })
+            .remainingAccounts(
+                legs.map((r) => ({ pubkey: balanceOf(r.publicKey), isSigner: false, isWritable: true }))
+            );
+
+    before(async () => {
+        await incoToken.methods
+            .initializeMint(6, sender, null)
+            .accounts({ mint: mint.publicKey, payer: sender })
+            .signers([mint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: sender })
+            .rpc();
+        for (const recipient of recipients) {
+            await incoToken.methods
+                .initializeOwnedBalance()
+                .accounts({ mint: mint.publicKey, owner: recipient.publicKey, payer: sender })
+                .signers([recipient])
+                .rpc();
+        }
+        await incoToken.methods
+            .mintToUser(new BN(testHandle(100).toString()))
+            .accountsPartial({
+                userBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                mintAuthority: sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+    });
+
+    it("Rejects a batch above MAX_BATCH_TRANSFERS", async () => {
+        try {
+            await batch(recipients).rpc();
+            expect.fail("oversized batch should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("BatchTooLarge");
+        }
+    });
+
+    it("Sends a maximum-size batch with every allowance granted", async () => {
+        const legs = recipients.slice(0, MAX_BATCH_TRANSFERS);
+
+        // Simulate without allowances to learn the handles the batch creates
+        const destinations = legs.map((r) => balanceOf(r.publicKey));
+        const [source, ...balances] = await simulateAccounts(
+            provider,
+            incoToken,
+            await batch(legs).transaction(),
+            "UserBalance",
+            [balanceOf(sender), ...destinations]
+        );
+        const simulated = await batch(legs).simulate();
+        const flags = simulated.events
+            .filter((e: any) => e.name === "batchTransferLeg")
+            .map((e: any) => handleOf(e.data.success));
+        expect(flags).to.have.length(MAX_BATCH_TRANSFERS);
+
+        const allowances = legs.flatMap((r, i) => [
+            { pubkey: r.publicKey, isSigner: false, isWritable: false },
+            {
+                pubkey: allowancePda(handleOf(balances[i].encryptedBalance), r.publicKey),
+                isSigner: false,
+                isWritable: true,
+            },
+            { pubkey: allowancePda(flags[i], sender), isSigner: false, isWritable: true },
+        ]);
+
+        await incoToken.methods
+            .batchTransfer(legs.map((_, i) => new BN(testHandle(i + 1).toString())))
+            .accountsPartial({
+                sourceBalance: balanceOf(sender),
+                mint: mint.publicKey,
+                sourceUser: sender,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+                sourceAllowance: allowancePda(handleOf(source.encryptedBalance), sender),
+                systemProgram: SystemProgram.programId,
+            })
+            .remainingAccounts([
+                ...destinations.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+                ...allowances,
+            ])
+            .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+            .rpc();
+    });
+});
+
+describe("inco-token owner allowances (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const mint = Keypair.generate();
+    const sender = provider.wallet.publicKey;
+    const recipient = Keypair.generate();
+
+    const balanceOf = (user: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("user_balance"), user.toBuffer(), mint.publicKey.toBuffer()],
+            incoToken.programId
+        )[0];
+
+    const mintTo = (allowance: PublicKey | null, systemProgram: PublicKey | null) =>
+        incoToken.methods.mintToUser(new BN(testHandle(100).toString())).accountsPartial({
+            userBalance: balanceOf(sender),
+            mint: mint.publicKey,
+            mintAuthority: sender,
+            incoLightningProgram: INCO_LIGHTNING_ID,
+            user: sender,
+            userAllowance: allowance,
+            systemProgram,
+        });
+
+    const transfer = (sourceAllowance: PublicKey | null, destAllowance: PublicKey | null) =>
+        incoToken.methods.confidentialTransfer(new BN(testHandle(7).toString())).accountsPartial({
+            sourceBalance: balanceOf(sender),
+            destBalance: balanceOf(recipient.publicKey),
+            mint: mint.publicKey,
+            owner: sender,
+            incoLightningProgram: INCO_LIGHTNING_ID,
+            sourceAllowance,
+            destUser: recipient.publicKey,
+            destAllowance,
+            systemProgram: SystemProgram.programId,
+        });
+
+    const expectAllowance = async (handle: bigint, allowed: PublicKey) =>
+        expect(await provider.connection.getAccountInfo(allowancePda(handle, allowed))).to.not.be.null;
+
+    before(async () => {
+        await incoToken.methods
+            .initializeMint(6, sender, null)
+            .accounts({ mint: mint.publicKey, payer: sender })
+            .signers([mint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: sender })
+            .rpc();
+        await incoToken.methods
+            .initializeOwnedBalance()
+            .accounts({ mint: mint.publicKey, owner: recipient.publicKey, payer: sender })
+            .signers([recipient])
+            .rpc();
+    });
+
+    it("Rejects an allowance without the system program", async () => {
+        try {
+            await mintTo(allowancePda(testHandle(100), sender), null).rpc();
+            expect.fail("an allowance without the system program should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("MissingAllowanceAccounts");
+        }
+    });
+
+    it("Lets the recipient decrypt a minted balance", async () => {
+        const [balance] = await simulateAccounts(
+            provider,
+            incoToken,
+            await mintTo(null, null).transaction(),
+            "UserBalance",
+            [balanceOf(sender)]
+        );
+        const handle = handleOf(balance.encryptedBalance);
+
+        await mintTo(allowancePda(handle, sender), SystemProgram.programId).rpc();
+        await expectAllowance(handle, sender);
+    });
+
+    it("Lets both owners decrypt their balances after a confidential transfer", async () => {
+        const [source, dest] = await simulateAccounts(
+            provider,
+            incoToken,
+            await transfer(null, null).transaction(),
+            "UserBalance",
+            [balanceOf(sender), balanceOf(recipient.publicKey)]
+        );
+        const sourceHandle = handleOf(source.encryptedBalance);
+        const destHandle = handleOf(dest.encryptedBalance);
+
+        await transfer(allowancePda(sourceHandle, sender), allowancePda(destHandle, recipient.publicKey)).rpc();
+        await expectAllowance(sourceHandle, sender);
+        await expectAllowance(destHandle, recipient.publicKey);
+    });
+});
+
+describe("inco-token encrypted memos (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const mint = Keypair.generate();
+    const source = Keypair.generate();
+    const destination = Keypair.generate();
+    const sender = provider.wallet.publicKey;
+    const recipient = Keypair.generate();
+    // Stand-in for a client-encrypted memo (e.g. an invoice reference)
+    const memoCiphertext = Buffer.from("inco-ciphertext:invoice-42");
+
+    const transfer = (
+        memo: Buffer | null,
+        {
+            senderAllowance = null as PublicKey | null,
+            recipientAllowance = null as PublicKey | null,
+            memoRecipient = recipient.publicKey,
+            memoAccounts = memo !== null,
+        } = {}
+    ) =>
+        incoToken.methods.transfer(new BN(testHandle(9).toString()), memo).accountsPartial({
+            source: source.publicKey,
+            destination: destination.publicKey,
+            authority: sender,
+            sender: memoAccounts ? sender : null,
+            recipient: memoAccounts ? memoRecipient : null,
+            senderMemoAllowance: senderAllowance,
+            recipientMemoAllowance: recipientAllowance,
+            incoLightningProgram: memoAccounts ? INCO_LIGHTNING_ID : null,
+            systemProgram: memoAccounts ? SystemProgram.programId : null,
+        });
+
+    const memoOf = async (method: any) => {
+        const simulated = await method.simulate();
+        const event = simulated.events.find((e: any) => e.name === "confidentialTransfer");
+        expect(event).to.not.be.undefined;
+        return event.data.memo;
+    };
+
+    before(async () => {
+        await incoToken.methods
+            .initializeMint(6, sender, null)
+            .accounts({ mint: mint.publicKey, payer: sender })
+            .signers([mint])
+            .rpc();
+        for (const [account, owner] of [
+            [source, sender],
+            [destination, recipient.publicKey],
+        ] as [Keypair, PublicKey][]) {
+            await incoToken.methods
+                .initializeAccount()
+                .accounts({ account: account.publicKey, mint: mint.publicKey, owner, payer: sender })
+                .signers([account])
+                .rpc();
+        }
+    });
+
+    it("Transfers without a memo", async () => {
+        expect(await memoOf(transfer(null))).to.be.null;
+        await transfer(null).rpc();
+    });
+
+    it("Rejects a memo without its accounts", async () => {
+        try {
+            await transfer(memoCiphertext, { memoAccounts: false }).rpc();
+            expect.fail("a memo without its allowance accounts should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("MissingMemoAccounts");
+        }
+    });
+
+    it("Attaches a memo only the sender and recipient may decrypt", async () => {
+        // Simulate without the allowances to learn the memo handle
+        const memo = handleOf(await memoOf(transfer(memoCiphertext)));
+        expect(memo).to.not.equal(0n);
+
+        const senderAllowance = allowancePda(memo, sender);
+        const recipientAllowance = allowancePda(memo, recipient.publicKey);
+        await transfer(memoCiphertext, { senderAllowance, recipientAllowance }).rpc();
+
+        expect(await provider.connection.getAccountInfo(senderAllowance)).to.not.be.null;
+        expect(await provider.connection.getAccountInfo(recipientAllowance)).to.not.be.null;
+    });
+
+    it("Rejects a memo granted to only one side", async () => {
+        try {
+            await transfer(memoCiphertext, { senderAllowance: allowancePda(testHandle(1), sender) }).rpc();
+            expect.fail("a memo must be granted to both sides or neither");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("MissingMemoAccounts");
+        }
+    });
+
+    it("Rejects a memo naming someone other than the recipient", async () => {
+        try {
+            await transfer(memoCiphertext, { memoRecipient: Keypair.generate().publicKey }).rpc();
+            expect.fail("the memo recipient must own the destination");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("OwnerMismatch");
+        }
+    });
+});
@@ -0,0 +1,156 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    BATCH_EPOCH_SLOTS,
+    MAX_TRACE_ENTRIES,
+    balancePda,
+    batchPda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm batch auction (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+    let epoch: number;
+
+    const fetchBatch = (e: number) => (amm.account as any).batchAuction.fetch(batchPda(amm, pool, e));
+
+    // Accounts shared by submit_batch_order, claim_batch_order and refund_batch_order
+    const batchAccounts = (e: number) => ({
+        pool,
+        batch: batchPda(amm, pool, e),
+        tokenAMint: mintA,
+        tokenBMint: mintB,
+        userBalanceA: balancePda(incoToken, user, mintA),
+        userBalanceB: balancePda(incoToken, user, mintB),
+        vaultA: balancePda(incoToken, pool, mintA),
+        vaultB: balancePda(incoToken, pool, mintB),
+        user,
+        incoTokenProgram: incoToken.programId,
+        incoLightningProgram: INCO_LIGHTNING_ID,
+    });
+
+    // Queue an order in the current epoch, waiting out an epoch about to close
+    const submitOrder = async (amountA: number, amountB: number) => {
+        while ((await provider.connection.getSlot()) % BATCH_EPOCH_SLOTS > BATCH_EPOCH_SLOTS - 20) {
+            await sleep(400);
+        }
+        const current = Math.floor((await provider.connection.getSlot()) / BATCH_EPOCH_SLOTS);
+        const signature = await amm.methods
+            .submitBatchOrder(new BN(current), handleBn(amountA), handleBn(amountB))
+            .accountsPartial(batchAccounts(current))
+            .preInstructions(computeBudget)
+            .rpc();
+        return { current, signature };
+    };
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 4000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Queues an order with both legs deposited", async () => {
+        const { current, signature } = await submitOrder(41, 42);
+        epoch = current;
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const batch = await fetchBatch(epoch);
+        expect(batch.orders.length).to.equal(1);
+        expect(batch.endSlot.toNumber()).to.equal((epoch + 1) * BATCH_EPOCH_SLOTS);
+    });
+
+    it("Rejects settlement before the epoch ends", async () => {
+        try {
+            await amm.methods
+                .settleBatch(handleBn(43), handleBn(44))
+                .accountsPartial({ pool, batch: batchPda(amm, pool, epoch), settler: user })
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("settling an open epoch should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("BatchNotEnded");
+        }
+    });
+
+    it("Lets a third party settle against hinted prices", async () => {
+        const { endSlot } = await fetchBatch(epoch);
+        while ((await provider.connection.getSlot()) < endSlot.toNumber()) {
+            await sleep(400);
+        }
+        const keeper = Keypair.generate();
+        await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(keeper.publicKey, 1_000_000_000),
+            "confirmed"
+        );
+
+        const signature = await amm.methods
+            .settleBatch(handleBn(43), handleBn(44))
+            .accountsPartial({ pool, batch: batchPda(amm, pool, epoch), settler: keeper.publicKey })
+            .signers([keeper])
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const batch = await fetchBatch(epoch);
+        expect(batch.settled).to.be.true;
+        expect(handleOf(batch.settleOk)).to.not.equal(0n);
+        expect(handleOf(batch.priceAInB)).to.not.equal(0n);
+    });
+
+    it("Claims a settled order once and never refunds it", async () => {
+        const signature = await amm.methods
+            .claimBatchOrder(0)
+            .accountsPartial(batchAccounts(epoch))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+        expect((await fetchBatch(epoch)).orders[0].claimed).to.be.true;
+
+        try {
+            await amm.methods.claimBatchOrder(0).accountsPartial(batchAccounts(epoch)).rpc();
+            expect.fail("a second claim should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("OrderAlreadyClaimed");
+        }
+        try {
+            await amm.methods.refundBatchOrder(0).accountsPartial(batchAccounts(epoch)).rpc();
+            expect.fail("a settled batch should not refund");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("BatchAlreadySettled");
+        }
+    });
+
+    it("Holds refunds until the settlement window closes", async () => {
+        const { current } = await submitOrder(45, 46);
+        try {
+            await amm.methods.refundBatchOrder(0).accountsPartial(batchAccounts(current)).rpc();
+            expect.fail("an unsettled batch inside its window should not refund");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("BatchNotExpired");
+        }
+    });
+});
@@ -0,0 +1,103 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+    createFundedMintPair,
+    createPool,
+    ensureFactory,
+    factoryPda,
+    programDataPda,
+    registryPagePda,
+} from "./utils/amm";
+
+describe("private-amm factory (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const outsider = Keypair.generate();
+
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+
+    before(async () => {
+        const [mintA, mintB] = await createFundedMintPair(incoToken, user, 7000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+    });
+
+    it("Registers each pool it creates", async () => {
+        const factory = await ensureFactory(amm, user);
+        const lastIndex = factory.poolCount.subn(1);
+        const page = await (amm.account as any).poolRegistryPage.fetch(registryPagePda(amm, lastIndex));
+        expect(page.pools.map((key: PublicKey) => key.toBase58())).to.include(pool.toBase58());
+    });
+
+    it("Only lets the upgrade authority create the factory", async () => {
+        try {
+            await amm.methods
+                .initializeFactory(30, new BN(0))
+                .accountsPartial({
+                    admin: outsider.publicKey,
+                    program: amm.programId,
+                    programData: programDataPda(amm),
+                    factory: factoryPda(amm),
+                })
+                .signers([outsider])
+                .rpc();
+            expect.fail("an outsider should not create the factory");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidAuthority");
+        }
+    });
+
+    it("Only lets the factory admin update the factory", async () => {
+        try {
+            await amm.methods
+                .setFactoryAdmin(outsider.publicKey)
+                .accountsPartial({ factory: factoryPda(amm), admin: outsider.publicKey })
+                .signers([outsider])
+                .rpc();
+            expect.fail("an outsider should not take over the factory");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidAuthority");
+        }
+    });
+
+    it("Only lets the factory admin set a pool's protocol fee", async () => {
+        try {
+            await amm.methods
+                .setProtocolFee(5000, outsider.publicKey)
+                .accountsPartial({ factory: factoryPda(amm), pool, admin: outsider.publicKey })
+                .signers([outsider])
+                .rpc();
+            expect.fail("a non-admin should not redirect protocol fees");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidAuthority");
+        }
+
+        await amm.methods
+            .setProtocolFee(2000, user)
+            .accountsPartial({ factory: factoryPda(amm), pool, admin: user })
+            .rpc();
+        const state = await fetchPool();
+        expect(state.protocolFeeShareBps).to.equal(2000);
+        expect(state.feeRecipient.toBase58()).to.equal(user.toBase58());
+    });
+
+    it("Caps the protocol fee share", async () => {
+        try {
+            await amm.methods
+                .setProtocolFee(5001, user)
+                .accountsPartial({ factory: factoryPda(amm), pool, admin: user })
+                .rpc();
+            expect.fail("a share above MAX_PROTOCOL_FEE_SHARE_BPS should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("ProtocolFeeTooHigh");
+        }
+    });
+});
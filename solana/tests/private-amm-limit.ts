@@ -0,0 +1,128 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    limitOrderPda,
+    liquidityAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm limit orders (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+    let order: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const fillAccounts = () => ({ pool, limitOrder: order, oracle: null, filler: user, incoLightningProgram: INCO_LIGHTNING_ID });
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 9000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        order = limitOrderPda(amm, pool, user, 0);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Escrows an order with its size and price encrypted", async () => {
+        const signature = await amm.methods
+            .placeLimitOrder(handleBn(81), handleBn(82), new BN(0))
+            .accountsPartial({
+                pool,
+                limitOrder: order,
+                inputMint: mintA,
+                userBalanceIn: balancePda(incoToken, user, mintA),
+                vaultIn: balancePda(incoToken, pool, mintA),
+                user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const state = await (amm.account as any).limitOrder.fetch(order);
+        expect(state.aToB).to.be.true;
+        expect(handleOf(state.amountIn)).to.not.equal(0n);
+    });
+
+    it("Rejects a fill with the wrong number of hints", async () => {
+        try {
+            await amm.methods.tryFill([]).accountsPartial(fillAccounts()).preInstructions(computeBudget).rpc();
+            expect.fail("a fill without its hint should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+
+    it("Tries a fill against a hinted output", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .tryFill([handleBn(83)])
+            .accountsPartial(fillAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.reserveA)).to.not.equal(handleOf(before.reserveA));
+        expect(handleOf((await (amm.account as any).limitOrder.fetch(order)).filled)).to.not.equal(0n);
+    });
+
+    it("Keeps an earlier fill flagged when a later attempt fails", async () => {
+        const before = await (amm.account as any).limitOrder.fetch(order);
+        await amm.methods
+            .tryFill([handleBn(84)])
+            .accountsPartial(fillAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+
+        // filled = filled || this attempt, a new handle derived from the old one
+        const after = await (amm.account as any).limitOrder.fetch(order);
+        expect(handleOf(after.filled)).to.not.equal(0n);
+        expect(handleOf(after.filled)).to.not.equal(handleOf(before.filled));
+        expect(handleOf(after.amountOut)).to.not.equal(handleOf(before.amountOut));
+    });
+
+    it("Cancels the order and closes it", async () => {
+        const signature = await amm.methods
+            .cancelOrder()
+            .accountsPartial({
+                pool,
+                limitOrder: order,
+                tokenAMint: mintA,
+                tokenBMint: mintB,
+                userBalanceA: balancePda(incoToken, user, mintA),
+                userBalanceB: balancePda(incoToken, user, mintB),
+                vaultA: balancePda(incoToken, pool, mintA),
+                vaultB: balancePda(incoToken, pool, mintB),
+                user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+        expect(await provider.connection.getAccountInfo(order)).to.be.null;
+    });
+});
@@ -0,0 +1,168 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    positionPda,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm LP transfers and tokens (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const recipient = Keypair.generate();
+    const lpMint = Keypair.generate();
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const fetchPosition = (owner: PublicKey) => (amm.account as any).userPosition.fetch(positionPda(amm, pool, owner));
+    const fetchLpBalance = () =>
+        (incoToken.account as any).userBalance.fetch(balancePda(incoToken, user, lpMint.publicKey));
+
+    const transfer = (to: PublicKey) =>
+        amm.methods
+            .transferPosition(handleBn(20))
+            .accountsPartial({
+                pool,
+                userPosition: positionPda(amm, pool, user),
+                recipientPosition: positionPda(amm, pool, to),
+                rewards: null,
+                recipient: to,
+                user,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(computeBudget)
+            .rpc();
+    const tokenAccounts = () => ({
+        pool,
+        userPosition: positionPda(amm, pool, user),
+        rewards: null,
+        lpMint: lpMint.publicKey,
+        userLpBalance: balancePda(incoToken, user, lpMint.publicKey),
+        user,
+        incoTokenProgram: incoToken.programId,
+        incoLightningProgram: INCO_LIGHTNING_ID,
+    });
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 6200);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+
+        // LP cTokens are minted by the pool PDA
+        await incoToken.methods
+            .initializeMint(6, pool, null)
+            .accounts({ mint: lpMint.publicKey, payer: user })
+            .signers([lpMint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: lpMint.publicKey, user })
+            .rpc();
+    });
+
+    it("Moves part of a position to another owner", async () => {
+        const before = await fetchPosition(user);
+        const signature = await transfer(recipient.publicKey);
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const received = await fetchPosition(recipient.publicKey);
+        expect(received.owner.toBase58()).to.equal(recipient.publicKey.toBase58());
+        expect(handleOf(received.lpAmount)).to.not.equal(0n);
+        expect(handleOf((await fetchPosition(user)).lpAmount)).to.not.equal(handleOf(before.lpAmount));
+    });
+
+    it("Rejects a transfer to the caller's own position", async () => {
+        try {
+            await transfer(user);
+            expect.fail("a self-transfer should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidOwner");
+        }
+    });
+
+    it("Rejects tokenizing before an LP mint is set", async () => {
+        try {
+            await amm.methods
+                .tokenizePosition(handleBn(21))
+                .accountsPartial(tokenAccounts())
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("tokenizing needs an LP mint");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidLpMint");
+        }
+    });
+
+    it("Only accepts an LP mint the pool mints", async () => {
+        const foreign = Keypair.generate();
+        await incoToken.methods
+            .initializeMint(6, user, null)
+            .accounts({ mint: foreign.publicKey, payer: user })
+            .signers([foreign])
+            .rpc();
+        try {
+            await amm.methods.setLpMint().accountsPartial({ pool, lpMint: foreign.publicKey, authority: user }).rpc();
+            expect.fail("an LP mint the pool cannot mint should be rejected");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidLpMint");
+        }
+
+        await amm.methods.setLpMint().accountsPartial({ pool, lpMint: lpMint.publicKey, authority: user }).rpc();
+        expect((await fetchPool()).lpMint.toBase58()).to.equal(lpMint.publicKey.toBase58());
+    });
+
+    it("Tokenizes part of a position into LP cTokens", async () => {
+        const state = await fetchPool();
+        const position = await fetchPosition(user);
+        const balance = await fetchLpBalance();
+        expect(handleOf(state.tokenizedLpSupply)).to.equal(0n);
+
+        const signature = await amm.methods
+            .tokenizePosition(handleBn(21))
+            .accountsPartial(tokenAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        expect(handleOf((await fetchPool()).tokenizedLpSupply)).to.not.equal(0n);
+        expect(handleOf((await fetchPosition(user)).lpAmount)).to.not.equal(handleOf(position.lpAmount));
+        expect(handleOf((await fetchLpBalance()).encryptedBalance)).to.not.equal(handleOf(balance.encryptedBalance));
+    });
+
+    it("Redeems LP cTokens back into the position", async () => {
+        const state = await fetchPool();
+        const position = await fetchPosition(user);
+
+        const signature = await amm.methods
+            .redeemPositionTokens(handleBn(22))
+            .accountsPartial(tokenAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        expect(handleOf((await fetchPool()).tokenizedLpSupply)).to.not.equal(handleOf(state.tokenizedLpSupply));
+        expect(handleOf((await fetchPosition(user)).lpAmount)).to.not.equal(handleOf(position.lpAmount));
+    });
+});
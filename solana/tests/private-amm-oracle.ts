@@ -0,0 +1,126 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    PoolType,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    swapAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+// Mock feeds only exist in `mock-oracle` builds:
+//   anchor build && anchor build -p private_amm -- --features mock-oracle && anchor test --skip-build
+describe("private-amm oracle pools (requires Inco Lightning and mock-oracle)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    const feedId = Array.from(Buffer.alloc(32, 1));
+    const otherFeedId = Array.from(Buffer.alloc(32, 2));
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+    let nonce = 0;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const feedPda = (id: number[]) =>
+        PublicKey.findProgramAddressSync(
+            [Buffer.from("mock_price"), user.toBuffer(), Buffer.from(id)],
+            amm.programId
+        )[0];
+
+    // 2.00000000 B per A, with a 0.01% confidence interval
+    const createFeed = (id: number[]) =>
+        amm.methods
+            .initializeMockPriceFeed(id, new BN(200_000_000), new BN(20_000), -8)
+            .accountsPartial({ priceFeed: feedPda(id), authority: user })
+            .rpc();
+
+    const setOracle = (feed: PublicKey, id: number[], maxPriceAge: number) =>
+        amm.methods
+            .setOracle(feed, id, false, new BN(maxPriceAge), 100)
+            .accountsPartial({ pool, authority: user })
+            .rpc();
+
+    const swapAToB = async (feed: PublicKey) => {
+        const receipt = nonce++;
+        return amm.methods
+            .swap(handleBn(5), handleBn(6), [], new BN(receipt), await deadline())
+            .accountsPartial({ ...swapAccounts(amm, incoToken, pool, user, mintA, mintB, receipt), oracle: feed })
+            .preInstructions(computeBudget)
+            .rpc();
+    };
+
+    before(async function () {
+        if (!amm.idl.instructions.some((ix: any) => ix.name === "initializeMockPriceFeed")) {
+            this.skip();
+        }
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 9500);
+        pool = await createPool(amm, incoToken, user, mintA, mintB, PoolType.oracle);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        await createFeed(feedId);
+        await createFeed(otherFeedId);
+    });
+
+    it("Swaps at the feed price", async () => {
+        await setOracle(feedPda(feedId), feedId, 60);
+        const before = await fetchPool();
+        expect(before.oracleFeedId).to.deep.equal(feedId);
+
+        const signature = await swapAToB(feedPda(feedId));
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.reserveA)).to.not.equal(handleOf(before.reserveA));
+        expect(handleOf(after.reserveB)).to.not.equal(handleOf(before.reserveB));
+    });
+
+    it("Rejects an update for another feed", async () => {
+        // The pool trusts this account, but it carries a different price id
+        await setOracle(feedPda(otherFeedId), feedId, 60);
+        try {
+            await swapAToB(feedPda(otherFeedId));
+            expect.fail("a mismatched feed should be rejected");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("OracleFeedMismatch");
+        }
+    });
+
+    it("Rejects a stale price", async () => {
+        await setOracle(feedPda(feedId), feedId, 1);
+        await sleep(3000);
+        try {
+            await swapAToB(feedPda(feedId));
+            expect.fail("a stale price should be rejected");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("StaleOraclePrice");
+        }
+    });
+
+    it("Accepts the feed again once a fresh price is published", async () => {
+        await setOracle(feedPda(feedId), feedId, 60);
+        await amm.methods
+            .setMockPrice(new BN(210_000_000), new BN(21_000), -8)
+            .accountsPartial({ priceFeed: feedPda(feedId), authority: user })
+            .rpc();
+        await swapAToB(feedPda(feedId));
+    });
+});
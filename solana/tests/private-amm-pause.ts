@@ -0,0 +1,97 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { handleOf } from "./utils/inco";
+import { createFundedMintPair, createPool, handleBn, liquidityAccounts, swapAccounts } from "./utils/amm";
+
+describe("private-amm pause (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const swap = async (nonce: number) =>
+        amm.methods
+            .swap(handleBn(5), handleBn(6), [handleBn(7)], new BN(nonce), await deadline())
+            .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintA, mintB, nonce))
+            .preInstructions(computeBudget)
+            .rpc();
+    const deposit = async () =>
+        amm.methods
+            .addLiquidity(handleBn(11), handleBn(12), handleBn(13), handleBn(14), new BN(0), false, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 6100);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Only lets the pool authority pause", async () => {
+        const stranger = Keypair.generate();
+        try {
+            await amm.methods.pause().accountsPartial({ pool, authority: stranger.publicKey }).signers([stranger]).rpc();
+            expect.fail("a stranger should not pause the pool");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidAuthority");
+        }
+        expect((await fetchPool()).paused).to.be.false;
+    });
+
+    it("Rejects swaps and deposits while paused", async () => {
+        await amm.methods.pause().accountsPartial({ pool, authority: user }).rpc();
+        expect((await fetchPool()).paused).to.be.true;
+
+        try {
+            await swap(0);
+            expect.fail("a paused pool should reject swaps");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("PoolPaused");
+        }
+        try {
+            await deposit();
+            expect.fail("a paused pool should reject deposits");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("PoolPaused");
+        }
+    });
+
+    it("Keeps withdrawals open while paused", async () => {
+        const before = await fetchPool();
+        await amm.methods
+            .removeLiquidity(handleBn(8), handleBn(9), await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(handleOf((await fetchPool()).lpSupply)).to.not.equal(handleOf(before.lpSupply));
+    });
+
+    it("Reopens swaps and deposits once unpaused", async () => {
+        await amm.methods.unpause().accountsPartial({ pool, authority: user }).rpc();
+        expect((await fetchPool()).paused).to.be.false;
+
+        const before = await fetchPool();
+        await swap(0);
+        await deposit();
+        const after = await fetchPool();
+        expect(handleOf(after.reserveA)).to.not.equal(handleOf(before.reserveA));
+        expect(handleOf(after.lpSupply)).to.not.equal(handleOf(before.lpSupply));
+    });
+});
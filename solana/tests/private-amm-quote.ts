@@ -0,0 +1,105 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, allowancePda, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm verify_quote (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const quoteAccounts = (amountOutAllowance: PublicKey | null = null, feeAllowance: PublicKey | null = null) => ({
+        pool,
+        oracle: null,
+        tickGrid: null,
+        amountOutAllowance,
+        feeAllowance,
+        caller: user,
+        incoLightningProgram: INCO_LIGHTNING_ID,
+    });
+    const verifyQuote = (allowances: [PublicKey, PublicKey] | [] = []) =>
+        amm.methods
+            .verifyQuote(handleBn(61), true, [handleBn(62)])
+            .accountsPartial(quoteAccounts(...allowances))
+            .preInstructions(computeBudget);
+
+    // QuoteResult return data: two little-endian u128 handles (amount_out, fee)
+    const readHandle = (data: Buffer, offset: number) =>
+        BigInt("0x" + Buffer.from(data.subarray(offset, offset + 16)).reverse().toString("hex"));
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 6000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Verifies a hint without touching the pool", async () => {
+        const before = await fetchPool();
+        const signature = await verifyQuote().rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.reserveA)).to.equal(handleOf(before.reserveA));
+        expect(handleOf(after.reserveB)).to.equal(handleOf(before.reserveB));
+    });
+
+    it("Returns the checked output and fee handles and grants them to the caller", async () => {
+        const tx = await verifyQuote().transaction();
+        tx.feePayer = user;
+        tx.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+        const { value } = await provider.connection.simulateTransaction(tx);
+        expect(value.err).to.be.null;
+        expect(value.returnData?.programId).to.equal(amm.programId.toBase58());
+
+        const returned = Buffer.from(value.returnData!.data[0], "base64");
+        expect(returned).to.have.length(32);
+        const amountOut = readHandle(returned, 0);
+        const fee = readHandle(returned, 16);
+        // Both are fresh select results, never the caller's hint passed through
+        expect(amountOut).to.not.equal(0n);
+        expect(fee).to.not.equal(0n);
+        expect(amountOut).to.not.equal(BigInt(handleBn(62).toString()));
+        expect(amountOut).to.not.equal(fee);
+
+        await verifyQuote([allowancePda(amountOut, user), allowancePda(fee, user)]).rpc();
+        expect(await provider.connection.getAccountInfo(allowancePda(amountOut, user))).to.not.be.null;
+        expect(await provider.connection.getAccountInfo(allowancePda(fee, user))).to.not.be.null;
+    });
+
+    it("Rejects a quote with the wrong number of hints", async () => {
+        try {
+            await amm.methods
+                .verifyQuote(handleBn(61), false, [])
+                .accountsPartial(quoteAccounts())
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("verify_quote without its hint should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+});
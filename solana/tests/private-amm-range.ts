@@ -0,0 +1,118 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    PoolType,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    swapAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm concentrated liquidity (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    const [TICK_LOWER, TICK_UPPER] = [-100, 100];
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const tickGrid = () =>
+        PublicKey.findProgramAddressSync([Buffer.from("tick_grid"), pool.toBuffer()], amm.programId)[0];
+
+    const rangePosition = () =>
+        PublicKey.findProgramAddressSync(
+            [
+                Buffer.from("range_position"),
+                pool.toBuffer(),
+                user.toBuffer(),
+                new BN(TICK_LOWER).toTwos(32).toArrayLike(Buffer, "le", 4),
+                new BN(TICK_UPPER).toTwos(32).toArrayLike(Buffer, "le", 4),
+            ],
+            amm.programId
+        )[0];
+
+    // Accounts shared by open_range_position and close_range_position
+    const rangeAccounts = () => ({
+        pool,
+        tickGrid: tickGrid(),
+        rangePosition: rangePosition(),
+        tokenAMint: mintA,
+        tokenBMint: mintB,
+        userBalanceA: balancePda(incoToken, user, mintA),
+        userBalanceB: balancePda(incoToken, user, mintB),
+        vaultA: balancePda(incoToken, pool, mintA),
+        vaultB: balancePda(incoToken, pool, mintB),
+        user,
+        incoTokenProgram: incoToken.programId,
+        incoLightningProgram: INCO_LIGHTNING_ID,
+    });
+
+    const fetchGrid = () => (amm.account as any).tickGrid.fetch(tickGrid());
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 3000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB, PoolType.concentrated);
+        await amm.methods
+            .initializeTickGrid(10, 0)
+            .accountsPartial({ pool, tickGrid: tickGrid(), authority: user, incoLightningProgram: INCO_LIGHTNING_ID })
+            .rpc();
+    });
+
+    it("Starts the grid with a price and its inverse", async () => {
+        const grid = await fetchGrid();
+        expect(handleOf(grid.sqrtPrice)).to.not.equal(0n);
+        expect(handleOf(grid.inverseSqrtPrice)).to.not.equal(0n);
+        expect(grid.ticks).to.be.empty;
+    });
+
+    it("Opens a range position without dividing", async () => {
+        const signature = await amm.methods
+            .openRangePosition(TICK_LOWER, TICK_UPPER, handleBn(1), handleBn(2), handleBn(3), await deadline())
+            .accountsPartial(rangeAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const grid = await fetchGrid();
+        expect(grid.ticks).to.deep.equal([TICK_LOWER, TICK_UPPER]);
+        const position = await (amm.account as any).rangePosition.fetch(rangePosition());
+        expect(position.owner.toBase58()).to.equal(user.toBase58());
+    });
+
+    it("Rejects a concentrated swap without its four hints", async () => {
+        try {
+            await amm.methods
+                .swap(handleBn(5), handleBn(6), [handleBn(7)], new BN(0), await deadline())
+                .accountsPartial({ ...swapAccounts(amm, incoToken, pool, user, mintA, mintB, 0), tickGrid: tickGrid() })
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("concentrated swap with one hint should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+
+    it("Closes the range position", async () => {
+        const signature = await amm.methods
+            .closeRangePosition(await deadline())
+            .accountsPartial(rangeAccounts())
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+        expect(await provider.connection.getAccountInfo(rangePosition())).to.be.null;
+    });
+});
@@ -0,0 +1,169 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    positionPda,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm liquidity mining (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    const endTime = () => new BN(Math.floor(Date.now() / 1000) + 3600);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let rewardMint: PublicKey;
+    let pool: PublicKey;
+    let rewards: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+    const fetchRewards = () => (amm.account as any).rewardVault.fetch(rewards);
+    const authorityAccounts = () => ({ pool, rewards, authority: user, incoLightningProgram: INCO_LIGHTNING_ID });
+    const keeper = Keypair.generate();
+    const syncAccounts = () => ({ pool, rewards, caller: keeper.publicKey, incoLightningProgram: INCO_LIGHTNING_ID });
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 5000);
+        [rewardMint] = await createFundedMintPair(incoToken, user, 5100);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+        rewards = PublicKey.findProgramAddressSync([Buffer.from("rewards"), pool.toBuffer()], amm.programId)[0];
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+
+        await amm.methods
+            .initializeRewards(new BN(10), endTime())
+            .accountsPartial({
+                pool,
+                rewards,
+                rewardMint,
+                rewardVault: balancePda(incoToken, rewards, rewardMint),
+                authority: user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+        await amm.methods
+            .fundRewards(handleBn(51))
+            .accountsPartial({
+                pool,
+                rewards,
+                rewardMint,
+                funderBalance: balancePda(incoToken, user, rewardMint),
+                rewardVault: balancePda(incoToken, rewards, rewardMint),
+                authority: user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+        await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(keeper.publicKey, 1_000_000_000),
+            "confirmed"
+        );
+    });
+
+    it("Holds emission changes until the LP supply is synced", async () => {
+        try {
+            await amm.methods.setRewardEmission(new BN(20), endTime()).accountsPartial(authorityAccounts()).rpc();
+            expect.fail("a stale inverse supply should block emission changes");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("RewardSupplyNotSynced");
+        }
+    });
+
+    it("Lets a third party sync the staked supply against a hinted inverse", async () => {
+        const signature = await amm.methods
+            .syncRewardSupply(handleBn(52))
+            .accountsPartial(syncAccounts())
+            .signers([keeper])
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const state = await fetchRewards();
+        const poolState = await fetchPool();
+        expect(handleOf(state.syncedLpSupply)).to.equal(handleOf(poolState.lpSupply));
+        expect(handleOf(state.syncedTokenizedLpSupply)).to.equal(handleOf(poolState.tokenizedLpSupply));
+        await amm.methods.setRewardEmission(new BN(20), endTime()).accountsPartial(authorityAccounts()).rpc();
+    });
+
+    it("Keeps a synced inverse when a resync hint fails", async () => {
+        const before = await fetchRewards();
+        await amm.methods
+            .syncRewardSupply(handleBn(0))
+            .accountsPartial(syncAccounts())
+            .signers([keeper])
+            .preInstructions(computeBudget)
+            .rpc();
+
+        // The inverse is re-selected, so only its handle changes
+        const after = await fetchRewards();
+        expect(handleOf(after.inverseLpSupply)).to.not.equal(0n);
+        expect(handleOf(after.syncedLpSupply)).to.equal(handleOf(before.syncedLpSupply));
+        expect(after.lastUpdateTime.toNumber()).to.be.at.least(before.lastUpdateTime.toNumber());
+    });
+
+    it("Accrues at the old supply and goes stale when a deposit changes it", async () => {
+        const before = await fetchRewards();
+        const signature = await amm.methods
+            .addLiquidity(handleBn(11), handleBn(12), handleBn(13), handleBn(14), new BN(0), false, await deadline())
+            .accountsPartial({ ...liquidityAccounts(amm, incoToken, pool, user, mintA, mintB), rewards })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const state = await fetchRewards();
+        expect(handleOf(state.syncedLpSupply)).to.not.equal(handleOf((await fetchPool()).lpSupply));
+        expect(state.lastUpdateTime.toNumber()).to.be.at.least(before.lastUpdateTime.toNumber());
+    });
+
+    it("Starts the held window at the deposit, not the previous sync", async () => {
+        const stale = await fetchRewards();
+        await amm.methods
+            .syncRewardSupply(handleBn(53))
+            .accountsPartial(syncAccounts())
+            .signers([keeper])
+            .preInstructions(computeBudget)
+            .rpc();
+        const synced = await fetchRewards();
+        expect(handleOf(synced.syncedLpSupply)).to.equal(handleOf((await fetchPool()).lpSupply));
+        expect(synced.lastUpdateTime.toNumber()).to.be.at.least(stale.lastUpdateTime.toNumber());
+    });
+
+    it("Claims accrued rewards after a resync", async () => {
+        const signature = await amm.methods
+            .claimRewards()
+            .accountsPartial({
+                pool,
+                rewards,
+                userPosition: positionPda(amm, pool, user),
+                rewardMint,
+                userRewardBalance: balancePda(incoToken, user, rewardMint),
+                rewardVault: balancePda(incoToken, rewards, rewardMint),
+                user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+    });
+});
@@ -0,0 +1,157 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { AccountMeta, ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    PoolType,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm multi-hop routing (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+
+    // Route: input -> middle -> output, through one pool per hop
+    // (output -> extra runs through a StableSwap pool, which routes reject)
+    let input: PublicKey;
+    let middle: PublicKey;
+    let output: PublicKey;
+    let extra: PublicKey;
+    let firstPool: PublicKey;
+    let secondPool: PublicKey;
+    let stablePool: PublicKey;
+
+    const fetchPool = (pool: PublicKey) => (amm.account as any).privatePool.fetch(pool);
+
+    // Canonical (ascending) mint order, as pools require
+    const ordered = (x: PublicKey, y: PublicKey): [PublicKey, PublicKey] =>
+        Buffer.compare(x.toBuffer(), y.toBuffer()) < 0 ? [x, y] : [y, x];
+
+    const seededPool = async (x: PublicKey, y: PublicKey) => {
+        const [mintA, mintB] = ordered(x, y);
+        const pool = await createPool(amm, incoToken, user, mintA, mintB);
+        await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        return pool;
+    };
+
+    // ROUTE_HOP_ACCOUNTS per hop: [pool, output mint, input vault, output vault]
+    const hopAccounts = (pool: PublicKey, mintIn: PublicKey, mintOut: PublicKey): AccountMeta[] => [
+        { pubkey: pool, isSigner: false, isWritable: true },
+        { pubkey: mintOut, isSigner: false, isWritable: false },
+        { pubkey: balancePda(incoToken, pool, mintIn), isSigner: false, isWritable: true },
+        { pubkey: balancePda(incoToken, pool, mintOut), isSigner: false, isWritable: true },
+    ];
+
+    const routeThrough = async (hops: AccountMeta[], hints: BN[], inputMint: PublicKey, outputMint: PublicKey) =>
+        amm.methods
+            .swapRoute(handleBn(71), handleBn(72), hints, await deadline())
+            .accountsPartial({
+                inputMint,
+                outputMint,
+                userBalanceIn: balancePda(incoToken, user, inputMint),
+                userBalanceOut: balancePda(incoToken, user, outputMint),
+                user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .remainingAccounts(hops)
+            .preInstructions(computeBudget)
+            .rpc();
+
+    const route = (hints: BN[], outputMint: PublicKey) =>
+        routeThrough(
+            [...hopAccounts(firstPool, input, middle), ...hopAccounts(secondPool, middle, output)],
+            hints,
+            input,
+            outputMint
+        );
+
+    before(async () => {
+        [input, middle] = await createFundedMintPair(incoToken, user, 8000);
+        [output, extra] = await createFundedMintPair(incoToken, user, 8100);
+        firstPool = await seededPool(input, middle);
+        secondPool = await seededPool(middle, output);
+        stablePool = await createPool(amm, incoToken, user, output, extra, PoolType.stableSwap, 100);
+    });
+
+    it("Swaps through two pools within the trace budget", async () => {
+        const before = [await fetchPool(firstPool), await fetchPool(secondPool)];
+        const signature = await route([handleBn(73), handleBn(74)], output);
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = [await fetchPool(firstPool), await fetchPool(secondPool)];
+        for (const [i, state] of after.entries()) {
+            expect(handleOf(state.reserveA)).to.not.equal(handleOf(before[i].reserveA));
+            expect(handleOf(state.reserveB)).to.not.equal(handleOf(before[i].reserveB));
+        }
+    });
+
+    it("Rejects a route missing a hop's hint", async () => {
+        try {
+            await route([handleBn(73)], output);
+            expect.fail("each hop needs its own hint");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+
+    it("Rejects a route that ends in another mint", async () => {
+        try {
+            await route([handleBn(73), handleBn(74)], middle);
+            expect.fail("the last hop must pay out the requested mint");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidRoute");
+        }
+    });
+
+    it("Rejects a third hop", async () => {
+        try {
+            await routeThrough(
+                [
+                    ...hopAccounts(firstPool, input, middle),
+                    ...hopAccounts(secondPool, middle, output),
+                    ...hopAccounts(stablePool, output, extra),
+                ],
+                [handleBn(73), handleBn(74), handleBn(75)],
+                input,
+                extra
+            );
+            expect.fail("routes are capped at MAX_ROUTE_HOPS");
+        } catch (err: any) {
+            // The hop count is checked before any pool is loaded
+            expect(err.toString()).to.contain("InvalidRoute");
+        }
+    });
+
+    it("Rejects a hop through a StableSwap pool", async () => {
+        try {
+            await routeThrough(
+                hopAccounts(stablePool, output, extra),
+                [handleBn(73), handleBn(74), handleBn(75)],
+                output,
+                extra
+            );
+            expect.fail("only constant-product pools can be routed through");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("UnsupportedRouteHop");
+        }
+    });
+});
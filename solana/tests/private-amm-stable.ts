@@ -0,0 +1,127 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    PoolType,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    swapAccounts,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm StableSwap invariant (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    const stableHints = [handleBn(21), handleBn(22), handleBn(23)];
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 2000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB, PoolType.stableSwap, 100);
+        await amm.methods
+            // A StableSwap seed is balanced, which fixes D = amount_a + amount_b
+            .addLiquidity(handleBn(1), handleBn(1), handleBn(3), handleBn(4), new BN(0), true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Opens swaps at the seed without a sync", async () => {
+        const seeded = await fetchPool();
+        expect(handleOf(seeded.invariantAnn)).to.not.equal(0n);
+        expect(handleOf(seeded.kConstant)).to.not.equal(0n);
+
+        const signature = await amm.methods
+            .swap(handleBn(5), handleBn(6), stableHints, new BN(0), await deadline())
+            .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintA, mintB, 0))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+    });
+
+    it("Syncs the invariant against hinted bounds", async () => {
+        const signature = await amm.methods
+            .syncInvariant(handleBn(30), [handleBn(31), handleBn(32), handleBn(33), handleBn(34)])
+            .accountsPartial({ pool, caller: user, incoLightningProgram: INCO_LIGHTNING_ID })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const state = await fetchPool();
+        expect(handleOf(state.invariantAnn)).to.not.equal(0n);
+    });
+
+    it("Rejects a sync with the wrong number of hints", async () => {
+        try {
+            await amm.methods
+                .syncInvariant(handleBn(30), [handleBn(31), handleBn(32)])
+                .accountsPartial({ pool, caller: user, incoLightningProgram: INCO_LIGHTNING_ID })
+                .rpc();
+            expect.fail("sync without its four hints should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+
+    it("Swaps against the synced invariant and banks the LP fee", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .swap(handleBn(5), handleBn(6), stableHints, new BN(1), await deadline())
+            .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintA, mintB, 1))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.lpFeesA)).to.not.equal(0n);
+        // Swaps never move D
+        expect(handleOf(after.kConstant)).to.equal(handleOf(before.kConstant));
+    });
+
+    it("Keeps swapping after a withdrawal without a sync", async () => {
+        const before = await fetchPool();
+        await amm.methods
+            .removeLiquidity(handleBn(16), handleBn(17), await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        const after = await fetchPool();
+        expect(handleOf(after.kConstant)).to.not.equal(handleOf(before.kConstant));
+
+        await amm.methods
+            .swap(handleBn(5), handleBn(6), stableHints, new BN(2), await deadline())
+            .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintA, mintB, 2))
+            .preInstructions(computeBudget)
+            .rpc();
+    });
+
+    it("Grows the invariant with a proportional deposit", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .addLiquidity(handleBn(11), handleBn(12), handleBn(13), handleBn(14), handleBn(15), false, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.kConstant)).to.not.equal(handleOf(before.kConstant));
+    });
+});
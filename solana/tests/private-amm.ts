@@ -0,0 +1,168 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { ComputeBudgetProgram, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INCO_LIGHTNING_ID, handleOf } from "./utils/inco";
+import {
+    MAX_TRACE_ENTRIES,
+    balancePda,
+    createFundedMintPair,
+    createPool,
+    handleBn,
+    liquidityAccounts,
+    swapAccounts,
+    swapReceiptPda,
+    traceEntries,
+} from "./utils/amm";
+
+describe("private-amm swap lifecycle (requires Inco Lightning)", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const amm = anchor.workspace.PrivateAmm as Program;
+    const incoToken = anchor.workspace.IncoToken as Program;
+
+    // Must match private_amm::MINIMUM_LIQUIDITY
+    const MINIMUM_LIQUIDITY = 1000;
+
+    const user = provider.wallet.publicKey;
+    const computeBudget = [ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })];
+    const deadline = async () => new BN((await provider.connection.getSlot()) + 150);
+    // Only StableSwap deposits read the invariant hint
+    const noInvariantHint = new BN(0);
+
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let pool: PublicKey;
+
+    const fetchPool = () => (amm.account as any).privatePool.fetch(pool);
+
+    before(async () => {
+        [mintA, mintB] = await createFundedMintPair(incoToken, user, 1000);
+        pool = await createPool(amm, incoToken, user, mintA, mintB);
+    });
+
+    it("Rejects a proportional deposit into an unseeded pool", async () => {
+        try {
+            await amm.methods
+                .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), noInvariantHint, false, await deadline())
+                .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("deposit into an empty pool should need seedPool");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InsufficientLiquidity");
+        }
+    });
+
+    it("Seeds the pool with a hinted square root", async () => {
+        // Hints are stand-in handles here: the program only checks them under
+        // encryption, so this exercises the full instruction path and its budget
+        const signature = await amm.methods
+            .addLiquidity(handleBn(1), handleBn(2), handleBn(3), handleBn(4), noInvariantHint, true, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const state = await fetchPool();
+        expect(handleOf(state.lpSupply)).to.not.equal(0n);
+        expect(handleOf(state.kConstant)).to.not.equal(0n);
+        expect(state.lockedLiquidity.toNumber()).to.equal(MINIMUM_LIQUIDITY);
+    });
+
+    it("Adds proportional liquidity against a hinted quotient", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .addLiquidity(handleBn(11), handleBn(12), handleBn(13), handleBn(14), noInvariantHint, false, await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.lpSupply)).to.not.equal(handleOf(before.lpSupply));
+    });
+
+    it("Swaps against a hinted output and claims it", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .swap(handleBn(5), handleBn(6), [handleBn(7)], new BN(0), await deadline())
+            .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintA, mintB, 0))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.reserveA)).to.not.equal(handleOf(before.reserveA));
+        expect(handleOf(after.reserveB)).to.not.equal(handleOf(before.reserveB));
+        const receipt = await (amm.account as any).swapReceipt.fetch(swapReceiptPda(amm, pool, user, 0));
+        expect(receipt.aToB).to.be.true;
+
+        await amm.methods
+            .claimSwapOutput()
+            .accountsPartial({
+                pool,
+                swapReceipt: swapReceiptPda(amm, pool, user, 0),
+                outputMint: mintB,
+                userBalanceOut: balancePda(incoToken, user, mintB),
+                vaultOut: balancePda(incoToken, pool, mintB),
+                user,
+                incoTokenProgram: incoToken.programId,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await provider.connection.getAccountInfo(swapReceiptPda(amm, pool, user, 0))).to.be.null;
+    });
+
+    it("Rejects a swap with the wrong number of hints", async () => {
+        try {
+            await amm.methods
+                .swap(handleBn(5), handleBn(6), [], new BN(1), await deadline())
+                .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintB, mintA, 1))
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("swap without its hint should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("InvalidHints");
+        }
+    });
+
+    it("Rejects swaps and withdrawals that land after their deadline", async () => {
+        const expired = new BN((await provider.connection.getSlot()) - 1);
+        try {
+            await amm.methods
+                .swap(handleBn(5), handleBn(6), [handleBn(7)], new BN(1), expired)
+                .accountsPartial(swapAccounts(amm, incoToken, pool, user, mintB, mintA, 1))
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("an expired swap should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("DeadlineExceeded");
+        }
+        try {
+            await amm.methods
+                .removeLiquidity(handleBn(8), handleBn(9), expired)
+                .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+                .preInstructions(computeBudget)
+                .rpc();
+            expect.fail("an expired withdrawal should fail");
+        } catch (err: any) {
+            expect(err.toString()).to.contain("DeadlineExceeded");
+        }
+    });
+
+    it("Removes liquidity against a hinted share", async () => {
+        const before = await fetchPool();
+        const signature = await amm.methods
+            .removeLiquidity(handleBn(8), handleBn(9), await deadline())
+            .accountsPartial(liquidityAccounts(amm, incoToken, pool, user, mintA, mintB))
+            .preInstructions(computeBudget)
+            .rpc();
+        expect(await traceEntries(provider, signature)).to.be.at.most(MAX_TRACE_ENTRIES);
+
+        const after = await fetchPool();
+        expect(handleOf(after.lpSupply)).to.not.equal(handleOf(before.lpSupply));
+    });
+});
@@ -0,0 +1,200 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { INCO_LIGHTNING_ID, testHandle } from "./inco";
+
+// Must match private_amm::PoolType
+export const PoolType = {
+    constantProduct: { constantProduct: {} },
+    stableSwap: { stableSwap: {} },
+    oracle: { oracle: {} },
+    concentrated: { concentrated: {} },
+};
+
+// Solana caps a transaction at 64 instruction-trace entries, top level included
+export const MAX_TRACE_ENTRIES = 64;
+
+export const handleBn = (seed: number) => new BN(testHandle(seed).toString());
+
+// Must match private_amm::REGISTRY_PAGE_SIZE
+export const REGISTRY_PAGE_SIZE = 32;
+
+export const factoryPda = (amm: Program) =>
+    PublicKey.findProgramAddressSync([Buffer.from("factory")], amm.programId)[0];
+
+// Registry page the next pool is appended to
+export const registryPagePda = (amm: Program, poolCount: BN) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("registry"), poolCount.divn(REGISTRY_PAGE_SIZE).toArrayLike(Buffer, "le", 8)],
+        amm.programId
+    )[0];
+
+export const poolPda = (amm: Program, mintA: PublicKey, mintB: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer()], amm.programId)[0];
+
+export const balancePda = (incoToken: Program, owner: PublicKey, mint: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("user_balance"), owner.toBuffer(), mint.toBuffer()],
+        incoToken.programId
+    )[0];
+
+export const positionPda = (amm: Program, pool: PublicKey, owner: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("position"), pool.toBuffer(), owner.toBuffer()], amm.programId)[0];
+
+export const limitOrderPda = (amm: Program, pool: PublicKey, owner: PublicKey, nonce: number) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("limit_order"), pool.toBuffer(), owner.toBuffer(), new BN(nonce).toArrayLike(Buffer, "le", 8)],
+        amm.programId
+    )[0];
+
+// Must match private_amm::BATCH_EPOCH_SLOTS
+export const BATCH_EPOCH_SLOTS = 150;
+
+export const batchPda = (amm: Program, pool: PublicKey, epoch: number) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("batch"), pool.toBuffer(), new BN(epoch).toArrayLike(Buffer, "le", 8)],
+        amm.programId
+    )[0];
+
+export const swapReceiptPda = (amm: Program, pool: PublicKey, owner: PublicKey, nonce: number) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("swap_receipt"), pool.toBuffer(), owner.toBuffer(), new BN(nonce).toArrayLike(Buffer, "le", 8)],
+        amm.programId
+    )[0];
+
+// Accounts shared by add_liquidity and remove_liquidity
+export const liquidityAccounts = (
+    amm: Program,
+    incoToken: Program,
+    pool: PublicKey,
+    user: PublicKey,
+    mintA: PublicKey,
+    mintB: PublicKey
+) => ({
+    pool,
+    userPosition: positionPda(amm, pool, user),
+    rewards: null,
+    tokenAMint: mintA,
+    tokenBMint: mintB,
+    userBalanceA: balancePda(incoToken, user, mintA),
+    userBalanceB: balancePda(incoToken, user, mintB),
+    vaultA: balancePda(incoToken, pool, mintA),
+    vaultB: balancePda(incoToken, pool, mintB),
+    user,
+    incoTokenProgram: incoToken.programId,
+    incoLightningProgram: INCO_LIGHTNING_ID,
+});
+
+// Accounts for a swap selling `inputMint` for `outputMint`
+export const swapAccounts = (
+    amm: Program,
+    incoToken: Program,
+    pool: PublicKey,
+    user: PublicKey,
+    inputMint: PublicKey,
+    outputMint: PublicKey,
+    nonce: number
+) => ({
+    pool,
+    swapReceipt: swapReceiptPda(amm, pool, user, nonce),
+    inputMint,
+    outputMint,
+    userBalanceIn: balancePda(incoToken, user, inputMint),
+    vaultIn: balancePda(incoToken, pool, inputMint),
+    oracle: null,
+    tickGrid: null,
+    user,
+    incoTokenProgram: incoToken.programId,
+    incoLightningProgram: INCO_LIGHTNING_ID,
+});
+
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+// The upgradeable loader's data account for a program
+export const programDataPda = (program: Program) =>
+    PublicKey.findProgramAddressSync([program.programId.toBuffer()], BPF_LOADER_UPGRADEABLE_ID)[0];
+
+// The factory is a singleton PDA, so test files share whichever one ran first;
+// only the program's upgrade authority (the provider wallet) may create it
+export const ensureFactory = async (amm: Program, admin: PublicKey) => {
+    const factory = factoryPda(amm);
+    const existing = await (amm.account as any).factory.fetchNullable(factory);
+    if (!existing) {
+        await amm.methods
+            .initializeFactory(30, new BN(0))
+            .accountsPartial({ admin, program: amm.programId, programData: programDataPda(amm), factory })
+            .rpc();
+    }
+    return (amm.account as any).factory.fetch(factory);
+};
+
+// Two confidential mints in canonical (ascending) order, each funded for `owner`
+export const createFundedMintPair = async (
+    incoToken: Program,
+    owner: PublicKey,
+    seed: number
+): Promise<[PublicKey, PublicKey]> => {
+    const mints = [Keypair.generate(), Keypair.generate()].sort((x, y) =>
+        Buffer.compare(x.publicKey.toBuffer(), y.publicKey.toBuffer())
+    );
+    for (const [i, mint] of mints.entries()) {
+        await incoToken.methods
+            .initializeMint(6, owner, null)
+            .accounts({ mint: mint.publicKey, payer: owner })
+            .signers([mint])
+            .rpc();
+        await incoToken.methods
+            .initializeUserBalance()
+            .accounts({ mint: mint.publicKey, user: owner })
+            .rpc();
+        await incoToken.methods
+            .mintToUser(handleBn(seed + i))
+            .accountsPartial({
+                userBalance: balancePda(incoToken, owner, mint.publicKey),
+                mint: mint.publicKey,
+                mintAuthority: owner,
+                incoLightningProgram: INCO_LIGHTNING_ID,
+            })
+            .rpc();
+    }
+    return [mints[0].publicKey, mints[1].publicKey];
+};
+
+export const createPool = async (
+    amm: Program,
+    incoToken: Program,
+    authority: PublicKey,
+    mintA: PublicKey,
+    mintB: PublicKey,
+    poolType = PoolType.constantProduct,
+    amp = 0
+) => {
+    const factory = await ensureFactory(amm, authority);
+    const pool = poolPda(amm, mintA, mintB);
+    await amm.methods
+        .initializePool(null, poolType, new BN(amp))
+        .accountsPartial({
+            pool,
+            factory: factoryPda(amm),
+            registryPage: registryPagePda(amm, factory.poolCount),
+            feeCollector: factory.admin,
+            tokenAMint: mintA,
+            tokenBMint: mintB,
+            vaultA: balancePda(incoToken, pool, mintA),
+            vaultB: balancePda(incoToken, pool, mintB),
+            authority,
+            incoTokenProgram: incoToken.programId,
+            incoLightningProgram: INCO_LIGHTNING_ID,
+        })
+        .rpc();
+    return pool;
+};
+
+// Instruction-trace entries a confirmed transaction used (one log line per invocation)
+export const traceEntries = async (provider: anchor.AnchorProvider, signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+    });
+    return (tx?.meta?.logMessages ?? []).filter((line) => / invoke \[\d+\]$/.test(line)).length;
+};
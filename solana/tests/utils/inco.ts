@@ -0,0 +1,44 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { PublicKey, Transaction } from "@solana/web3.js";
+
+// Inco Lightning Program ID (cloned from devnet by the localnet validator)
+export const INCO_LIGHTNING_ID = new PublicKey("5sjEbPiqgZrYwR31ahR6Uk9wf5awoX61YGg7jExQSwaj");
+
+export const u128Le = (value: bigint) => {
+    const bytes = Buffer.alloc(16);
+    for (let i = 0; i < 16; i++) {
+        bytes[i] = Number((value >> BigInt(i * 8)) & BigInt(0xff));
+    }
+    return bytes;
+};
+
+// Stand-in input handle for tests; Inco Lightning only rejects the zero handle
+export const testHandle = (seed: number) => BigInt(seed) * BigInt("0x1000000000000000100000001");
+
+// Euint128 / Ebool decode as a single-field tuple struct
+export const handleOf = (value: any): bigint => BigInt((value[0] ?? value).toString());
+
+// Inco allowance PDA granting `allowed` decryption access to `handle`
+export const allowancePda = (handle: bigint, allowed: PublicKey) =>
+    PublicKey.findProgramAddressSync([u128Le(handle), allowed.toBuffer()], INCO_LIGHTNING_ID)[0];
+
+// Simulate a transaction and decode the post-state of `addresses` as `accountName`,
+// so tests can learn handles created on-chain before passing their allowance PDAs
+export const simulateAccounts = async (
+    provider: anchor.AnchorProvider,
+    program: Program,
+    tx: Transaction,
+    accountName: string,
+    addresses: PublicKey[]
+): Promise<any[]> => {
+    tx.feePayer = provider.wallet.publicKey;
+    tx.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+    const { value } = await provider.connection.simulateTransaction(tx, undefined, addresses);
+    if (value.err) {
+        throw new Error(`simulation failed: ${JSON.stringify(value.err)}\n${value.logs?.join("\n")}`);
+    }
+    return value.accounts!.map((account) =>
+        program.coder.accounts.decode(accountName, Buffer.from(account!.data[0], "base64"))
+    );
+};